@@ -0,0 +1,241 @@
+//! Python core metadata (`METADATA` / `PKG-INFO`).
+//!
+//! Core metadata is an RFC 822-style header block: one `Field: value` per line, with
+//! continuation lines indented, and an optional message body holding the long
+//! description. A wheel carries it as `{name}-{version}.dist-info/METADATA`; it is the
+//! authoritative record of the distribution's name and version.
+//!
+//! See <https://packaging.python.org/en/latest/specifications/core-metadata/>.
+
+use serde::{Deserialize, Serialize};
+
+use crate::zip::ZipArchive;
+
+/// The fields of a distribution's core metadata the plugin keeps.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CoreMetadata {
+    pub(crate) metadata_version: String,
+    pub(crate) name: String,
+    pub(crate) version: String,
+    #[serde(default)]
+    pub(crate) summary: Option<String>,
+    #[serde(default)]
+    pub(crate) requires_python: Option<String>,
+    #[serde(default)]
+    pub(crate) requires_dist: Vec<String>,
+    #[serde(default)]
+    pub(crate) provides_extra: Vec<String>,
+    /// `License-Expression` when present (metadata 2.4), otherwise `License`.
+    #[serde(default)]
+    pub(crate) license: Option<String>,
+    #[serde(default)]
+    pub(crate) classifiers: Vec<String>,
+}
+
+/// Parse a core metadata document.
+pub(crate) fn parse_core_metadata(data: &[u8]) -> Result<CoreMetadata, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid METADATA: not UTF-8".to_string())?;
+
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            // The headers end at the first blank line; the body is the description
+            break;
+        }
+        if line.starts_with([' ', '\t']) {
+            let (_, value) = fields
+                .last_mut()
+                .ok_or("Invalid METADATA: continuation line before any field")?;
+            value.push('\n');
+            value.push_str(line.trim());
+            continue;
+        }
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid METADATA line: '{line}'"))?;
+        fields.push((key.trim(), value.trim().to_string()));
+    }
+
+    let first = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+    let all = |key: &str| -> Vec<String> {
+        fields
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.clone())
+            .collect()
+    };
+    let required = |key: &str| first(key).ok_or_else(|| format!("METADATA is missing {key}"));
+
+    let metadata = CoreMetadata {
+        metadata_version: required("Metadata-Version")?,
+        name: required("Name")?,
+        version: required("Version")?,
+        summary: first("Summary"),
+        requires_python: first("Requires-Python"),
+        requires_dist: all("Requires-Dist"),
+        provides_extra: all("Provides-Extra"),
+        license: first("License-Expression").or_else(|| first("License")),
+        classifiers: all("Classifier"),
+    };
+
+    if !is_valid_name(&metadata.name) {
+        return Err(format!(
+            "Invalid project name '{}' in METADATA",
+            metadata.name
+        ));
+    }
+    if !is_valid_version(&metadata.version) {
+        return Err(format!(
+            "Invalid version '{}' in METADATA",
+            metadata.version
+        ));
+    }
+    Ok(metadata)
+}
+
+/// Read the `METADATA` file out of a wheel, unparsed.
+///
+/// The `.dist-info` directory is found from the archive itself rather than the
+/// filename, so a wheel whose filename disagrees with its metadata can be reported.
+pub(crate) fn wheel_metadata_file(data: &[u8]) -> Result<Vec<u8>, String> {
+    let archive = ZipArchive::parse(data).map_err(|e| format!("Invalid wheel: {e}"))?;
+    let mut candidates = archive.names().filter(|name| {
+        name.strip_suffix(".dist-info/METADATA")
+            .is_some_and(|dir| !dir.is_empty() && !dir.contains('/'))
+    });
+    let path = match (candidates.next(), candidates.next()) {
+        (Some(path), None) => path.to_string(),
+        (None, _) => return Err("Invalid wheel: no .dist-info/METADATA file".to_string()),
+        (Some(_), Some(_)) => {
+            return Err("Invalid wheel: more than one .dist-info directory".to_string())
+        }
+    };
+    archive
+        .read(&path)
+        .map_err(|e| format!("Invalid wheel: {e}"))?
+        .ok_or_else(|| "Invalid wheel: no .dist-info/METADATA file".to_string())
+}
+
+/// A valid project name per PEP 508: ASCII letters, digits, `.`, `_`, and `-`, starting
+/// and ending with a letter or digit.
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    !bytes.is_empty()
+        && bytes[0].is_ascii_alphanumeric()
+        && bytes[bytes.len() - 1].is_ascii_alphanumeric()
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// A version that's safe to use as a path segment: non-empty, without whitespace,
+/// slashes, or `..`. This is looser than PEP 440, so legacy versions still pass.
+pub(crate) fn is_valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && !version.contains(char::is_whitespace)
+        && !version.contains(['/', '\\'])
+        && !version.contains("..")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip::testing::build_zip;
+
+    const METADATA: &str = "\
+Metadata-Version: 2.1
+Name: requests
+Version: 2.28.0
+Summary: Python HTTP for Humans.
+License: Apache 2.0
+Requires-Python: >=3.7, <4
+Requires-Dist: charset-normalizer (<3,>=2)
+Requires-Dist: PySocks (!=1.5.7,>=1.5.6) ; extra == 'socks'
+Provides-Extra: socks
+Classifier: Programming Language :: Python :: 3
+Classifier: License :: OSI Approved :: Apache Software License
+Description-Content-Type: text/markdown
+
+# Requests
+
+Name: not a header
+";
+
+    #[test]
+    fn parses_core_metadata() {
+        let metadata = parse_core_metadata(METADATA.as_bytes()).unwrap();
+        assert_eq!(metadata.metadata_version, "2.1");
+        assert_eq!(metadata.name, "requests");
+        assert_eq!(metadata.version, "2.28.0");
+        assert_eq!(metadata.summary.as_deref(), Some("Python HTTP for Humans."));
+        assert_eq!(metadata.requires_python.as_deref(), Some(">=3.7, <4"));
+        assert_eq!(metadata.requires_dist.len(), 2);
+        assert_eq!(metadata.provides_extra, ["socks"]);
+        assert_eq!(metadata.license.as_deref(), Some("Apache 2.0"));
+        assert_eq!(metadata.classifiers.len(), 2);
+    }
+
+    #[test]
+    fn joins_continuation_lines_and_prefers_license_expression() {
+        let text = "Metadata-Version: 2.4\nName: pkg\nVersion: 1.0\n\
+                    License: Permission is hereby granted,\n        free of charge\n\
+                    License-Expression: MIT OR Apache-2.0\n";
+        let metadata = parse_core_metadata(text.as_bytes()).unwrap();
+        assert_eq!(metadata.license.as_deref(), Some("MIT OR Apache-2.0"));
+
+        let text = text.replace("License-Expression: MIT OR Apache-2.0\n", "");
+        let metadata = parse_core_metadata(text.as_bytes()).unwrap();
+        assert_eq!(
+            metadata.license.as_deref(),
+            Some("Permission is hereby granted,\nfree of charge")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_metadata() {
+        let cases = [
+            ("Metadata-Version: 2.1\nVersion: 1.0\n", "missing Name"),
+            ("Metadata-Version: 2.1\nName: pkg\n", "missing Version"),
+            ("Name: pkg\nVersion: 1.0\n", "missing Metadata-Version"),
+            (
+                "Metadata-Version: 2.1\nName: -pkg\nVersion: 1.0\n",
+                "Invalid project name",
+            ),
+            (
+                "Metadata-Version: 2.1\nName: pkg\nVersion: 1 0\n",
+                "Invalid version",
+            ),
+            (
+                "Metadata-Version: 2.1\nName: pkg\nVersion: ../1.0\n",
+                "Invalid version",
+            ),
+            ("  indented\n", "continuation line"),
+            ("no colon here\n", "Invalid METADATA line"),
+        ];
+        for (text, expected) in cases {
+            let err = parse_core_metadata(text.as_bytes()).unwrap_err();
+            assert!(err.contains(expected), "{text}: {err}");
+        }
+    }
+
+    #[test]
+    fn reads_metadata_file_from_wheel() {
+        let wheel = build_zip(&[
+            ("requests/__init__.py", b""),
+            ("requests-2.28.0.dist-info/METADATA", METADATA.as_bytes()),
+            ("requests-2.28.0.dist-info/RECORD", b""),
+        ]);
+        assert_eq!(wheel_metadata_file(&wheel).unwrap(), METADATA.as_bytes());
+
+        let err = wheel_metadata_file(&build_zip(&[("requests/__init__.py", b"")])).unwrap_err();
+        assert!(err.contains("no .dist-info/METADATA"), "{err}");
+        let err = wheel_metadata_file(b"PK\x03\x04").unwrap_err();
+        assert!(err.contains("Invalid wheel"), "{err}");
+    }
+}
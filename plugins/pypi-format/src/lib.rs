@@ -4,6 +4,31 @@
 //! This plugin demonstrates filename convention parsing following PEP 427 (wheels)
 //! and PEP 503 (Simple Repository API) standards.
 //!
+//! A wheel's name and version are read from its core metadata
+//! (`{name}-{version}.dist-info/METADATA`). Uploads whose filename disagrees with the
+//! metadata are rejected, and the metadata is stored as an artifact property so the
+//! index can list summaries, Python requirements, and dependencies. Source
+//! distributions are identified by filename alone.
+//!
+//! The simple pages are also served as PEP 691 JSON: `Accept` selects between
+//! `application/vnd.pypi.simple.v1+json`, `application/vnd.pypi.simple.v1+html`, and
+//! `text/html`, and a request accepting none of them gets a 406.
+//!
+//! Project pages carry each file's `Requires-Python` as `data-requires-python` (or
+//! `requires-python` in JSON), read from a wheel's METADATA or an sdist's `PKG-INFO`.
+//!
+//! A release or individual files are yanked (PEP 592) by uploading a `*.yank.json`
+//! document; project pages then mark them with `data-yanked` (or `yanked` in JSON), and
+//! they can still be downloaded.
+//!
+//! With the `upload-handler` interface, `POST /` accepts `twine upload` (the legacy
+//! upload API): the form is checked, including its `sha256_digest`, and the file handed
+//! to the host to store like any other upload.
+//!
+//! Each wheel's `METADATA` is also kept as uploaded and served at
+//! `/packages/{filename}.metadata` (PEP 658); project pages advertise it with its
+//! SHA-256 (`data-core-metadata`, PEP 714) so resolvers needn't download the wheel.
+//!
 //! ## Wheel filename convention (PEP 427)
 //!
 //! ```text
@@ -22,14 +47,47 @@
 //! ```
 
 wit_bindgen::generate!({
-    world: "format-plugin-v2",
+    world: "format-plugin-v4",
     path: "../../wit/format-plugin.wit",
 });
 
+use exports::artifact_keeper::format::artifact_properties::{
+    Artifact, Guest as ArtifactPropertiesGuest,
+};
 use exports::artifact_keeper::format::handler::{Guest as HandlerGuest, Metadata};
 use exports::artifact_keeper::format::request_handler::{
     Guest as RequestHandlerGuest, HttpRequest, HttpResponse, RepoContext,
 };
+use exports::artifact_keeper::format::upload_handler::{
+    Guest as UploadHandlerGuest, Upload, UploadResult,
+};
+
+mod core_metadata;
+mod multipart;
+mod negotiate;
+mod sdist;
+mod upload;
+mod version;
+mod yank;
+mod zip;
+
+use core_metadata::{is_valid_version, parse_core_metadata, wheel_metadata_file, CoreMetadata};
+use negotiate::{negotiate, SimpleFormat};
+use sdist::sdist_pkg_info;
+use sha2::{Digest, Sha256};
+use upload::parse_legacy_upload;
+use yank::{is_yank_path, parse_yank, Yank};
+
+type IndexFiles = Vec<(String, Vec<u8>)>;
+
+/// Artifact property holding a wheel's JSON-encoded core metadata.
+const METADATA_PROPERTY: &str = "pypi.metadata";
+/// Artifact property holding a wheel's `METADATA` file as uploaded, served as the
+/// PEP 658 `{filename}.metadata` file.
+const METADATA_FILE_PROPERTY: &str = "pypi.metadata.file";
+
+/// Artifact property holding a validated, JSON-encoded yank document.
+const YANK_PROPERTY: &str = "pypi.yank";
 
 struct PypiFormatHandler;
 
@@ -43,8 +101,19 @@ impl HandlerGuest for PypiFormatHandler {
             return Err("Empty file".to_string());
         }
 
+        if is_yank_path(&path) {
+            let yank = parse_yank(&data)?;
+            return Ok(Metadata {
+                path,
+                version: yank.version,
+                content_type: "application/json".to_string(),
+                size_bytes: data.len() as u64,
+                checksum_sha256: None,
+            });
+        }
+
         let filename = path.rsplit('/').next().unwrap_or(&path);
-        let version = extract_version(filename);
+        let version = distribution_version(&path, &data)?;
 
         let content_type = if filename.ends_with(".whl") || filename.ends_with(".zip") {
             "application/zip"
@@ -72,6 +141,11 @@ impl HandlerGuest for PypiFormatHandler {
             return Err("Artifact path cannot be empty".to_string());
         }
 
+        if is_yank_path(&path) {
+            parse_yank(&data)?;
+            return Ok(());
+        }
+
         let filename = path.rsplit('/').next().unwrap_or(&path);
         let lower = filename.to_lowercase();
 
@@ -106,179 +180,526 @@ impl HandlerGuest for PypiFormatHandler {
             }
         }
 
+        if lower.ends_with(".whl") {
+            read_wheel_info(&path, &data)?;
+        }
+
         Ok(())
     }
 
     fn generate_index(artifacts: Vec<Metadata>) -> Result<Option<Vec<(String, Vec<u8>)>>, String> {
-        if artifacts.is_empty() {
-            return Ok(None);
-        }
+        let artifacts: Vec<PypiArtifact> = artifacts
+            .into_iter()
+            .map(PypiArtifact::from_metadata)
+            .collect();
+        let artifacts = apply_yanks(artifacts);
+        generate_pypi_index(&artifacts)
+    }
+}
 
-        // Collect unique normalized package names
-        let mut packages: Vec<String> = artifacts
-            .iter()
-            .filter_map(|a| {
-                let filename = a.path.rsplit('/').next()?;
-                extract_package_name(filename).map(|n| normalize_package_name(&n))
-            })
+impl RequestHandlerGuest for PypiFormatHandler {
+    fn handle_request(
+        request: HttpRequest,
+        context: RepoContext,
+        artifacts: Vec<Metadata>,
+    ) -> Result<HttpResponse, String> {
+        let artifacts: Vec<PypiArtifact> = artifacts
+            .into_iter()
+            .map(PypiArtifact::from_metadata)
             .collect();
-        packages.sort();
-        packages.dedup();
+        let artifacts = apply_yanks(artifacts);
+        route_request(&request, &context, &artifacts)
+    }
+}
 
-        // Generate PEP 503 Simple Repository root index
-        let mut html = String::from(
-            "<!DOCTYPE html>\n<html>\n<head><title>Simple Index</title></head>\n<body>\n",
-        );
-        for pkg in &packages {
-            html.push_str(&format!("  <a href=\"/simple/{pkg}/\">{pkg}</a>\n"));
+impl ArtifactPropertiesGuest for PypiFormatHandler {
+    fn extract_properties(path: String, data: Vec<u8>) -> Result<Vec<(String, String)>, String> {
+        if is_yank_path(&path) {
+            let yank = parse_yank(&data)?;
+            let json = serde_json::to_string(&yank)
+                .map_err(|e| format!("Failed to serialize yank document: {e}"))?;
+            return Ok(vec![(YANK_PROPERTY.to_string(), json)]);
         }
-        html.push_str("</body>\n</html>\n");
 
-        // Also generate a JSON index for API consumers
-        let entries: Vec<serde_json::Value> = artifacts
-            .iter()
-            .map(|a| {
-                let filename = a.path.rsplit('/').next().unwrap_or(&a.path);
-                let name = extract_package_name(filename)
-                    .map(|n| normalize_package_name(&n))
-                    .unwrap_or_default();
-
-                let mut entry = serde_json::Map::new();
-                entry.insert("path".into(), serde_json::Value::String(a.path.clone()));
-                entry.insert("name".into(), serde_json::Value::String(name));
-                if let Some(ref v) = a.version {
-                    entry.insert("version".into(), serde_json::Value::String(v.clone()));
-                }
-                entry.insert(
-                    "content_type".into(),
-                    serde_json::Value::String(a.content_type.clone()),
-                );
-                entry.insert(
-                    "size_bytes".into(),
-                    serde_json::Value::Number(a.size_bytes.into()),
-                );
-                serde_json::Value::Object(entry)
-            })
-            .collect();
-
-        let json_index = serde_json::json!({
-            "format": "pypi-custom",
-            "total_count": artifacts.len(),
-            "total_size_bytes": artifacts.iter().map(|a| a.size_bytes).sum::<u64>(),
-            "packages": entries,
-        });
+        // Source distributions are identified by filename; their PKG-INFO is kept only
+        // when it can be read and agrees with the filename.
+        if !path.to_lowercase().ends_with(".whl") {
+            let Some(metadata) = read_sdist_info(&path, &data) else {
+                return Ok(Vec::new());
+            };
+            let json = serde_json::to_string(&metadata)
+                .map_err(|e| format!("Failed to serialize core metadata: {e}"))?;
+            return Ok(vec![(METADATA_PROPERTY.to_string(), json)]);
+        }
 
-        let json_bytes = serde_json::to_vec_pretty(&json_index)
-            .map_err(|e| format!("Failed to serialize index: {e}"))?;
+        let (metadata, file) = read_wheel_info(&path, &data)?;
+        let json = serde_json::to_string(&metadata)
+            .map_err(|e| format!("Failed to serialize core metadata: {e}"))?;
+        Ok(vec![
+            (METADATA_PROPERTY.to_string(), json),
+            (METADATA_FILE_PROPERTY.to_string(), file),
+        ])
+    }
 
-        Ok(Some(vec![
-            ("simple/index.html".to_string(), html.into_bytes()),
-            ("pypi-index.json".to_string(), json_bytes),
-        ]))
+    fn generate_index_with_properties(
+        artifacts: Vec<Artifact>,
+    ) -> Result<Option<Vec<(String, Vec<u8>)>>, String> {
+        let artifacts = apply_yanks(artifacts.into_iter().map(PypiArtifact::new).collect());
+        generate_pypi_index(&artifacts)
     }
-}
 
-impl RequestHandlerGuest for PypiFormatHandler {
-    fn handle_request(
+    fn handle_request_with_properties(
         request: HttpRequest,
         context: RepoContext,
-        artifacts: Vec<Metadata>,
+        artifacts: Vec<Artifact>,
     ) -> Result<HttpResponse, String> {
-        let path = request.path.as_str();
-
-        // Only handle GET and HEAD
-        if request.method != "GET" && request.method != "HEAD" {
-            return Ok(HttpResponse {
-                status: 405,
-                headers: vec![("allow".to_string(), "GET, HEAD".to_string())],
-                body: b"Method Not Allowed".to_vec(),
-            });
+        let artifacts = apply_yanks(artifacts.into_iter().map(PypiArtifact::new).collect());
+        route_request(&request, &context, &artifacts)
+    }
+}
+
+impl UploadHandlerGuest for PypiFormatHandler {
+    fn handle_upload(
+        request: HttpRequest,
+        _context: RepoContext,
+        artifacts: Vec<Artifact>,
+    ) -> Result<UploadResult, String> {
+        let artifacts: Vec<PypiArtifact> = artifacts.into_iter().map(PypiArtifact::new).collect();
+        Ok(route_upload(&request, &artifacts))
+    }
+}
+
+export!(PypiFormatHandler);
+
+/// A repository artifact together with the core metadata stored for it on upload.
+///
+/// Source distributions, and wheels uploaded before properties were available, have
+/// no stored metadata and fall back to filename parsing. Yank documents are artifacts
+/// too, carrying their content instead of core metadata.
+struct PypiArtifact {
+    metadata: Metadata,
+    core: Option<CoreMetadata>,
+    /// The wheel's `METADATA` file, served alongside it per PEP 658.
+    metadata_file: Option<String>,
+    yank: Option<Yank>,
+    /// Set by [`apply_yanks`] when a yank document covers this file: the reason, or an
+    /// empty string if none was given.
+    yanked: Option<String>,
+}
+
+impl PypiArtifact {
+    fn new(artifact: Artifact) -> Self {
+        let property = |key: &str| {
+            artifact
+                .properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+        let core = property(METADATA_PROPERTY).and_then(|v| serde_json::from_str(&v).ok());
+        let metadata_file = property(METADATA_FILE_PROPERTY);
+        let yank = property(YANK_PROPERTY).and_then(|v| serde_json::from_str(&v).ok());
+        PypiArtifact {
+            metadata: artifact.metadata,
+            core,
+            metadata_file,
+            yank,
+            yanked: None,
         }
+    }
 
-        // Route: /simple/ - PEP 503 root index
-        if path == "/simple/" || path == "/simple" || path == "/" {
-            return handle_simple_root(&context, &artifacts);
+    fn from_metadata(metadata: Metadata) -> Self {
+        PypiArtifact {
+            metadata,
+            core: None,
+            metadata_file: None,
+            yank: None,
+            yanked: None,
         }
+    }
 
-        // Route: /simple/{project}/ - PEP 503 project page
-        let trimmed = path.trim_end_matches('/');
-        if let Some(project) = trimmed.strip_prefix("/simple/") {
-            if !project.contains('/') && !project.is_empty() {
-                return handle_simple_project(project, &context, &artifacts);
-            }
+    /// Whether this is a yank document rather than a distribution file.
+    fn is_document(&self) -> bool {
+        is_yank_path(&self.metadata.path)
+    }
+
+    /// The version, from the core metadata when available.
+    fn version(&self) -> Option<&str> {
+        match &self.core {
+            Some(core) => Some(&core.version),
+            None => self.metadata.version.as_deref(),
         }
+    }
 
-        // Route: /packages/{filename} - redirect to download
-        if let Some(filename) = trimmed.strip_prefix("/packages/") {
-            if !filename.contains('/') && !filename.is_empty() {
-                return handle_package_download(filename, &context, &artifacts);
-            }
+    /// The `Requires-Python` specifier from the stored core metadata.
+    fn requires_python(&self) -> Option<&str> {
+        self.core.as_ref()?.requires_python.as_deref()
+    }
+
+    /// The SHA-256 of the PEP 658 metadata file, if the artifact has one.
+    fn metadata_file_sha256(&self) -> Option<String> {
+        self.metadata_file
+            .as_ref()
+            .map(|file| sha256_hex(file.as_bytes()))
+    }
+
+    fn filename(&self) -> &str {
+        self.metadata
+            .path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.metadata.path)
+    }
+
+    /// The PEP 503 normalized project name, from the core metadata when available.
+    fn project_name(&self) -> Option<String> {
+        match &self.core {
+            Some(core) => Some(normalize_package_name(&core.name)),
+            None => extract_package_name(self.filename()).map(|n| normalize_package_name(&n)),
         }
+    }
+}
 
-        // 404 for everything else
-        Ok(HttpResponse {
-            status: 404,
-            headers: vec![("content-type".to_string(), "text/plain".to_string())],
-            body: b"Not Found".to_vec(),
-        })
+/// Drop yank documents from the artifact list, marking the files they yank.
+///
+/// Documents whose content wasn't stored (no properties) yank nothing. When several
+/// documents cover a file, one that gives a reason wins.
+fn apply_yanks(artifacts: Vec<PypiArtifact>) -> Vec<PypiArtifact> {
+    let (documents, mut packages): (Vec<PypiArtifact>, Vec<PypiArtifact>) =
+        artifacts.into_iter().partition(PypiArtifact::is_document);
+    let yanks: Vec<&Yank> = documents.iter().filter_map(|a| a.yank.as_ref()).collect();
+
+    for package in &mut packages {
+        let Some(project) = package.project_name() else {
+            continue;
+        };
+        let yanked = yanks
+            .iter()
+            .filter(|y| y.covers(&project, package.version(), package.filename()))
+            .max_by_key(|y| y.reason.is_some())
+            .map(|y| y.reason.clone().unwrap_or_default());
+        package.yanked = yanked;
     }
+    packages
 }
 
-export!(PypiFormatHandler);
+// ---------------------------------------------------------------------------
+// Index generation
+// ---------------------------------------------------------------------------
+
+/// Generate the PEP 503 root index and `pypi-index.json`.
+fn generate_pypi_index(artifacts: &[PypiArtifact]) -> Result<Option<IndexFiles>, String> {
+    if artifacts.is_empty() {
+        return Ok(None);
+    }
+
+    // Collect unique normalized package names
+    let mut packages: Vec<String> = artifacts
+        .iter()
+        .filter_map(PypiArtifact::project_name)
+        .collect();
+    packages.sort();
+    packages.dedup();
+
+    // Generate PEP 503 Simple Repository root index
+    let mut html =
+        String::from("<!DOCTYPE html>\n<html>\n<head><title>Simple Index</title></head>\n<body>\n");
+    for pkg in &packages {
+        html.push_str(&format!("  <a href=\"/simple/{pkg}/\">{pkg}</a>\n"));
+    }
+    html.push_str("</body>\n</html>\n");
+
+    // Also generate a JSON index for API consumers
+    let entries: Vec<serde_json::Value> = artifacts.iter().map(index_entry).collect();
+
+    let json_index = serde_json::json!({
+        "format": "pypi-custom",
+        "total_count": artifacts.len(),
+        "total_size_bytes": artifacts.iter().map(|a| a.metadata.size_bytes).sum::<u64>(),
+        "packages": entries,
+    });
+
+    let json_bytes = serde_json::to_vec_pretty(&json_index)
+        .map_err(|e| format!("Failed to serialize index: {e}"))?;
+
+    Ok(Some(vec![
+        ("simple/index.html".to_string(), html.into_bytes()),
+        ("pypi-index.json".to_string(), json_bytes),
+    ]))
+}
+
+/// A single file entry in `pypi-index.json`.
+///
+/// Wheels with stored core metadata also list its summary, Python requirement,
+/// dependencies, extras, license, and classifiers.
+fn index_entry(a: &PypiArtifact) -> serde_json::Value {
+    let mut entry = serde_json::Map::new();
+    entry.insert(
+        "path".into(),
+        serde_json::Value::String(a.metadata.path.clone()),
+    );
+    entry.insert(
+        "name".into(),
+        serde_json::Value::String(a.project_name().unwrap_or_default()),
+    );
+    if let Some(ref v) = a.metadata.version {
+        entry.insert("version".into(), serde_json::Value::String(v.clone()));
+    }
+    entry.insert(
+        "content_type".into(),
+        serde_json::Value::String(a.metadata.content_type.clone()),
+    );
+    entry.insert(
+        "size_bytes".into(),
+        serde_json::Value::Number(a.metadata.size_bytes.into()),
+    );
+    if let Some(core) = &a.core {
+        entry.insert("summary".into(), serde_json::json!(core.summary));
+        entry.insert(
+            "requires_python".into(),
+            serde_json::json!(core.requires_python),
+        );
+        entry.insert(
+            "requires_dist".into(),
+            serde_json::json!(core.requires_dist),
+        );
+        entry.insert(
+            "provides_extra".into(),
+            serde_json::json!(core.provides_extra),
+        );
+        entry.insert("license".into(), serde_json::json!(core.license));
+        entry.insert("classifiers".into(), serde_json::json!(core.classifiers));
+    }
+    if let Some(reason) = &a.yanked {
+        entry.insert("yanked".into(), yanked_json(reason));
+    }
+    serde_json::Value::Object(entry)
+}
 
 // ---------------------------------------------------------------------------
 // Request handler helpers
 // ---------------------------------------------------------------------------
 
+/// Route a request to the simple pages or a package download.
+fn route_request(
+    request: &HttpRequest,
+    context: &RepoContext,
+    artifacts: &[PypiArtifact],
+) -> Result<HttpResponse, String> {
+    let path = request.path.as_str();
+
+    // Only handle GET and HEAD
+    if request.method != "GET" && request.method != "HEAD" {
+        return Ok(HttpResponse {
+            status: 405,
+            headers: vec![("allow".to_string(), "GET, HEAD".to_string())],
+            body: b"Method Not Allowed".to_vec(),
+        });
+    }
+
+    // Route: /simple/ - PEP 503 root index
+    if path == "/simple/" || path == "/simple" || path == "/" {
+        return negotiated(request, |format| {
+            handle_simple_root(format, context, artifacts)
+        });
+    }
+
+    // Route: /simple/{project}/ - PEP 503 project page
+    let trimmed = path.trim_end_matches('/');
+    if let Some(project) = trimmed.strip_prefix("/simple/") {
+        if !project.contains('/') && !project.is_empty() {
+            return negotiated(request, |format| {
+                handle_simple_project(project, format, context, artifacts)
+            });
+        }
+    }
+
+    // Route: /packages/{filename}.metadata - PEP 658 core metadata file
+    // Route: /packages/{filename} - redirect to download
+    if let Some(filename) = trimmed.strip_prefix("/packages/") {
+        if !filename.contains('/') && !filename.is_empty() {
+            if let Some(distribution) = filename.strip_suffix(".metadata") {
+                if let Some(artifact) = artifacts.iter().find(|a| a.filename() == distribution) {
+                    return Ok(handle_metadata_file(artifact));
+                }
+            }
+            return handle_package_download(filename, context, artifacts);
+        }
+    }
+
+    // 404 for everything else
+    Ok(HttpResponse {
+        status: 404,
+        headers: vec![("content-type".to_string(), "text/plain".to_string())],
+        body: b"Not Found".to_vec(),
+    })
+}
+
+/// Handle `POST /`, the legacy upload API `twine upload` uses.
+///
+/// Accepted files are stored at `packages/{project}/{version}/{filename}`, where the
+/// version is the one `parse_metadata` reads from the file rather than the form's. A
+/// file that's already in the repository is refused with the 400
+/// `twine upload --skip-existing` recognizes.
+fn route_upload(request: &HttpRequest, artifacts: &[PypiArtifact]) -> UploadResult {
+    let rejected = |status: u16, message: String| UploadResult {
+        file: None,
+        response: HttpResponse {
+            status,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: message.into_bytes(),
+        },
+    };
+
+    if request.path != "/" && !request.path.is_empty() {
+        return rejected(404, "Not Found".to_string());
+    }
+    if request.method != "POST" {
+        let mut result = rejected(405, "Method Not Allowed".to_string());
+        result
+            .response
+            .headers
+            .push(("allow".to_string(), "POST".to_string()));
+        return result;
+    }
+
+    let content_type = request_header(request, "content-type");
+    let upload = match parse_legacy_upload(content_type.as_deref(), &request.body) {
+        Ok(upload) => upload,
+        Err(e) => return rejected(400, e),
+    };
+    if artifacts.iter().any(|a| a.filename() == upload.filename) {
+        return rejected(400, format!("File already exists: '{}'", upload.filename));
+    }
+    // The path uses the version parse_metadata will read from the file, not the form's
+    let version = match distribution_version(&upload.filename, upload.content) {
+        Ok(Some(version)) if is_valid_version(&version) => version,
+        Ok(_) => {
+            return rejected(
+                400,
+                format!("Can't read a valid version from '{}'", upload.filename),
+            )
+        }
+        Err(e) => return rejected(400, e),
+    };
+
+    UploadResult {
+        file: Some(Upload {
+            path: format!(
+                "packages/{}/{version}/{}",
+                normalize_package_name(&upload.name),
+                upload.filename
+            ),
+            data: upload.content.to_vec(),
+        }),
+        response: HttpResponse {
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: b"OK".to_vec(),
+        },
+    }
+}
+
+/// A request header's value, with repeated headers joined by commas.
+fn request_header(request: &HttpRequest, name: &str) -> Option<String> {
+    let values: Vec<&str> = request
+        .headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+        .collect();
+    (!values.is_empty()).then(|| values.join(", "))
+}
+
+/// Serve a simple page in the format the request's `Accept` header selects, or a 406.
+///
+/// Every response carries `Vary: Accept`, since the same URL has several
+/// representations.
+fn negotiated(
+    request: &HttpRequest,
+    render: impl FnOnce(SimpleFormat) -> Result<HttpResponse, String>,
+) -> Result<HttpResponse, String> {
+    let accept = request_header(request, "accept");
+    let mut response = match negotiate(accept.as_deref()) {
+        Some(format) => render(format)?,
+        None => HttpResponse {
+            status: 406,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: format!(
+                "Not Acceptable: available types are {}, {}, and {}",
+                negotiate::JSON_V1,
+                negotiate::HTML_V1,
+                negotiate::HTML_LEGACY
+            )
+            .into_bytes(),
+        },
+    };
+    response
+        .headers
+        .push(("vary".to_string(), "Accept".to_string()));
+    Ok(response)
+}
+
 /// PEP 503 root index: list all normalized package names as links.
+///
+/// As PEP 691 JSON, the same list is given under `projects`.
 fn handle_simple_root(
+    format: SimpleFormat,
     context: &RepoContext,
-    artifacts: &[Metadata],
+    artifacts: &[PypiArtifact],
 ) -> Result<HttpResponse, String> {
     let mut packages: Vec<String> = artifacts
         .iter()
-        .filter_map(|a| {
-            let filename = a.path.rsplit('/').next()?;
-            extract_package_name(filename).map(|n| normalize_package_name(&n))
-        })
+        .filter_map(PypiArtifact::project_name)
         .collect();
     packages.sort();
     packages.dedup();
 
-    let mut html =
-        String::from("<!DOCTYPE html>\n<html>\n<head><title>Simple Index</title></head>\n<body>\n");
-    for pkg in &packages {
-        html.push_str(&format!(
-            "  <a href=\"{}/simple/{}/\">{}</a>\n",
-            context.base_url, pkg, pkg
-        ));
-    }
-    html.push_str("</body>\n</html>\n");
+    let body = if format == SimpleFormat::Json {
+        let projects: Vec<serde_json::Value> = packages
+            .iter()
+            .map(|pkg| serde_json::json!({ "name": pkg }))
+            .collect();
+        simple_json(serde_json::json!({ "projects": projects }))?
+    } else {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head><title>Simple Index</title></head>\n<body>\n",
+        );
+        for pkg in &packages {
+            html.push_str(&format!(
+                "  <a href=\"{}/simple/{}/\">{}</a>\n",
+                context.base_url, pkg, pkg
+            ));
+        }
+        html.push_str("</body>\n</html>\n");
+        html.into_bytes()
+    };
 
     Ok(HttpResponse {
         status: 200,
-        headers: vec![("content-type".to_string(), "text/html".to_string())],
-        body: html.into_bytes(),
+        headers: vec![(
+            "content-type".to_string(),
+            format.content_type().to_string(),
+        )],
+        body,
     })
 }
 
 /// PEP 503 project page: list files for a specific package with `#sha256=` fragments.
+///
+/// As PEP 691 JSON, each file is listed with its URL and `hashes`.
 fn handle_simple_project(
     project: &str,
+    format: SimpleFormat,
     context: &RepoContext,
-    artifacts: &[Metadata],
+    artifacts: &[PypiArtifact],
 ) -> Result<HttpResponse, String> {
     let normalized_project = normalize_package_name(project);
 
     // Filter artifacts matching this project
-    let matching: Vec<&Metadata> = artifacts
+    let matching: Vec<&PypiArtifact> = artifacts
         .iter()
-        .filter(|a| {
-            let filename = a.path.rsplit('/').next().unwrap_or(&a.path);
-            extract_package_name(filename)
-                .map(|n| normalize_package_name(&n) == normalized_project)
-                .unwrap_or(false)
-        })
+        .filter(|a| a.project_name().as_deref() == Some(normalized_project.as_str()))
         .collect();
 
     if matching.is_empty() {
@@ -289,6 +710,51 @@ fn handle_simple_project(
         });
     }
 
+    if format == SimpleFormat::Json {
+        let files: Vec<serde_json::Value> = matching
+            .iter()
+            .map(|artifact| {
+                let filename = artifact.filename();
+                let mut hashes = serde_json::Map::new();
+                if let Some(sha) = artifact
+                    .metadata
+                    .checksum_sha256
+                    .as_ref()
+                    .filter(|s| !s.is_empty())
+                {
+                    hashes.insert("sha256".into(), serde_json::Value::String(sha.clone()));
+                }
+                let mut file = serde_json::json!({
+                    "filename": filename,
+                    "url": format!("{}/packages/{}", context.base_url, filename),
+                    "hashes": hashes,
+                    "yanked": artifact.yanked.as_deref().map_or(false.into(), yanked_json),
+                });
+                if let Some(requires_python) = artifact.requires_python() {
+                    file["requires-python"] = requires_python.into();
+                }
+                if let Some(sha) = artifact.metadata_file_sha256() {
+                    // PEP 714 renamed dist-info-metadata; older clients still read it
+                    let hashes = serde_json::json!({ "sha256": sha });
+                    file["core-metadata"] = hashes.clone();
+                    file["dist-info-metadata"] = hashes;
+                }
+                file
+            })
+            .collect();
+        return Ok(HttpResponse {
+            status: 200,
+            headers: vec![(
+                "content-type".to_string(),
+                format.content_type().to_string(),
+            )],
+            body: simple_json(serde_json::json!({
+                "name": normalized_project,
+                "files": files,
+            }))?,
+        });
+    }
+
     let mut html = format!(
         "<!DOCTYPE html>\n<html>\n<head><title>Links for {}</title></head>\n<body>\n\
          <h1>Links for {}</h1>\n",
@@ -296,14 +762,29 @@ fn handle_simple_project(
     );
 
     for artifact in &matching {
-        let filename = artifact.path.rsplit('/').next().unwrap_or(&artifact.path);
-        let hash_fragment = match &artifact.checksum_sha256 {
+        let filename = artifact.filename();
+        let hash_fragment = match &artifact.metadata.checksum_sha256 {
             Some(sha) if !sha.is_empty() => format!("#sha256={}", sha),
             _ => String::new(),
         };
+        let mut attrs = String::new();
+        if let Some(requires_python) = artifact.requires_python() {
+            attrs.push_str(&format!(
+                " data-requires-python=\"{}\"",
+                html_escape(requires_python)
+            ));
+        }
+        if let Some(reason) = &artifact.yanked {
+            attrs.push_str(&format!(" data-yanked=\"{}\"", html_escape(reason)));
+        }
+        if let Some(sha) = artifact.metadata_file_sha256() {
+            attrs.push_str(&format!(
+                " data-core-metadata=\"sha256={sha}\" data-dist-info-metadata=\"sha256={sha}\""
+            ));
+        }
         html.push_str(&format!(
-            "  <a href=\"{}/packages/{}{}\">{}</a>\n",
-            context.base_url, filename, hash_fragment, filename
+            "  <a href=\"{}/packages/{}{}\"{}>{}</a>\n",
+            context.base_url, filename, hash_fragment, attrs, filename
         ));
     }
 
@@ -311,25 +792,60 @@ fn handle_simple_project(
 
     Ok(HttpResponse {
         status: 200,
-        headers: vec![("content-type".to_string(), "text/html".to_string())],
+        headers: vec![(
+            "content-type".to_string(),
+            format.content_type().to_string(),
+        )],
         body: html.into_bytes(),
     })
 }
 
+/// The PEP 691 `yanked` value of a yanked file: its reason, or `true` if none was given.
+fn yanked_json(reason: &str) -> serde_json::Value {
+    if reason.is_empty() {
+        serde_json::Value::Bool(true)
+    } else {
+        serde_json::Value::String(reason.to_string())
+    }
+}
+
+/// Serialize a PEP 691 JSON page, adding the `meta` block every page carries.
+fn simple_json(mut page: serde_json::Value) -> Result<Vec<u8>, String> {
+    page["meta"] = serde_json::json!({ "api-version": "1.0" });
+    serde_json::to_vec(&page).map_err(|e| format!("Failed to serialize simple page: {e}"))
+}
+
+/// Serve a wheel's PEP 658 core metadata file, or a 404 if it has none stored.
+fn handle_metadata_file(artifact: &PypiArtifact) -> HttpResponse {
+    match &artifact.metadata_file {
+        Some(file) => HttpResponse {
+            status: 200,
+            headers: vec![(
+                "content-type".to_string(),
+                "text/plain; charset=utf-8".to_string(),
+            )],
+            body: file.clone().into_bytes(),
+        },
+        None => HttpResponse {
+            status: 404,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: format!("No metadata file for '{}'", artifact.filename()).into_bytes(),
+        },
+    }
+}
+
 /// Redirect package download to the artifact storage download endpoint.
 fn handle_package_download(
     filename: &str,
     context: &RepoContext,
-    artifacts: &[Metadata],
+    artifacts: &[PypiArtifact],
 ) -> Result<HttpResponse, String> {
     // Find the artifact matching this filename
-    let artifact = artifacts
-        .iter()
-        .find(|a| a.path.rsplit('/').next().unwrap_or(&a.path) == filename);
+    let artifact = artifacts.iter().find(|a| a.filename() == filename);
 
     match artifact {
         Some(a) => {
-            let download_url = format!("{}/{}", context.download_base_url, a.path);
+            let download_url = format!("{}/{}", context.download_base_url, a.metadata.path);
             Ok(HttpResponse {
                 status: 302,
                 headers: vec![("location".to_string(), download_url)],
@@ -348,6 +864,84 @@ fn handle_package_download(
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Read a wheel's core metadata and check that its filename agrees with it.
+///
+/// Returns the parsed metadata and the `METADATA` file it came from. The filename's
+/// name is compared after PEP 503 normalization, and its version with the `-` to `_`
+/// escaping wheel filenames apply.
+fn read_wheel_info(path: &str, data: &[u8]) -> Result<(CoreMetadata, String), String> {
+    let file = wheel_metadata_file(data)?;
+    let metadata = parse_core_metadata(&file)?;
+    let filename = path.rsplit('/').next().unwrap_or(path);
+
+    let name = extract_package_name(filename).unwrap_or_default();
+    if normalize_package_name(&name) != normalize_package_name(&metadata.name) {
+        return Err(format!(
+            "Wheel filename '{filename}' doesn't match the project name '{}' in its METADATA",
+            metadata.name
+        ));
+    }
+    let version = extract_version(filename).unwrap_or_default();
+    if !version.eq_ignore_ascii_case(&metadata.version.replace('-', "_")) {
+        return Err(format!(
+            "Wheel filename '{filename}' doesn't match the version '{}' in its METADATA",
+            metadata.version
+        ));
+    }
+    // parse_core_metadata has already checked the file is UTF-8
+    let file = String::from_utf8(file).map_err(|_| "Invalid METADATA: not UTF-8".to_string())?;
+    Ok((metadata, file))
+}
+
+/// A distribution's version: from a wheel's METADATA, or from an sdist's filename.
+fn distribution_version(path: &str, data: &[u8]) -> Result<Option<String>, String> {
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    if filename.ends_with(".whl") {
+        Ok(Some(read_wheel_info(path, data)?.0.version))
+    } else {
+        Ok(extract_version(filename))
+    }
+}
+
+/// Read an sdist's `PKG-INFO`, if it has a readable one that agrees with the filename.
+///
+/// Unlike a wheel's METADATA this is best-effort: sdists that predate PKG-INFO, or whose
+/// filename can't be compared with it, are still accepted and identified by filename.
+fn read_sdist_info(path: &str, data: &[u8]) -> Option<CoreMetadata> {
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let file = sdist_pkg_info(filename, data).ok()??;
+    let metadata = parse_core_metadata(&file).ok()?;
+
+    let name = extract_package_name(filename)?;
+    let version = extract_version(filename)?;
+    let agrees = normalize_package_name(&name) == normalize_package_name(&metadata.name)
+        && version.eq_ignore_ascii_case(&metadata.version);
+    agrees.then_some(metadata)
+}
+
+/// Escape text for an HTML attribute value or element content.
+fn html_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
 /// Normalize a Python package name per PEP 503.
 ///
 /// Converts to lowercase and replaces any run of non-alphanumeric characters
@@ -412,6 +1006,25 @@ fn extract_version(filename: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sdist::testing::build_tar_gz;
+    use crate::upload::testing::{upload_form, BOUNDARY};
+    use crate::zip::testing::build_zip;
+
+    /// A wheel whose METADATA declares `name` and `version`.
+    fn wheel(name: &str, version: &str) -> Vec<u8> {
+        let metadata = format!(
+            "Metadata-Version: 2.1\nName: {name}\nVersion: {version}\n\
+             Summary: Python HTTP for Humans.\nRequires-Python: >=3.7\n\
+             Requires-Dist: idna (<4,>=2.5)\nLicense: Apache 2.0\n"
+        );
+        build_zip(&[
+            (&format!("{name}/__init__.py"), b""),
+            (
+                &format!("{name}-{version}.dist-info/METADATA"),
+                metadata.as_bytes(),
+            ),
+        ])
+    }
 
     // -- format_key --
 
@@ -516,7 +1129,7 @@ mod tests {
 
     #[test]
     fn parse_metadata_wheel() {
-        let data = vec![0x50, 0x4b, 0x03, 0x04]; // ZIP magic
+        let data = wheel("requests", "2.28.0");
         let result = PypiFormatHandler::parse_metadata(
             "packages/requests/2.28.0/requests-2.28.0-py3-none-any.whl".into(),
             data,
@@ -526,6 +1139,35 @@ mod tests {
         assert_eq!(meta.version, Some("2.28.0".to_string()));
     }
 
+    #[test]
+    fn parse_metadata_wheel_version_from_metadata() {
+        // Wheel filenames escape '-' in local versions; METADATA has the real version
+        let data = wheel("My.Package", "1.0+local-1");
+        let meta = PypiFormatHandler::parse_metadata(
+            "my_package-1.0+local_1-py3-none-any.whl".into(),
+            data,
+        )
+        .unwrap();
+        assert_eq!(meta.version, Some("1.0+local-1".to_string()));
+    }
+
+    #[test]
+    fn parse_metadata_rejects_renamed_wheel() {
+        let err = PypiFormatHandler::parse_metadata(
+            "requests-2.29.0-py3-none-any.whl".into(),
+            wheel("requests", "2.28.0"),
+        )
+        .unwrap_err();
+        assert!(err.contains("version '2.28.0'"), "{err}");
+
+        let err = PypiFormatHandler::parse_metadata(
+            "urllib3-2.28.0-py3-none-any.whl".into(),
+            wheel("requests", "2.28.0"),
+        )
+        .unwrap_err();
+        assert!(err.contains("project name 'requests'"), "{err}");
+    }
+
     #[test]
     fn parse_metadata_sdist() {
         let data = vec![0x1f, 0x8b, 0x08]; // gzip magic
@@ -548,7 +1190,7 @@ mod tests {
 
     #[test]
     fn validate_accepts_wheel() {
-        let data = vec![0x50, 0x4b, 0x03, 0x04];
+        let data = wheel("requests", "2.28.0");
         let result = PypiFormatHandler::validate("requests-2.28.0-py3-none-any.whl".into(), data);
         assert!(result.is_ok());
     }
@@ -586,6 +1228,17 @@ mod tests {
         assert!(result.unwrap_err().contains("name-version"));
     }
 
+    #[test]
+    fn validate_rejects_wheel_without_metadata() {
+        let data = vec![0x50, 0x4b, 0x03, 0x04];
+        let result = PypiFormatHandler::validate("requests-2.28.0-py3-none-any.whl".into(), data);
+        assert!(result.unwrap_err().contains("Invalid wheel"));
+
+        let data = build_zip(&[("requests/__init__.py", b"")]);
+        let result = PypiFormatHandler::validate("requests-2.28.0-py3-none-any.whl".into(), data);
+        assert!(result.unwrap_err().contains("METADATA"));
+    }
+
     #[test]
     fn validate_rejects_empty_path() {
         let result = PypiFormatHandler::validate("".into(), vec![0x00]);
@@ -653,6 +1306,95 @@ mod tests {
         assert!(html.contains("my-package"));
     }
 
+    // -- core metadata properties --
+
+    #[test]
+    fn extract_properties_stores_wheel_metadata() {
+        let properties = PypiFormatHandler::extract_properties(
+            "requests-2.28.0-py3-none-any.whl".into(),
+            wheel("requests", "2.28.0"),
+        )
+        .unwrap();
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties[0].0, METADATA_PROPERTY);
+        assert_eq!(properties[1].0, METADATA_FILE_PROPERTY);
+        assert!(properties[1]
+            .1
+            .starts_with("Metadata-Version: 2.1\nName: requests\n"));
+        let core: CoreMetadata = serde_json::from_str(&properties[0].1).unwrap();
+        assert_eq!(core.name, "requests");
+        assert_eq!(core.requires_python.as_deref(), Some(">=3.7"));
+
+        let properties =
+            PypiFormatHandler::extract_properties("requests-2.28.0.tar.gz".into(), vec![0x1f])
+                .unwrap();
+        assert!(properties.is_empty());
+    }
+
+    #[test]
+    fn extract_properties_reads_sdist_pkg_info() {
+        let pkg_info = b"Metadata-Version: 2.1\nName: requests\nVersion: 2.28.0\n\
+                         Requires-Python: >=3.7, <4\n";
+        let sdist = build_tar_gz(&[("requests-2.28.0/PKG-INFO", pkg_info)]);
+        let properties =
+            PypiFormatHandler::extract_properties("requests-2.28.0.tar.gz".into(), sdist.clone())
+                .unwrap();
+        // Only the parsed metadata; sdists have no PEP 658 metadata file
+        assert_eq!(properties.len(), 1);
+        let core: CoreMetadata = serde_json::from_str(&properties[0].1).unwrap();
+        assert_eq!(core.requires_python.as_deref(), Some(">=3.7, <4"));
+
+        // PKG-INFO that disagrees with the filename is ignored
+        let properties =
+            PypiFormatHandler::extract_properties("requests-2.29.0.tar.gz".into(), sdist).unwrap();
+        assert!(properties.is_empty());
+    }
+
+    #[test]
+    fn generate_index_with_properties_lists_core_metadata() {
+        let path = "packages/My.Package-1.0-py3-none-any.whl";
+        let properties =
+            PypiFormatHandler::extract_properties(path.into(), wheel("My.Package", "1.0")).unwrap();
+        let artifacts = vec![
+            Artifact {
+                metadata: Metadata {
+                    path: path.into(),
+                    version: Some("1.0".into()),
+                    content_type: "application/zip".into(),
+                    size_bytes: 1024,
+                    checksum_sha256: None,
+                },
+                properties,
+            },
+            Artifact {
+                metadata: Metadata {
+                    path: "packages/numpy-1.24.2.tar.gz".into(),
+                    version: Some("1.24.2".into()),
+                    content_type: "application/gzip".into(),
+                    size_bytes: 4096,
+                    checksum_sha256: None,
+                },
+                properties: Vec::new(),
+            },
+        ];
+        let result = PypiFormatHandler::generate_index_with_properties(artifacts)
+            .unwrap()
+            .unwrap();
+        let html = String::from_utf8(result[0].1.clone()).unwrap();
+        assert!(html.contains("/simple/my-package/"));
+
+        let json: serde_json::Value = serde_json::from_slice(&result[1].1).unwrap();
+        let wheel_entry = &json["packages"][0];
+        assert_eq!(wheel_entry["name"], "my-package");
+        assert_eq!(wheel_entry["summary"], "Python HTTP for Humans.");
+        assert_eq!(wheel_entry["requires_python"], ">=3.7");
+        assert_eq!(wheel_entry["requires_dist"][0], "idna (<4,>=2.5)");
+        assert_eq!(wheel_entry["license"], "Apache 2.0");
+        let sdist_entry = &json["packages"][1];
+        assert_eq!(sdist_entry["name"], "numpy");
+        assert!(sdist_entry.get("summary").is_none());
+    }
+
     // -- handle_request (PEP 503) --
 
     fn test_context() -> RepoContext {
@@ -791,6 +1533,498 @@ mod tests {
         assert_eq!(resp.status, 404);
     }
 
+    // -- handle_request (PEP 691) --
+
+    fn accept_request(path: &str, accept: &str) -> HttpRequest {
+        HttpRequest {
+            headers: vec![("Accept".to_string(), accept.to_string())],
+            ..get_request(path)
+        }
+    }
+
+    fn header<'a>(resp: &'a HttpResponse, name: &str) -> Option<&'a str> {
+        resp.headers
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn handle_request_defaults_to_html() {
+        let resp = PypiFormatHandler::handle_request(
+            get_request("/simple/requests/"),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        assert_eq!(header(&resp, "content-type"), Some("text/html"));
+        assert_eq!(header(&resp, "vary"), Some("Accept"));
+    }
+
+    #[test]
+    fn handle_request_json_root() {
+        let resp = PypiFormatHandler::handle_request(
+            accept_request("/simple/", "application/vnd.pypi.simple.v1+json"),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(
+            header(&resp, "content-type"),
+            Some("application/vnd.pypi.simple.v1+json")
+        );
+        assert_eq!(header(&resp, "vary"), Some("Accept"));
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        assert_eq!(json["meta"]["api-version"], "1.0");
+        assert_eq!(
+            json["projects"],
+            serde_json::json!([{ "name": "numpy" }, { "name": "requests" }])
+        );
+    }
+
+    #[test]
+    fn handle_request_json_project() {
+        let resp = PypiFormatHandler::handle_request(
+            accept_request(
+                "/simple/Requests/",
+                "application/vnd.pypi.simple.v1+json, text/html;q=0.01",
+            ),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        assert_eq!(resp.status, 200);
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        assert_eq!(json["meta"]["api-version"], "1.0");
+        assert_eq!(json["name"], "requests");
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["filename"], "requests-2.28.0-py3-none-any.whl");
+        assert_eq!(
+            files[0]["url"],
+            "http://localhost:8080/ext/pypi-custom/pypi-test/packages/\
+             requests-2.28.0-py3-none-any.whl"
+        );
+        assert_eq!(files[0]["hashes"]["sha256"], "abc123");
+    }
+
+    #[test]
+    fn handle_request_versioned_html() {
+        let resp = PypiFormatHandler::handle_request(
+            accept_request(
+                "/simple/requests/",
+                "application/vnd.pypi.simple.latest+html",
+            ),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(
+            header(&resp, "content-type"),
+            Some("application/vnd.pypi.simple.v1+html")
+        );
+        assert!(String::from_utf8(resp.body)
+            .unwrap()
+            .contains("#sha256=abc123"));
+    }
+
+    #[test]
+    fn handle_request_not_acceptable() {
+        let resp = PypiFormatHandler::handle_request(
+            accept_request("/simple/", "application/json"),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        assert_eq!(resp.status, 406);
+        assert_eq!(header(&resp, "vary"), Some("Accept"));
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains("application/vnd.pypi.simple.v1+json"));
+    }
+
+    // -- metadata files (PEP 658 / PEP 714) --
+
+    /// test_artifacts, with core metadata stored for the requests wheel.
+    fn artifacts_with_metadata() -> Vec<Artifact> {
+        test_artifacts()
+            .into_iter()
+            .map(|metadata| {
+                let properties = if metadata.path == "requests-2.28.0-py3-none-any.whl" {
+                    PypiFormatHandler::extract_properties(
+                        metadata.path.clone(),
+                        wheel("requests", "2.28.0"),
+                    )
+                    .unwrap()
+                } else {
+                    Vec::new()
+                };
+                Artifact {
+                    metadata,
+                    properties,
+                }
+            })
+            .collect()
+    }
+
+    fn metadata_file_sha256() -> String {
+        let artifacts = artifacts_with_metadata();
+        let (_, file) = artifacts[0]
+            .properties
+            .iter()
+            .find(|(k, _)| k == METADATA_FILE_PROPERTY)
+            .unwrap();
+        sha256_hex(file.as_bytes())
+    }
+
+    #[test]
+    fn handle_request_serves_metadata_file() {
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/packages/requests-2.28.0-py3-none-any.whl.metadata"),
+            test_context(),
+            artifacts_with_metadata(),
+        )
+        .unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(sha256_hex(&resp.body), metadata_file_sha256());
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains("Requires-Dist: idna (<4,>=2.5)"));
+
+        // No metadata file for sdists or wheels stored without one
+        for path in [
+            "/packages/requests-2.28.0.tar.gz.metadata",
+            "/packages/numpy-1.24.2-cp311-cp311-manylinux_2_17_x86_64.whl.metadata",
+            "/packages/missing-1.0-py3-none-any.whl.metadata",
+        ] {
+            let resp = PypiFormatHandler::handle_request_with_properties(
+                get_request(path),
+                test_context(),
+                artifacts_with_metadata(),
+            )
+            .unwrap();
+            assert_eq!(resp.status, 404, "{path}");
+        }
+    }
+
+    #[test]
+    fn handle_request_project_page_links_metadata() {
+        let sha = metadata_file_sha256();
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/simple/requests/"),
+            test_context(),
+            artifacts_with_metadata(),
+        )
+        .unwrap();
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains(&format!(
+            "requests-2.28.0-py3-none-any.whl#sha256=abc123\" data-requires-python=\"&gt;=3.7\" \
+             data-core-metadata=\"sha256={sha}\" data-dist-info-metadata=\"sha256={sha}\">"
+        )));
+        assert!(body.contains("requests-2.28.0.tar.gz#sha256=def456\">"));
+
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            accept_request("/simple/requests/", "application/vnd.pypi.simple.v1+json"),
+            test_context(),
+            artifacts_with_metadata(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files[0]["core-metadata"]["sha256"], sha.as_str());
+        assert_eq!(files[0]["dist-info-metadata"]["sha256"], sha.as_str());
+        assert!(files[1].get("core-metadata").is_none());
+    }
+
+    // -- requires-python --
+
+    #[test]
+    fn handle_request_project_page_requires_python() {
+        let mut artifacts = artifacts_with_metadata();
+        let pkg_info = b"Metadata-Version: 1.2\nName: requests\nVersion: 2.28.0\n\
+                         Requires-Python: >=2.7, !=3.0.*, <4\n";
+        artifacts[1].properties = PypiFormatHandler::extract_properties(
+            "requests-2.28.0.tar.gz".into(),
+            build_tar_gz(&[("requests-2.28.0/PKG-INFO", pkg_info)]),
+        )
+        .unwrap();
+
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/simple/requests/"),
+            test_context(),
+            artifacts,
+        )
+        .unwrap();
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains(
+            "requests-2.28.0-py3-none-any.whl#sha256=abc123\" data-requires-python=\"&gt;=3.7\""
+        ));
+        assert!(body.contains(
+            "requests-2.28.0.tar.gz#sha256=def456\" \
+             data-requires-python=\"&gt;=2.7, !=3.0.*, &lt;4\">"
+        ));
+    }
+
+    #[test]
+    fn handle_request_json_requires_python() {
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            accept_request("/simple/requests/", "application/vnd.pypi.simple.v1+json"),
+            test_context(),
+            artifacts_with_metadata(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        assert_eq!(json["files"][0]["requires-python"], ">=3.7");
+        assert!(json["files"][1].get("requires-python").is_none());
+    }
+
+    #[test]
+    fn html_escape_attribute_values() {
+        assert_eq!(html_escape("<4, >=3\"&'"), "&lt;4, &gt;=3&quot;&amp;&#39;");
+    }
+
+    // -- yanking (PEP 592) --
+
+    fn yank_artifact(document: &str) -> Artifact {
+        let path = "yanks/requests.yank.json";
+        Artifact {
+            metadata: PypiFormatHandler::parse_metadata(path.into(), document.into()).unwrap(),
+            properties: PypiFormatHandler::extract_properties(path.into(), document.into())
+                .unwrap(),
+        }
+    }
+
+    fn artifacts_with_yanks(documents: &[&str]) -> Vec<Artifact> {
+        let mut artifacts = artifacts_with_metadata();
+        artifacts.extend(documents.iter().map(|d| yank_artifact(d)));
+        artifacts
+    }
+
+    #[test]
+    fn validate_checks_yank_documents() {
+        let path = "requests.yank.json";
+        let document = r#"{"project": "requests", "version": "2.28.0"}"#;
+        assert!(PypiFormatHandler::validate(path.into(), document.into()).is_ok());
+        let err = PypiFormatHandler::validate(path.into(), br#"{"project": "requests"}"#.to_vec())
+            .unwrap_err();
+        assert!(err.contains("version or files"), "{err}");
+
+        let meta = PypiFormatHandler::parse_metadata(path.into(), document.into()).unwrap();
+        assert_eq!(meta.content_type, "application/json");
+        assert_eq!(meta.version.as_deref(), Some("2.28.0"));
+    }
+
+    #[test]
+    fn handle_request_marks_yanked_version() {
+        let artifacts = artifacts_with_yanks(&[
+            r#"{"project": "Requests", "version": "2.28.0", "reason": "Breaks <proxies>"}"#,
+        ]);
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/simple/requests/"),
+            test_context(),
+            artifacts,
+        )
+        .unwrap();
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains("data-yanked=\"Breaks &lt;proxies&gt;\""));
+        assert_eq!(body.matches("data-yanked").count(), 2);
+        // The document isn't a distribution file
+        assert!(!body.contains("yank.json"));
+    }
+
+    #[test]
+    fn handle_request_marks_yanked_files() {
+        let artifacts = artifacts_with_yanks(&[
+            r#"{"project": "requests", "files": ["requests-2.28.0.tar.gz"]}"#,
+        ]);
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/simple/requests/"),
+            test_context(),
+            artifacts.clone(),
+        )
+        .unwrap();
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains("requests-2.28.0.tar.gz#sha256=def456\" data-yanked=\"\">"));
+        assert_eq!(body.matches("data-yanked").count(), 1);
+
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            accept_request("/simple/requests/", "application/vnd.pypi.simple.v1+json"),
+            test_context(),
+            artifacts.clone(),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        assert_eq!(json["files"][0]["yanked"], false);
+        assert_eq!(json["files"][1]["yanked"], true);
+
+        // Yanked files can still be downloaded
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            get_request("/packages/requests-2.28.0.tar.gz"),
+            test_context(),
+            artifacts,
+        )
+        .unwrap();
+        assert_eq!(resp.status, 302);
+    }
+
+    #[test]
+    fn handle_request_json_yank_reason() {
+        let artifacts = artifacts_with_yanks(&[
+            r#"{"project": "requests", "version": "2.28.0"}"#,
+            r#"{"project": "requests", "version": "2.28.0", "reason": "CVE-2023-32681"}"#,
+        ]);
+        let resp = PypiFormatHandler::handle_request_with_properties(
+            accept_request("/simple/requests/", "application/vnd.pypi.simple.v1+json"),
+            test_context(),
+            artifacts,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f["yanked"] == "CVE-2023-32681"));
+    }
+
+    #[test]
+    fn generate_index_lists_yanked_files() {
+        let artifacts = artifacts_with_yanks(&[
+            r#"{"project": "numpy", "version": "1.24.2", "reason": "bad build"}"#,
+        ]);
+        let result = PypiFormatHandler::generate_index_with_properties(artifacts)
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[1].1).unwrap();
+        assert_eq!(json["total_count"], 3);
+        let packages = json["packages"].as_array().unwrap();
+        assert!(packages[0].get("yanked").is_none());
+        assert_eq!(packages[2]["yanked"], "bad build");
+    }
+
+    // -- legacy upload (twine) --
+
+    /// What `twine upload` sends for the requests wheel of `version`.
+    fn twine_request(version: &str, data: &[u8], sha256: &str) -> HttpRequest {
+        let filename = format!("requests-{version}-py3-none-any.whl");
+        let fields = [
+            (":action", "file_upload"),
+            ("protocol_version", "1"),
+            ("metadata_version", "2.1"),
+            ("name", "Requests"),
+            ("version", version),
+            ("filetype", "bdist_wheel"),
+            ("pyversion", "py3"),
+            ("classifiers", "Programming Language :: Python :: 3"),
+            ("sha256_digest", sha256),
+        ];
+        HttpRequest {
+            method: "POST".to_string(),
+            path: "/".to_string(),
+            query: String::new(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                format!("multipart/form-data; boundary={BOUNDARY}"),
+            )],
+            body: upload_form(&fields, Some((&filename, data))),
+        }
+    }
+
+    #[test]
+    fn handle_upload_accepts_twine_upload() {
+        let data = wheel("requests", "2.29.0");
+        let request = twine_request("2.29.0", &data, &sha256_hex(&data));
+        let result =
+            PypiFormatHandler::handle_upload(request, test_context(), artifacts_with_metadata())
+                .unwrap();
+        assert_eq!(result.response.status, 200);
+        let file = result.file.unwrap();
+        assert_eq!(
+            file.path,
+            "packages/requests/2.29.0/requests-2.29.0-py3-none-any.whl"
+        );
+        assert_eq!(file.data, data);
+        // The host stores it through the usual upload path
+        assert!(PypiFormatHandler::validate(file.path, file.data).is_ok());
+    }
+
+    #[test]
+    fn handle_upload_rejects_bad_uploads() {
+        let data = wheel("requests", "2.29.0");
+        let result = PypiFormatHandler::handle_upload(
+            twine_request("2.29.0", &data, &"0".repeat(64)),
+            test_context(),
+            artifacts_with_metadata(),
+        )
+        .unwrap();
+        assert!(result.file.is_none());
+        assert_eq!(result.response.status, 400);
+        let body = String::from_utf8(result.response.body).unwrap();
+        assert!(body.contains("sha256_digest"), "{body}");
+
+        // Existing files are refused
+        let data = wheel("requests", "2.28.0");
+        let request = twine_request("2.28.0", &data, &sha256_hex(&data));
+        let result =
+            PypiFormatHandler::handle_upload(request, test_context(), artifacts_with_metadata())
+                .unwrap();
+        assert!(result.file.is_none());
+        assert_eq!(result.response.status, 400);
+        let body = String::from_utf8(result.response.body).unwrap();
+        assert!(body.contains("File already exists"), "{body}");
+
+        // The version can't climb out of the project's directory
+        let data = wheel("requests", "..");
+        let request = twine_request("..", &data, &sha256_hex(&data));
+        let result =
+            PypiFormatHandler::handle_upload(request, test_context(), artifacts_with_metadata())
+                .unwrap();
+        assert!(result.file.is_none());
+        assert_eq!(result.response.status, 400);
+    }
+
+    #[test]
+    fn handle_upload_stores_under_metadata_version() {
+        // The form and filename may differ in case from the wheel's METADATA
+        let data = wheel("requests", "2.29.0rc1");
+        let request = twine_request("2.29.0RC1", &data, &sha256_hex(&data));
+        let result = PypiFormatHandler::handle_upload(request, test_context(), Vec::new()).unwrap();
+        assert_eq!(result.response.status, 200);
+        assert_eq!(
+            result.file.unwrap().path,
+            "packages/requests/2.29.0rc1/requests-2.29.0RC1-py3-none-any.whl"
+        );
+    }
+
+    #[test]
+    fn handle_upload_routes() {
+        let data = wheel("requests", "2.29.0");
+        let request = twine_request("2.29.0", &data, &sha256_hex(&data));
+
+        let wrong_path = HttpRequest {
+            path: "/simple/".to_string(),
+            ..request.clone()
+        };
+        let result =
+            PypiFormatHandler::handle_upload(wrong_path, test_context(), Vec::new()).unwrap();
+        assert!(result.file.is_none());
+        assert_eq!(result.response.status, 404);
+
+        let wrong_method = HttpRequest {
+            method: "PUT".to_string(),
+            ..request.clone()
+        };
+        let result =
+            PypiFormatHandler::handle_upload(wrong_method, test_context(), Vec::new()).unwrap();
+        assert_eq!(result.response.status, 405);
+
+        let not_form = HttpRequest {
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            ..request
+        };
+        let result =
+            PypiFormatHandler::handle_upload(not_form, test_context(), Vec::new()).unwrap();
+        assert_eq!(result.response.status, 400);
+    }
+
     #[test]
     fn handle_request_post_rejected() {
         let req = HttpRequest {
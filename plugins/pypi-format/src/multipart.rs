@@ -0,0 +1,186 @@
+//! `multipart/form-data` request bodies (RFC 7578).
+//!
+//! The body is a sequence of parts separated by `--{boundary}` lines and ended by
+//! `--{boundary}--`. Each part has its own headers; `Content-Disposition: form-data`
+//! names the field and, for file parts, the uploaded filename.
+
+/// One field of a form.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Part<'a> {
+    pub(crate) name: String,
+    pub(crate) filename: Option<String>,
+    pub(crate) data: &'a [u8],
+}
+
+/// The boundary parameter of a `multipart/form-data` content type, or `None` if the
+/// content type is anything else.
+pub(crate) fn form_boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';');
+    let media_type = params.next()?.trim();
+    if !media_type.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .filter_map(|p| p.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+        .filter(|b| !b.is_empty())
+}
+
+/// Split a form body into its parts, in order.
+pub(crate) fn parse_form<'a>(body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, String> {
+    let delimiter = format!("--{boundary}").into_bytes();
+    let mut rest = match find(body, &delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return Err("missing multipart boundary".to_string()),
+    };
+    // Each part's body ends at CRLF followed by the next delimiter
+    let separator = [b"\r\n".as_slice(), &delimiter].concat();
+
+    let mut parts = Vec::new();
+    loop {
+        if rest.starts_with(b"--") {
+            return Ok(parts);
+        }
+        rest = rest
+            .strip_prefix(b"\r\n")
+            .ok_or("malformed multipart boundary line")?;
+
+        let header_end = find(rest, b"\r\n\r\n").ok_or("unterminated multipart part headers")?;
+        let headers =
+            std::str::from_utf8(&rest[..header_end]).map_err(|_| "invalid multipart headers")?;
+        let content = &rest[header_end + 4..];
+        let end = find(content, &separator).ok_or("unterminated multipart part")?;
+
+        let (name, filename) = content_disposition(headers)?;
+        parts.push(Part {
+            name,
+            filename,
+            data: &content[..end],
+        });
+        rest = &content[end + separator.len()..];
+    }
+}
+
+/// The field name and filename from a part's `Content-Disposition` header.
+fn content_disposition(headers: &str) -> Result<(String, Option<String>), String> {
+    let value = headers
+        .split("\r\n")
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("content-disposition"))
+        .map(|(_, value)| value)
+        .ok_or("multipart part without Content-Disposition")?;
+
+    let mut name = None;
+    let mut filename = None;
+    for param in split_params(value).into_iter().skip(1) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        let value = unquote(value.trim());
+        match key.trim().to_ascii_lowercase().as_str() {
+            "name" => name = Some(value),
+            "filename" => filename = Some(value),
+            _ => {}
+        }
+    }
+    let name = name.ok_or("multipart part without a field name")?;
+    Ok((name, filename))
+}
+
+/// Split a header value on `;`, leaving semicolons inside quoted strings alone.
+fn split_params(value: &str) -> Vec<&str> {
+    let mut params = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(&value[start..]);
+    params
+}
+
+/// Remove the quotes and backslash escapes from a quoted string.
+fn unquote(value: &str) -> String {
+    let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value.to_string();
+    };
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            _ => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_boundary() {
+        assert_eq!(
+            form_boundary("multipart/form-data; boundary=abc123"),
+            Some("abc123")
+        );
+        assert_eq!(
+            form_boundary("Multipart/Form-Data; charset=utf-8; boundary=\"a b\""),
+            Some("a b")
+        );
+        assert_eq!(form_boundary("multipart/form-data"), None);
+        assert_eq!(form_boundary("application/json; boundary=x"), None);
+    }
+
+    #[test]
+    fn parses_fields_and_files() {
+        let body = b"preamble\r\n--XyZ\r\n\
+            Content-Disposition: form-data; name=\":action\"\r\n\r\n\
+            file_upload\r\n--XyZ\r\n\
+            Content-Disposition: form-data; name=\"classifiers\"\r\n\r\n\
+            A :: B\r\n--XyZ\r\n\
+            Content-Disposition: form-data; name=\"content\"; filename=\"a;b \\\"c\\\".whl\"\r\n\
+            Content-Type: application/octet-stream\r\n\r\n\
+            PK\x03\x04\r\n--X\r\n--XyZ--\r\n";
+        let parts = parse_form(body, "XyZ").unwrap();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0].name, ":action");
+        assert_eq!(parts[0].filename, None);
+        assert_eq!(parts[0].data, b"file_upload");
+        assert_eq!(parts[1].data, b"A :: B");
+        assert_eq!(parts[2].name, "content");
+        assert_eq!(parts[2].filename.as_deref(), Some("a;b \"c\".whl"));
+        // Text that merely looks like a boundary is kept
+        assert_eq!(parts[2].data, b"PK\x03\x04\r\n--X");
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        assert!(parse_form(b"no boundary here", "XyZ").is_err());
+        assert!(parse_form(
+            b"--XyZ\r\nContent-Disposition: form-data\r\n\r\nx\r\n--XyZ--",
+            "XyZ"
+        )
+        .unwrap_err()
+        .contains("field name"));
+        assert!(parse_form(
+            b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nunterminated",
+            "XyZ"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! PEP 691 content negotiation for the Simple Repository API.
+//!
+//! The simple pages can be served as JSON (`application/vnd.pypi.simple.v1+json`) or as
+//! HTML, either under the versioned `application/vnd.pypi.simple.v1+html` type or the
+//! legacy `text/html`. The client states its preferences in `Accept` with q-values; the
+//! `latest` aliases name the current API version, and responses always carry the
+//! concrete versioned type.
+//!
+//! See <https://peps.python.org/pep-0691/#version-format-selection>.
+
+pub(crate) const JSON_V1: &str = "application/vnd.pypi.simple.v1+json";
+pub(crate) const HTML_V1: &str = "application/vnd.pypi.simple.v1+html";
+pub(crate) const HTML_LEGACY: &str = "text/html";
+
+/// A representation of a simple page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SimpleFormat {
+    Json,
+    HtmlV1,
+    Html,
+}
+
+impl SimpleFormat {
+    /// Formats in the order preferred when the client ranks them equally; legacy HTML
+    /// comes first so clients that don't know PEP 691 keep getting what they always did.
+    const ALL: [SimpleFormat; 3] = [SimpleFormat::Html, SimpleFormat::HtmlV1, SimpleFormat::Json];
+
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            SimpleFormat::Json => JSON_V1,
+            SimpleFormat::HtmlV1 => HTML_V1,
+            SimpleFormat::Html => HTML_LEGACY,
+        }
+    }
+
+    /// The types an `Accept` media type may name this format by.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            SimpleFormat::Json => &[JSON_V1, "application/vnd.pypi.simple.latest+json"],
+            SimpleFormat::HtmlV1 => &[HTML_V1, "application/vnd.pypi.simple.latest+html"],
+            SimpleFormat::Html => &[HTML_LEGACY],
+        }
+    }
+}
+
+/// A media range from an `Accept` header.
+struct MediaRange<'a> {
+    media_type: &'a str,
+    quality: f32,
+}
+
+impl MediaRange<'_> {
+    /// How specifically this range names `format`: 3 for the exact type, 2 for `type/*`,
+    /// 1 for `*/*`, or `None` if it doesn't match.
+    fn specificity(&self, format: SimpleFormat) -> Option<u8> {
+        let mt = self.media_type;
+        if format.aliases().iter().any(|a| a.eq_ignore_ascii_case(mt)) {
+            return Some(3);
+        }
+        if mt == "*/*" {
+            return Some(1);
+        }
+        let major = mt.strip_suffix("/*")?;
+        let (format_major, _) = format.content_type().split_once('/')?;
+        major.eq_ignore_ascii_case(format_major).then_some(2)
+    }
+}
+
+/// Pick the format to serve for an `Accept` header, or `None` if the client accepts
+/// none of them (a 406).
+///
+/// Each format takes the q-value of the most specific range that matches it. The
+/// highest q-value wins; ties go to the format the client listed first, then to
+/// [`SimpleFormat::ALL`] order. A missing or empty header accepts anything.
+pub(crate) fn negotiate(accept: Option<&str>) -> Option<SimpleFormat> {
+    let accept = accept
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .unwrap_or("*/*");
+    let ranges: Vec<MediaRange> = accept.split(',').filter_map(parse_range).collect();
+
+    let mut best: Option<(SimpleFormat, f32, usize)> = None;
+    for format in SimpleFormat::ALL {
+        // The most specific matching range decides, even when its q-value is lower
+        let Some((position, range)) = ranges
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| Some((i, r, r.specificity(format)?)))
+            .max_by_key(|&(i, _, specificity)| (specificity, std::cmp::Reverse(i)))
+            .map(|(i, r, _)| (i, r))
+        else {
+            continue;
+        };
+        if range.quality <= 0.0 {
+            continue;
+        }
+        let better = match best {
+            None => true,
+            Some((_, quality, best_position)) => {
+                range.quality > quality || (range.quality == quality && position < best_position)
+            }
+        };
+        if better {
+            best = Some((format, range.quality, position));
+        }
+    }
+    best.map(|(format, _, _)| format)
+}
+
+/// Parse `type/subtype; q=0.5; other=param`. Malformed ranges are ignored.
+fn parse_range(range: &str) -> Option<MediaRange<'_>> {
+    let mut parts = range.split(';');
+    let media_type = parts.next()?.trim();
+    if !media_type.contains('/') {
+        return None;
+    }
+    let mut quality = 1.0;
+    for param in parts {
+        if let Some((key, value)) = param.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("q") {
+                quality = value.trim().parse::<f32>().ok()?.clamp(0.0, 1.0);
+            }
+        }
+    }
+    Some(MediaRange {
+        media_type,
+        quality,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_to_legacy_html() {
+        assert_eq!(negotiate(None), Some(SimpleFormat::Html));
+        assert_eq!(negotiate(Some("")), Some(SimpleFormat::Html));
+        assert_eq!(negotiate(Some("*/*")), Some(SimpleFormat::Html));
+        assert_eq!(
+            negotiate(Some("text/html,application/xhtml+xml,*/*;q=0.8")),
+            Some(SimpleFormat::Html)
+        );
+    }
+
+    #[test]
+    fn honours_q_values() {
+        // What pip sends
+        let pip = "application/vnd.pypi.simple.v1+json, \
+                   application/vnd.pypi.simple.v1+html; q=0.1, text/html; q=0.01";
+        assert_eq!(negotiate(Some(pip)), Some(SimpleFormat::Json));
+        assert_eq!(
+            negotiate(Some("application/vnd.pypi.simple.v1+json;q=0.2, text/html")),
+            Some(SimpleFormat::Html)
+        );
+        assert_eq!(
+            negotiate(Some("application/vnd.pypi.simple.latest+html")),
+            Some(SimpleFormat::HtmlV1)
+        );
+        assert_eq!(
+            negotiate(Some("application/*;q=0.5, text/html;q=0")),
+            Some(SimpleFormat::HtmlV1)
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_first_listed() {
+        assert_eq!(
+            negotiate(Some(
+                "application/vnd.pypi.simple.latest+json, application/vnd.pypi.simple.v1+html"
+            )),
+            Some(SimpleFormat::Json)
+        );
+        assert_eq!(
+            negotiate(Some(
+                "application/vnd.pypi.simple.v1+html, application/vnd.pypi.simple.v1+json"
+            )),
+            Some(SimpleFormat::HtmlV1)
+        );
+    }
+
+    #[test]
+    fn rejects_unacceptable() {
+        assert_eq!(negotiate(Some("application/json")), None);
+        assert_eq!(negotiate(Some("application/vnd.pypi.simple.v2+json")), None);
+        assert_eq!(negotiate(Some("text/html;q=0, */*;q=0")), None);
+        // A more specific q=0 excludes a format a wildcard would accept
+        assert_eq!(
+            negotiate(Some("*/*, text/html;q=0, application/*;q=0")),
+            None
+        );
+    }
+}
@@ -0,0 +1,390 @@
+//! Source distribution archives.
+//!
+//! An sdist is a `.tar.gz` (or, for older ones, a `.zip`) holding a single top-level
+//! `{name}-{version}/` directory, with the distribution's core metadata in
+//! `{name}-{version}/PKG-INFO`. Only that file is read: gzip is inflated with
+//! `miniz_oxide` a chunk at a time and the tar stream walked header by header as it
+//! arrives, following ustar prefixes, GNU long names, and pax `path` records. Inflating
+//! stops at the first top-level `PKG-INFO`, or once `MAX_TAR_SIZE` bytes have come out
+//! without one, since plugins have little memory and fuel to spare.
+//!
+//! See <https://packaging.python.org/en/latest/specifications/source-distribution-format/>.
+
+use miniz_oxide::inflate::stream::{inflate, InflateState};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+
+use crate::zip::ZipArchive;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const GZIP_DEFLATE: u8 = 8;
+const GZIP_FHCRC: u8 = 0x02;
+const GZIP_FEXTRA: u8 = 0x04;
+const GZIP_FNAME: u8 = 0x08;
+const GZIP_FCOMMENT: u8 = 0x10;
+
+/// How much of an sdist's tar stream is inflated looking for `PKG-INFO` before giving up.
+const MAX_TAR_SIZE: usize = 4 * 1024 * 1024;
+/// How much is inflated between looks at the tar stream.
+const INFLATE_CHUNK: usize = 64 * 1024;
+
+const TAR_BLOCK: usize = 512;
+
+/// Read the top-level `PKG-INFO` out of a `.tar.gz` or `.zip` sdist.
+///
+/// Returns `None` when the archive has no such file, or none in its first `MAX_TAR_SIZE`
+/// bytes.
+pub(crate) fn sdist_pkg_info(filename: &str, data: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let lower = filename.to_lowercase();
+    if lower.ends_with(".tar.gz") {
+        tar_gz_pkg_info(data)
+    } else if lower.ends_with(".zip") {
+        let archive = ZipArchive::parse(data)?;
+        let path = archive
+            .names()
+            .find(|name| is_pkg_info(name))
+            .map(str::to_string);
+        match path {
+            Some(path) => archive.read(&path),
+            None => Ok(None),
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+/// Whether `path` is `{dir}/PKG-INFO` directly under the archive's top-level directory.
+fn is_pkg_info(path: &str) -> bool {
+    let path = path.strip_prefix("./").unwrap_or(path);
+    path.strip_suffix("/PKG-INFO")
+        .is_some_and(|dir| !dir.is_empty() && !dir.contains('/'))
+}
+
+/// Inflate a `.tar.gz` until its top-level `PKG-INFO` has been read.
+fn tar_gz_pkg_info(data: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let mut input = gzip_body(data)?;
+    let mut inflater = InflateState::new_boxed(DataFormat::Raw);
+    let mut tar = Vec::new();
+    let mut reader = TarReader::default();
+
+    loop {
+        let len = tar.len();
+        if len >= MAX_TAR_SIZE {
+            return Ok(None);
+        }
+        tar.resize(MAX_TAR_SIZE.min(len + INFLATE_CHUNK), 0);
+        let result = inflate(&mut inflater, input, &mut tar[len..], MZFlush::None);
+        input = &input[result.bytes_consumed..];
+        tar.truncate(len + result.bytes_written);
+        let stalled = result.bytes_consumed == 0 && result.bytes_written == 0;
+        let ended = match result.status {
+            Ok(MZStatus::StreamEnd) => true,
+            Ok(_) if !stalled => false,
+            Ok(_) | Err(MZError::Buf) => return Err("gzip stream is truncated".to_string()),
+            Err(e) => return Err(format!("gzip stream failed to decompress: {e:?}")),
+        };
+
+        match reader.next_pkg_info(&tar)? {
+            Some(TarScan::Found(pkg_info)) => return Ok(Some(pkg_info)),
+            Some(TarScan::End) => return Ok(None),
+            None if ended => return Err("tar archive is truncated".to_string()),
+            None => {}
+        }
+    }
+}
+
+/// The DEFLATE stream of a single-member gzip file, after its header.
+///
+/// The trailing CRC-32 and size aren't checked: inflating usually stops well before the
+/// end of the stream.
+fn gzip_body(data: &[u8]) -> Result<&[u8], String> {
+    if data.len() < 18 || data[..2] != GZIP_MAGIC || data[2] != GZIP_DEFLATE {
+        return Err("not a gzip file".to_string());
+    }
+    let flags = data[3];
+    let mut offset = 10;
+    if flags & GZIP_FEXTRA != 0 {
+        let len = data
+            .get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or("truncated gzip header")?;
+        offset += 2 + len;
+    }
+    for flag in [GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            let end = data
+                .get(offset..)
+                .and_then(|rest| rest.iter().position(|&b| b == 0))
+                .ok_or("truncated gzip header")?;
+            offset += end + 1;
+        }
+    }
+    if flags & GZIP_FHCRC != 0 {
+        offset += 2;
+    }
+    data.get(offset..data.len() - 8)
+        .ok_or_else(|| "truncated gzip header".to_string())
+}
+
+/// Where a walk of a tar stream stopped.
+#[derive(Debug, PartialEq, Eq)]
+enum TarScan {
+    /// The content of the top-level `PKG-INFO`.
+    Found(Vec<u8>),
+    /// The end-of-archive marker, without a `PKG-INFO` before it.
+    End,
+}
+
+/// A walk of a tar stream that may not have fully arrived yet.
+#[derive(Default)]
+struct TarReader {
+    /// Offset of the next header.
+    offset: usize,
+    /// A path set by a GNU long name or pax header for the entry that follows.
+    next_path: Option<String>,
+}
+
+impl TarReader {
+    /// Walk on through `tar`, which must extend what was passed before.
+    ///
+    /// Returns `None` when `tar` ends partway through an entry.
+    fn next_pkg_info(&mut self, tar: &[u8]) -> Result<Option<TarScan>, String> {
+        while let Some(header) = tar.get(self.offset..self.offset + TAR_BLOCK) {
+            if header.iter().all(|&b| b == 0) {
+                return Ok(Some(TarScan::End));
+            }
+            let size = octal(&header[124..136]).ok_or("corrupt tar header size")?;
+            let start = self.offset + TAR_BLOCK;
+            let next = size
+                .div_ceil(TAR_BLOCK)
+                .checked_mul(TAR_BLOCK)
+                .and_then(|padded| start.checked_add(padded))
+                .ok_or("corrupt tar header size")?;
+            let Some(content) = tar.get(start..start + size) else {
+                return Ok(None);
+            };
+            self.offset = next;
+
+            match header[156] {
+                // GNU long name for the next entry
+                b'L' => {
+                    self.next_path = Some(text(content).to_string());
+                    continue;
+                }
+                // pax extended header for the next entry
+                b'x' => {
+                    if let Some(path) = pax_path(content) {
+                        self.next_path = Some(path);
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let path = self.next_path.take().unwrap_or_else(|| {
+                let name = text(&header[..100]);
+                let prefix = if &header[257..262] == b"ustar" {
+                    text(&header[345..500])
+                } else {
+                    ""
+                };
+                if prefix.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{prefix}/{name}")
+                }
+            });
+            let is_file = matches!(header[156], b'0' | 0);
+            if is_file && is_pkg_info(&path) {
+                return Ok(Some(TarScan::Found(content.to_vec())));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The `path` record of a pax extended header: `"{len} path={value}\n"`.
+fn pax_path(records: &[u8]) -> Option<String> {
+    let mut rest = records;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|&b| b == b' ')?;
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?;
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(value) = record.strip_prefix(b"path=") {
+            return String::from_utf8(value.to_vec()).ok();
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
+/// A NUL-terminated header field.
+fn text(field: &[u8]) -> &str {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    std::str::from_utf8(&field[..end]).unwrap_or("")
+}
+
+/// An octal header number, padded with spaces or NULs.
+fn octal(field: &[u8]) -> Option<usize> {
+    let digits = text(field).trim_matches(' ');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    usize::from_str_radix(digits, 8).ok()
+}
+
+/// Building archives for tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use crate::zip::crc32;
+
+    /// Build a `.tar.gz` holding `entries` as regular files.
+    pub(crate) fn build_tar_gz(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = Vec::new();
+        for (name, content) in entries {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[100..107].copy_from_slice(b"0000644");
+            header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(content);
+            tar.resize(tar.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        }
+        tar.resize(tar.len() + 2 * TAR_BLOCK, 0);
+
+        let mut gz = vec![0x1f, 0x8b, GZIP_DEFLATE, 0, 0, 0, 0, 0, 0, 0xff];
+        gz.extend_from_slice(&miniz_oxide::deflate::compress_to_vec(&tar, 6));
+        gz.extend_from_slice(&crc32(&tar).to_le_bytes());
+        gz.extend_from_slice(&(tar.len() as u32).to_le_bytes());
+        gz
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::build_tar_gz;
+    use super::*;
+    use crate::zip::testing::build_zip;
+
+    #[test]
+    fn reads_pkg_info_from_tar_gz() {
+        let sdist = build_tar_gz(&[
+            ("requests-2.28.0/setup.py", b"setup()\n"),
+            ("requests-2.28.0/requests.egg-info/PKG-INFO", b"nested"),
+            ("requests-2.28.0/PKG-INFO", b"Metadata-Version: 2.1\n"),
+        ]);
+        assert_eq!(
+            sdist_pkg_info("requests-2.28.0.tar.gz", &sdist).unwrap(),
+            Some(b"Metadata-Version: 2.1\n".to_vec())
+        );
+
+        let sdist = build_tar_gz(&[("requests-2.28.0/setup.py", b"setup()\n")]);
+        assert_eq!(
+            sdist_pkg_info("requests-2.28.0.tar.gz", &sdist).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_pkg_info_from_zip() {
+        let sdist = build_zip(&[("pkg-1.0/PKG-INFO", b"Metadata-Version: 1.0\n")]);
+        assert_eq!(
+            sdist_pkg_info("pkg-1.0.zip", &sdist).unwrap(),
+            Some(b"Metadata-Version: 1.0\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn follows_long_names() {
+        let long_dir = format!("{}-1.0", "a".repeat(120));
+        let mut header = [0u8; TAR_BLOCK];
+        header[..13].copy_from_slice(b"././@LongLink");
+        let long_name = format!("{long_dir}/PKG-INFO");
+        header[124..135].copy_from_slice(format!("{:011o}", long_name.len()).as_bytes());
+        header[156] = b'L';
+        let mut tar = header.to_vec();
+        tar.extend_from_slice(long_name.as_bytes());
+        tar.resize(2 * TAR_BLOCK, 0);
+        let mut header = [0u8; TAR_BLOCK];
+        header[..10].copy_from_slice(b"truncated/");
+        header[124..135].copy_from_slice(b"00000000002");
+        header[156] = b'0';
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(b"ok");
+        tar.resize(4 * TAR_BLOCK, 0);
+        assert_eq!(
+            TarReader::default().next_pkg_info(&tar).unwrap(),
+            Some(TarScan::Found(b"ok".to_vec()))
+        );
+
+        assert_eq!(
+            pax_path(b"30 mtime=1700000000.123456789\n23 path=pkg-1/PKG-INFO\n"),
+            Some("pkg-1/PKG-INFO".to_string())
+        );
+    }
+
+    #[test]
+    fn walks_tar_as_it_arrives() {
+        let mut tar = Vec::new();
+        for (name, content) in [
+            ("pkg-1.0/setup.py", "setup()\n"),
+            ("pkg-1.0/PKG-INFO", "ok"),
+        ] {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+            header[156] = b'0';
+            tar.extend_from_slice(&header);
+            tar.extend_from_slice(content.as_bytes());
+            tar.resize(tar.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        }
+
+        let mut reader = TarReader::default();
+        assert_eq!(reader.next_pkg_info(&tar[..TAR_BLOCK + 3]).unwrap(), None);
+        assert_eq!(reader.next_pkg_info(&tar[..3 * TAR_BLOCK]).unwrap(), None);
+        assert_eq!(
+            reader.next_pkg_info(&tar).unwrap(),
+            Some(TarScan::Found(b"ok".to_vec()))
+        );
+
+        tar.truncate(2 * TAR_BLOCK);
+        tar.resize(4 * TAR_BLOCK, 0);
+        assert_eq!(
+            TarReader::default().next_pkg_info(&tar).unwrap(),
+            Some(TarScan::End)
+        );
+    }
+
+    #[test]
+    fn gives_up_past_max_tar_size() {
+        let padding = vec![0; MAX_TAR_SIZE];
+        let sdist = build_tar_gz(&[
+            ("pkg-1.0/data.bin", &padding),
+            ("pkg-1.0/PKG-INFO", b"Metadata-Version: 2.1\n"),
+        ]);
+        assert_eq!(sdist_pkg_info("pkg-1.0.tar.gz", &sdist).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_oversized_zip_pkg_info() {
+        let sdist = build_zip(&[("pkg-1.0/PKG-INFO", &vec![b'A'; 2 * 1024 * 1024])]);
+        let err = sdist_pkg_info("pkg-1.0.zip", &sdist).unwrap_err();
+        assert!(err.contains("too large"), "{err}");
+    }
+
+    #[test]
+    fn rejects_corrupt_gzip() {
+        let err = sdist_pkg_info("pkg-1.0.tar.gz", b"not gzip at all, really").unwrap_err();
+        assert!(err.contains("not a gzip file"), "{err}");
+
+        let sdist = build_tar_gz(&[("pkg-1.0/setup.py", b"setup()\n")]);
+        let mut truncated = sdist[..sdist.len() / 2].to_vec();
+        truncated.extend_from_slice(&sdist[sdist.len() - 8..]);
+        let err = sdist_pkg_info("pkg-1.0.tar.gz", &truncated).unwrap_err();
+        assert!(err.contains("truncated"), "{err}");
+    }
+}
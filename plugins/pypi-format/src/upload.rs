@@ -0,0 +1,231 @@
+//! The legacy upload API, as `twine upload` speaks it.
+//!
+//! An upload is a `multipart/form-data` POST with `:action=file_upload`,
+//! `protocol_version=1`, the distribution's `name` and `version`, its digests, and the
+//! file itself in the `content` part. Further core metadata fields (`summary`,
+//! `requires_python`, ...) are sent too, but the plugin reads those from the file.
+//!
+//! See <https://docs.pypi.org/api/upload/>.
+
+use crate::core_metadata::{is_valid_name, is_valid_version};
+use crate::multipart::{form_boundary, parse_form, Part};
+use crate::{extract_package_name, extract_version, normalize_package_name, sha256_hex};
+
+/// A distribution file accepted from an upload form.
+#[derive(Debug)]
+pub(crate) struct LegacyUpload<'a> {
+    pub(crate) name: String,
+    pub(crate) filename: String,
+    pub(crate) content: &'a [u8],
+}
+
+/// Parse and check an upload form.
+///
+/// The `sha256_digest` field must match the file, and the file's name must agree with
+/// the `name` and `version` fields.
+pub(crate) fn parse_legacy_upload<'a>(
+    content_type: Option<&str>,
+    body: &'a [u8],
+) -> Result<LegacyUpload<'a>, String> {
+    let boundary = content_type
+        .and_then(form_boundary)
+        .ok_or("Expected a multipart/form-data upload")?;
+    let parts = parse_form(body, boundary).map_err(|e| format!("Invalid upload form: {e}"))?;
+    let field = |name: &str| field(&parts, name);
+
+    match field(":action") {
+        Some("file_upload") => {}
+        Some(action) => return Err(format!("Unsupported :action '{action}'")),
+        None => return Err("Missing :action field".to_string()),
+    }
+    match field("protocol_version") {
+        Some("1") => {}
+        Some(version) => return Err(format!("Unsupported protocol_version '{version}'")),
+        None => return Err("Missing protocol_version field".to_string()),
+    }
+
+    let name = field("name").ok_or("Missing name field")?;
+    if !is_valid_name(name) {
+        return Err(format!("Invalid project name '{name}'"));
+    }
+    let version = field("version").ok_or("Missing version field")?;
+    if !is_valid_version(version) {
+        return Err(format!("Invalid version '{version}'"));
+    }
+
+    let file = parts
+        .iter()
+        .find(|p| p.name == "content" && p.filename.is_some())
+        .ok_or("Missing file in content field")?;
+    let filename = file.filename.as_deref().unwrap_or_default();
+    if filename.is_empty() || filename.contains(['/', '\\']) {
+        return Err(format!("Invalid filename '{filename}'"));
+    }
+    if file.data.is_empty() {
+        return Err("Uploaded file is empty".to_string());
+    }
+
+    let expected = field("sha256_digest").ok_or("Missing sha256_digest field")?;
+    let actual = sha256_hex(file.data);
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "sha256_digest {expected} doesn't match the uploaded file ({actual})"
+        ));
+    }
+
+    let file_name = extract_package_name(filename).unwrap_or_default();
+    if normalize_package_name(&file_name) != normalize_package_name(name) {
+        return Err(format!(
+            "Filename '{filename}' doesn't match the project name '{name}'"
+        ));
+    }
+    let file_version = extract_version(filename).unwrap_or_default();
+    if !file_version.eq_ignore_ascii_case(&version.replace('-', "_")) {
+        return Err(format!(
+            "Filename '{filename}' doesn't match the version '{version}'"
+        ));
+    }
+
+    Ok(LegacyUpload {
+        name: name.to_string(),
+        filename: filename.to_string(),
+        content: file.data,
+    })
+}
+
+/// The first non-file field named `name`, trimmed.
+fn field<'a>(parts: &'a [Part], name: &str) -> Option<&'a str> {
+    parts
+        .iter()
+        .find(|p| p.name == name && p.filename.is_none())
+        .and_then(|p| std::str::from_utf8(p.data).ok())
+        .map(str::trim)
+}
+
+/// Building upload forms for tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    pub(crate) const BOUNDARY: &str = "----twine-boundary";
+
+    /// A `multipart/form-data` body with `fields` and, if given, a `content` file part.
+    pub(crate) fn upload_form(fields: &[(&str, &str)], file: Option<(&str, &[u8])>) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!(
+                    "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n\
+                     {value}\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        if let Some((filename, content)) = file {
+            body.extend_from_slice(
+                format!(
+                    "--{BOUNDARY}\r\nContent-Disposition: form-data; name=\"content\"; \
+                     filename=\"{filename}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(content);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{upload_form, BOUNDARY};
+    use super::*;
+
+    const FILENAME: &str = "my_package-1.0+local_1-py3-none-any.whl";
+    const CONTENT: &[u8] = b"PK\x03\x04 wheel";
+
+    fn content_type() -> String {
+        format!("multipart/form-data; boundary={BOUNDARY}")
+    }
+
+    fn fields(sha256: &str) -> Vec<(&'static str, String)> {
+        vec![
+            (":action", "file_upload".to_string()),
+            ("protocol_version", "1".to_string()),
+            ("name", "My.Package".to_string()),
+            ("version", "1.0+local-1".to_string()),
+            ("filetype", "bdist_wheel".to_string()),
+            ("sha256_digest", sha256.to_string()),
+        ]
+    }
+
+    fn upload(fields: &[(&str, String)], file: Option<(&str, &[u8])>) -> Result<String, String> {
+        let fields: Vec<(&str, &str)> = fields.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let body = upload_form(&fields, file);
+        parse_legacy_upload(Some(&content_type()), &body).map(|u| u.filename)
+    }
+
+    #[test]
+    fn accepts_twine_upload() {
+        let body = upload_form(
+            &fields(&sha256_hex(CONTENT))
+                .iter()
+                .map(|(k, v)| (*k, v.as_str()))
+                .collect::<Vec<_>>(),
+            Some((FILENAME, CONTENT)),
+        );
+        let upload = parse_legacy_upload(Some(&content_type()), &body).unwrap();
+        assert_eq!(upload.name, "My.Package");
+        assert_eq!(upload.filename, FILENAME);
+        assert_eq!(upload.content, CONTENT);
+    }
+
+    #[test]
+    fn rejects_invalid_forms() {
+        let sha = sha256_hex(CONTENT);
+        // The form with `key` replaced by `value`, or removed
+        let with = |key: &'static str, value: Option<&str>| {
+            let mut f = fields(&sha);
+            f.retain(|(k, _)| *k != key);
+            f.extend(value.map(|v| (key, v.to_string())));
+            f
+        };
+        let zeros = "0".repeat(64);
+        let cases = [
+            (with(":action", Some("submit")), "Unsupported :action"),
+            (with(":action", None), "Missing :action"),
+            (
+                with("protocol_version", Some("2")),
+                "Unsupported protocol_version",
+            ),
+            (with("name", None), "Missing name"),
+            (with("name", Some("-bad")), "Invalid project name"),
+            (
+                with("name", Some("other")),
+                "doesn't match the project name",
+            ),
+            (with("version", Some("1.1")), "doesn't match the version"),
+            (with("version", Some("..")), "Invalid version"),
+            (with("version", Some("1.0/..")), "Invalid version"),
+            (with("version", Some("1.0\\x")), "Invalid version"),
+            (with("sha256_digest", None), "Missing sha256_digest"),
+            (
+                with("sha256_digest", Some(&zeros)),
+                "doesn't match the uploaded file",
+            ),
+        ];
+        for (fields, expected) in cases {
+            let err = upload(&fields, Some((FILENAME, CONTENT))).unwrap_err();
+            assert!(err.contains(expected), "{expected}: {err}");
+        }
+
+        let err = upload(&fields(&sha), None).unwrap_err();
+        assert!(err.contains("Missing file"), "{err}");
+        let err = upload(&fields(&sha), Some(("../evil.whl", CONTENT))).unwrap_err();
+        assert!(err.contains("Invalid filename"), "{err}");
+
+        let err = parse_legacy_upload(Some("application/json"), b"{}").unwrap_err();
+        assert!(err.contains("multipart/form-data"), "{err}");
+        let err = parse_legacy_upload(Some(&content_type()), b"garbage").unwrap_err();
+        assert!(err.contains("Invalid upload form"), "{err}");
+    }
+}
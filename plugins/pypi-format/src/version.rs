@@ -0,0 +1,177 @@
+//! Version normalization (PEP 440).
+//!
+//! The same release can be spelled many ways: `1.0-RC1`, `1.0rc1`, and `v1.0.0rc1` are
+//! one version, and so are `2.28` and `2.28.0`. Versions are compared in the canonical
+//! form `packaging.utils.canonicalize_version` produces: normalized per PEP 440, with
+//! trailing zero release components dropped.
+//!
+//! See <https://packaging.python.org/en/latest/specifications/version-specifiers/>.
+
+/// Pre-release spellings and their normalized labels, longest first.
+const PRE_LABELS: [(&str, &str); 8] = [
+    ("alpha", "a"),
+    ("a", "a"),
+    ("beta", "b"),
+    ("b", "b"),
+    ("preview", "rc"),
+    ("pre", "rc"),
+    ("rc", "rc"),
+    ("c", "rc"),
+];
+/// Post-release spellings, longest first.
+const POST_LABELS: [&str; 3] = ["post", "rev", "r"];
+
+/// Whether `a` and `b` are the same version: equal once canonicalized, or for versions
+/// that aren't PEP 440, equal ignoring case.
+pub(crate) fn same_version(a: &str, b: &str) -> bool {
+    match (canonicalize_version(a), canonicalize_version(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.trim().eq_ignore_ascii_case(b.trim()),
+    }
+}
+
+/// The canonical form of a PEP 440 version, or `None` if `version` isn't one.
+fn canonicalize_version(version: &str) -> Option<String> {
+    let lower = version.trim().to_ascii_lowercase();
+    let rest = lower.strip_prefix('v').unwrap_or(&lower);
+    let mut canonical = String::new();
+
+    // Epoch and release
+    let (mut first, mut rest) = number(rest)?;
+    if let Some(after) = rest.strip_prefix('!') {
+        if first != "0" {
+            canonical.push_str(&format!("{first}!"));
+        }
+        (first, rest) = number(after)?;
+    }
+    let mut release = vec![first];
+    while let Some((n, after)) = rest.strip_prefix('.').and_then(number) {
+        release.push(n);
+        rest = after;
+    }
+    while release.len() > 1 && release.last().is_some_and(|n| n == "0") {
+        release.pop();
+    }
+    canonical.push_str(&release.join("."));
+
+    // Pre-release
+    let after = separator(rest);
+    if let Some((label, after)) = PRE_LABELS
+        .iter()
+        .find_map(|(spelling, label)| Some((*label, after.strip_prefix(spelling)?)))
+    {
+        let (n, after) = optional_number(after);
+        canonical.push_str(&format!("{label}{n}"));
+        rest = after;
+    }
+
+    // Post-release, either labelled or as a bare `-N`
+    if let Some((n, after)) = rest.strip_prefix('-').and_then(number) {
+        canonical.push_str(&format!(".post{n}"));
+        rest = after;
+    } else {
+        let after = separator(rest);
+        if let Some(after) = POST_LABELS.iter().find_map(|l| after.strip_prefix(l)) {
+            let (n, after) = optional_number(after);
+            canonical.push_str(&format!(".post{n}"));
+            rest = after;
+        }
+    }
+
+    // Development release
+    if let Some(after) = separator(rest).strip_prefix("dev") {
+        let (n, after) = optional_number(after);
+        canonical.push_str(&format!(".dev{n}"));
+        rest = after;
+    }
+
+    // Local version label
+    if let Some(local) = rest.strip_prefix('+') {
+        let segments: Vec<&str> = local.split(['-', '_', '.']).collect();
+        if segments
+            .iter()
+            .any(|s| s.is_empty() || !s.bytes().all(|b| b.is_ascii_alphanumeric()))
+        {
+            return None;
+        }
+        canonical.push('+');
+        canonical.push_str(&segments.join("."));
+        rest = "";
+    }
+
+    rest.is_empty().then_some(canonical)
+}
+
+/// A leading run of digits, without leading zeros, and what follows it.
+fn number(s: &str) -> Option<(String, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    let digits = s[..end].trim_start_matches('0');
+    let digits = if digits.is_empty() { "0" } else { digits };
+    Some((digits.to_string(), &s[end..]))
+}
+
+/// The number after a pre-, post-, or dev-release label, which may be separated from it
+/// and defaults to 0.
+fn optional_number(s: &str) -> (String, &str) {
+    number(separator(s)).unwrap_or_else(|| ("0".to_string(), s))
+}
+
+/// `s` without one leading `-`, `_`, or `.`.
+fn separator(s: &str) -> &str {
+    s.strip_prefix(['-', '_', '.']).unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalizes_spellings() {
+        let cases = [
+            ("1.0", "1"),
+            ("2.28.0", "2.28"),
+            ("v1.0.0", "1"),
+            ("0!1.2", "1.2"),
+            ("1!01.02", "1!1.2"),
+            ("1.0-RC1", "1rc1"),
+            ("1.0.alpha.2", "1a2"),
+            ("1.0b", "1b0"),
+            ("1.0c3", "1rc3"),
+            ("1.0preview1", "1rc1"),
+            ("1.0-1", "1.post1"),
+            ("1.0.rev2", "1.post2"),
+            ("1.0_post", "1.post0"),
+            ("1.0-dev3", "1.dev3"),
+            ("1.0rc1.post2.dev3", "1rc1.post2.dev3"),
+            ("1.0+Ubuntu-1_a", "1+ubuntu.1.a"),
+        ];
+        for (version, expected) in cases {
+            assert_eq!(
+                canonicalize_version(version).as_deref(),
+                Some(expected),
+                "{version}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_pep440_versions() {
+        for version in ["", "latest", "1.0.", "1.0-foo", "1.0+", "1.0+a..b", "1..0"] {
+            assert_eq!(canonicalize_version(version), None, "{version}");
+        }
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert!(same_version("2.28", "2.28.0"));
+        assert!(same_version("1.0-rc1", "1.0rc1"));
+        assert!(!same_version("1.0", "1.0.1"));
+        assert!(!same_version("1.0rc1", "1.0"));
+        // Legacy versions fall back to a case-insensitive match
+        assert!(same_version("2004d", "2004D"));
+        assert!(!same_version("2004d", "2004e"));
+    }
+}
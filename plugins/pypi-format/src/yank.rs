@@ -0,0 +1,153 @@
+//! Yanked releases (PEP 592).
+//!
+//! A yanked file stays downloadable, so installs pinned to it keep working, but
+//! resolvers skip it unless nothing else satisfies the requirement. Files are yanked by
+//! uploading a JSON document named `<anything>.yank.json`, naming either a whole version
+//! or individual files of a project. Deleting the document un-yanks them. Versions match
+//! as PEP 440 versions, so `2.28` also yanks `2.28.0`.
+//!
+//! ```json
+//! {
+//!   "project": "requests",
+//!   "version": "2.28.0",
+//!   "reason": "Breaks proxy authentication"
+//! }
+//! ```
+//!
+//! Use `"files": ["requests-2.28.0-py3-none-any.whl"]` instead of `version` to yank
+//! individual files.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core_metadata::{is_valid_name, is_valid_version};
+use crate::normalize_package_name;
+use crate::version::same_version;
+
+/// Filename suffix that marks an artifact as a yank document rather than a package.
+const YANK_SUFFIX: &str = ".yank.json";
+
+/// Whether an artifact path names a yank document.
+pub(crate) fn is_yank_path(path: &str) -> bool {
+    path.to_ascii_lowercase().ends_with(YANK_SUFFIX)
+}
+
+/// A version or set of files of a project marked as yanked.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Yank {
+    pub(crate) project: String,
+    #[serde(default)]
+    pub(crate) version: Option<String>,
+    #[serde(default)]
+    pub(crate) files: Vec<String>,
+    #[serde(default)]
+    pub(crate) reason: Option<String>,
+}
+
+impl Yank {
+    /// Whether this yanks the file `filename`, of version `version` of `project`.
+    ///
+    /// `project` must already be normalized. Versions compare as PEP 440 versions, so
+    /// yanking `2.28` covers `2.28.0` too.
+    pub(crate) fn covers(&self, project: &str, version: Option<&str>, filename: &str) -> bool {
+        if normalize_package_name(&self.project) != project {
+            return false;
+        }
+        match &self.version {
+            Some(yanked) => version.is_some_and(|v| same_version(v, yanked)),
+            None => self.files.iter().any(|f| f == filename),
+        }
+    }
+}
+
+/// Parse and validate an uploaded yank document.
+pub(crate) fn parse_yank(data: &[u8]) -> Result<Yank, String> {
+    let yank: Yank =
+        serde_json::from_slice(data).map_err(|e| format!("Invalid yank document: {e}"))?;
+
+    if !is_valid_name(&yank.project) {
+        return Err(format!(
+            "Invalid project name '{}' in yank document",
+            yank.project
+        ));
+    }
+    match (&yank.version, yank.files.is_empty()) {
+        (Some(_), false) => {
+            return Err("Yank document must name either a version or files, not both".to_string())
+        }
+        (None, true) => return Err("Yank document must name a version or files".to_string()),
+        (Some(version), true) if !is_valid_version(version) => {
+            return Err(format!("Invalid version '{version}' in yank document"))
+        }
+        _ => {}
+    }
+    if let Some(bad) = yank
+        .files
+        .iter()
+        .find(|f| f.is_empty() || f.contains(['/', '\\']))
+    {
+        return Err(format!("Invalid filename '{bad}' in yank document"));
+    }
+    Ok(yank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_yank_paths() {
+        assert!(is_yank_path("yanks/requests-2.28.0.yank.json"));
+        assert!(is_yank_path("REQUESTS.YANK.JSON"));
+        assert!(!is_yank_path("requests-2.28.0.tar.gz"));
+        assert!(!is_yank_path("yank.json"));
+    }
+
+    #[test]
+    fn yanks_versions_and_files() {
+        let yank =
+            parse_yank(br#"{"project": "My_Package", "version": "1.0RC1", "reason": "broken"}"#)
+                .unwrap();
+        assert_eq!(yank.reason.as_deref(), Some("broken"));
+        assert!(yank.covers("my-package", Some("1.0rc1"), "my_package-1.0rc1.tar.gz"));
+        assert!(!yank.covers("my-package", Some("1.0"), "my_package-1.0.tar.gz"));
+        assert!(!yank.covers("other", Some("1.0rc1"), "other-1.0rc1.tar.gz"));
+
+        let yank = parse_yank(br#"{"project": "requests", "version": "2.28"}"#).unwrap();
+        assert!(yank.covers("requests", Some("2.28.0"), "requests-2.28.0.tar.gz"));
+        let yank = parse_yank(br#"{"project": "pkg", "version": "1.0-rc1"}"#).unwrap();
+        assert!(yank.covers("pkg", Some("1.0rc1"), "pkg-1.0rc1.tar.gz"));
+        assert!(!yank.covers("pkg", Some("1.0"), "pkg-1.0.tar.gz"));
+
+        let yank =
+            parse_yank(br#"{"project": "pkg", "files": ["pkg-1.0-py3-none-any.whl"]}"#).unwrap();
+        assert_eq!(yank.reason, None);
+        assert!(yank.covers("pkg", Some("1.0"), "pkg-1.0-py3-none-any.whl"));
+        assert!(!yank.covers("pkg", Some("1.0"), "pkg-1.0.tar.gz"));
+    }
+
+    #[test]
+    fn rejects_invalid_yanks() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"not json", "Invalid yank document"),
+            (br#"{"project": "-pkg", "version": "1.0"}"#, "project name"),
+            (br#"{"project": "pkg"}"#, "must name a version or files"),
+            (
+                br#"{"project": "pkg", "version": "1.0", "files": ["pkg-1.0.tar.gz"]}"#,
+                "not both",
+            ),
+            (
+                br#"{"project": "pkg", "version": "1 0"}"#,
+                "Invalid version",
+            ),
+            (
+                br#"{"project": "pkg", "files": ["dist/pkg-1.0.tar.gz"]}"#,
+                "Invalid filename",
+            ),
+        ];
+        for (data, expected) in cases {
+            let err = parse_yank(data).unwrap_err();
+            assert!(err.contains(expected), "{err}");
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Minimal ZIP archive reader.
+//!
+//! Wheels are ZIP archives. Reading one file out of a wheel only needs the central
+//! directory, which lists every entry with its sizes and offset, and the local header
+//! in front of the entry's data. Stored and DEFLATE entries are supported; ZIP64,
+//! encrypted, and multi-disk archives are rejected, as are entries over 1 MiB.
+
+/// End of central directory record signature (`PK\x05\x06`).
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+/// Central directory file header signature (`PK\x01\x02`).
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+/// Local file header signature (`PK\x03\x04`).
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+
+const EOCD_SIZE: usize = 22;
+const CENTRAL_HEADER_SIZE: usize = 46;
+const LOCAL_HEADER_SIZE: usize = 30;
+
+/// The largest entry that's read. Entries are inflated whole and checksummed byte by
+/// byte, and what's read (core metadata) is far smaller, so this keeps a DEFLATE bomb
+/// within the plugin's memory and fuel.
+const MAX_ENTRY_SIZE: usize = 1024 * 1024;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+const FLAG_ENCRYPTED: u16 = 1;
+
+/// An entry listed in the central directory.
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc32: u32,
+    compressed_size: usize,
+    size: usize,
+    local_offset: usize,
+}
+
+/// A parsed ZIP archive borrowing its bytes.
+pub(crate) struct ZipArchive<'a> {
+    data: &'a [u8],
+    entries: Vec<Entry>,
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Parse the central directory of a ZIP archive.
+    pub(crate) fn parse(data: &'a [u8]) -> Result<Self, String> {
+        let eocd = find_eocd(data).ok_or("not a ZIP archive")?;
+        let disk = u16_at(data, eocd + 4)?;
+        let entry_count = u16_at(data, eocd + 10)?;
+        let directory_size = u32_at(data, eocd + 12)?;
+        let directory_offset = u32_at(data, eocd + 16)?;
+        if entry_count == u16::MAX || directory_offset == u32::MAX {
+            return Err("ZIP64 archives are not supported".to_string());
+        }
+        if disk != 0 {
+            return Err("multi-disk ZIP archives are not supported".to_string());
+        }
+
+        let mut offset = directory_offset as usize;
+        let end = offset
+            .checked_add(directory_size as usize)
+            .filter(|&end| end <= eocd)
+            .ok_or("central directory extends past the end of the archive")?;
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            if offset + CENTRAL_HEADER_SIZE > end || u32_at(data, offset)? != CENTRAL_SIGNATURE {
+                return Err("corrupt central directory".to_string());
+            }
+            let name_len = u16_at(data, offset + 28)? as usize;
+            let extra_len = u16_at(data, offset + 30)? as usize;
+            let comment_len = u16_at(data, offset + 32)? as usize;
+            let name_start = offset + CENTRAL_HEADER_SIZE;
+            let name = data
+                .get(name_start..name_start + name_len)
+                .ok_or("corrupt central directory")?;
+
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                flags: u16_at(data, offset + 8)?,
+                method: u16_at(data, offset + 10)?,
+                crc32: u32_at(data, offset + 16)?,
+                compressed_size: u32_at(data, offset + 20)? as usize,
+                size: u32_at(data, offset + 24)? as usize,
+                local_offset: u32_at(data, offset + 42)? as usize,
+            });
+            offset = name_start + name_len + extra_len + comment_len;
+        }
+
+        Ok(ZipArchive { data, entries })
+    }
+
+    /// Names of every entry, in central directory order.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// Read and decompress the entry named `name`, or `None` if there isn't one.
+    pub(crate) fn read(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(entry) = self.entries.iter().find(|e| e.name == name) else {
+            return Ok(None);
+        };
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(format!("{name} is encrypted"));
+        }
+        if entry.size > MAX_ENTRY_SIZE {
+            return Err(format!(
+                "{name} is too large to read ({} bytes, limit {MAX_ENTRY_SIZE})",
+                entry.size
+            ));
+        }
+
+        let local = entry.local_offset;
+        if u32_at(self.data, local)? != LOCAL_SIGNATURE {
+            return Err(format!("{name} has a corrupt local header"));
+        }
+        // Offsets and sizes come from the archive, so they may overflow a 32-bit usize
+        let past_end = || format!("{name} extends past the end of the archive");
+        let name_len = u16_at(self.data, local.saturating_add(26))? as usize;
+        let extra_len = u16_at(self.data, local.saturating_add(28))? as usize;
+        let start = local
+            .checked_add(LOCAL_HEADER_SIZE + name_len + extra_len)
+            .ok_or_else(past_end)?;
+        let end = start
+            .checked_add(entry.compressed_size)
+            .ok_or_else(past_end)?;
+        let compressed = self.data.get(start..end).ok_or_else(past_end)?;
+
+        let content = match entry.method {
+            METHOD_STORED => compressed.to_vec(),
+            METHOD_DEFLATE => {
+                miniz_oxide::inflate::decompress_to_vec_with_limit(compressed, entry.size)
+                    .map_err(|e| format!("{name} failed to decompress: {e}"))?
+            }
+            method => {
+                return Err(format!(
+                    "{name} uses unsupported compression method {method}"
+                ))
+            }
+        };
+
+        if content.len() != entry.size || crc32(&content) != entry.crc32 {
+            return Err(format!("{name} failed its size or CRC-32 check"));
+        }
+        Ok(Some(content))
+    }
+}
+
+/// Locate the end of central directory record, which may be followed by a comment.
+fn find_eocd(data: &[u8]) -> Option<usize> {
+    let last = data.len().checked_sub(EOCD_SIZE)?;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|&i| data[i..i + 4] == EOCD_SIGNATURE.to_le_bytes())
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, String> {
+    data.get(offset..offset.saturating_add(2))
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "truncated ZIP archive".to_string())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+    data.get(offset..offset.saturating_add(4))
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "truncated ZIP archive".to_string())
+}
+
+/// CRC-32 (ISO 3309), as ZIP and gzip use.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xEDB8_8320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+/// Building archives for tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// Build a ZIP archive, DEFLATE-compressing every entry whose name ends in `METADATA`
+    /// and storing the rest.
+    pub(crate) fn build_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut directory = Vec::new();
+
+        for (name, content) in entries {
+            let (method, stored) = if name.ends_with("METADATA") {
+                (
+                    METHOD_DEFLATE,
+                    miniz_oxide::deflate::compress_to_vec(content, 6),
+                )
+            } else {
+                (METHOD_STORED, content.to_vec())
+            };
+            let mut fields = Vec::new();
+            fields.extend_from_slice(&method.to_le_bytes());
+            fields.extend_from_slice(&[0; 4]); // mod time and date
+            fields.extend_from_slice(&crc32(content).to_le_bytes());
+            fields.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            fields.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+            let local_offset = data.len() as u32;
+            data.extend_from_slice(&LOCAL_SIGNATURE.to_le_bytes());
+            data.extend_from_slice(&[20, 0, 0, 0]); // version needed, flags
+            data.extend_from_slice(&fields);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&stored);
+
+            directory.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]); // versions, flags
+            directory.extend_from_slice(&fields);
+            directory.extend_from_slice(&[0; 6]); // comment length, disk, internal attributes
+            directory.extend_from_slice(&[0; 4]); // external attributes
+            directory.extend_from_slice(&local_offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+
+        let directory_offset = data.len() as u32;
+        data.extend_from_slice(&directory);
+        data.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        data.extend_from_slice(&[0; 4]); // disk numbers
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        data.extend_from_slice(&directory_offset.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::build_zip;
+    use super::*;
+
+    #[test]
+    fn reads_stored_and_deflated_entries() {
+        let metadata = "Metadata-Version: 2.1\n".repeat(50);
+        let zip = build_zip(&[
+            ("pkg/__init__.py", b"print('hi')\n"),
+            ("pkg-1.0.dist-info/METADATA", metadata.as_bytes()),
+        ]);
+        let archive = ZipArchive::parse(&zip).unwrap();
+        assert_eq!(
+            archive.names().collect::<Vec<_>>(),
+            ["pkg/__init__.py", "pkg-1.0.dist-info/METADATA"]
+        );
+        assert_eq!(
+            archive.read("pkg/__init__.py").unwrap().unwrap(),
+            b"print('hi')\n"
+        );
+        assert_eq!(
+            archive.read("pkg-1.0.dist-info/METADATA").unwrap().unwrap(),
+            metadata.as_bytes()
+        );
+        assert_eq!(archive.read("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_corrupt_archives() {
+        assert!(ZipArchive::parse(b"PK\x03\x04").is_err());
+        assert!(ZipArchive::parse(b"").is_err());
+
+        let mut zip = build_zip(&[("a.txt", b"hello")]);
+        zip[30 + 5] = b'j'; // first byte of the stored content
+        let err = ZipArchive::parse(&zip).unwrap().read("a.txt").unwrap_err();
+        assert!(err.contains("CRC-32"), "{err}");
+    }
+
+    #[test]
+    fn rejects_oversized_entries() {
+        // A METADATA that inflates past the limit from a few KiB
+        let bomb = vec![b'A'; MAX_ENTRY_SIZE + 1];
+        let zip = build_zip(&[("pkg-1.0.dist-info/METADATA", &bomb)]);
+        assert!(zip.len() < 8 * 1024);
+        let err = ZipArchive::parse(&zip)
+            .unwrap()
+            .read("pkg-1.0.dist-info/METADATA")
+            .unwrap_err();
+        assert!(err.contains("too large"), "{err}");
+
+        // A small entry whose declared size is huge
+        let mut zip = build_zip(&[("a.txt", b"hello")]);
+        let central = zip.len() - EOCD_SIZE - CENTRAL_HEADER_SIZE - "a.txt".len();
+        zip[central + 24..central + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = ZipArchive::parse(&zip).unwrap().read("a.txt").unwrap_err();
+        assert!(err.contains("too large"), "{err}");
+    }
+
+    #[test]
+    fn rejects_out_of_range_entries() {
+        let zip = build_zip(&[("a.txt", b"hello")]);
+        let central = zip.len() - EOCD_SIZE - CENTRAL_HEADER_SIZE - "a.txt".len();
+
+        let mut huge_size = zip.clone();
+        huge_size[central + 20..central + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = ZipArchive::parse(&huge_size)
+            .unwrap()
+            .read("a.txt")
+            .unwrap_err();
+        assert!(err.contains("extends past the end"), "{err}");
+
+        let mut huge_offset = zip;
+        huge_offset[central + 42..central + 46].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = ZipArchive::parse(&huge_offset)
+            .unwrap()
+            .read("a.txt")
+            .unwrap_err();
+        assert!(err.contains("truncated"), "{err}");
+    }
+
+    #[test]
+    fn crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
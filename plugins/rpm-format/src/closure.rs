@@ -0,0 +1,236 @@
+//! Repository closure.
+//!
+//! Checks every binary package's requires against what the repository's packages
+//! provide, the way `dnf repoclosure` does, so a repository that's no longer installable
+//! can be spotted after an upload. A require is satisfied by a matching provide
+//! (including each package's implicit `name = EVR` provide) or, for a path, by a file
+//! some package owns. `rpmlib(...)` requires are satisfied by rpm itself, and rich
+//! (boolean) dependencies aren't evaluated.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::header::{Dependency, RpmHeaderInfo, RPMSENSE_EQUAL, RPMSENSE_GREATER, RPMSENSE_LESS};
+use crate::vercmp::{compare_evr, Evr};
+use crate::{format_evr, split_evr};
+
+/// The comparison bits of RPMSENSE flags.
+const SENSE_MASK: u32 = RPMSENSE_LESS | RPMSENSE_GREATER | RPMSENSE_EQUAL;
+
+/// The requires of each package that no package in the set satisfies, in package order.
+///
+/// Each require is described as it would appear in a spec file, such as
+/// `openssl-libs >= 1:3.0.7`, and listed once.
+pub(crate) fn unresolved_requires(packages: &[&RpmHeaderInfo]) -> Vec<Vec<String>> {
+    let mut provides: HashMap<&str, Vec<(u32, String)>> = HashMap::new();
+    let mut files = HashSet::new();
+    for header in packages {
+        let evr = format_evr(header.epoch, &header.version, &header.release);
+        provides
+            .entry(header.name.as_str())
+            .or_default()
+            .push((RPMSENSE_EQUAL, evr));
+        for provide in &header.provides {
+            provides
+                .entry(provide.name.as_str())
+                .or_default()
+                .push((provide.flags, provide.version.clone()));
+        }
+        files.extend(header.files.iter().map(|f| f.path.as_str()));
+    }
+
+    let satisfied = |require: &Dependency| {
+        let provided = provides.get(require.name.as_str()).is_some_and(|p| {
+            p.iter()
+                .any(|(flags, version)| ranges_overlap(*flags, version, require))
+        });
+        provided || (require.name.starts_with('/') && files.contains(require.name.as_str()))
+    };
+
+    packages
+        .iter()
+        .map(|header| {
+            let mut seen = HashSet::new();
+            header
+                .requires
+                .iter()
+                .filter(|r| !r.name.starts_with("rpmlib(") && !r.name.starts_with('('))
+                .filter(|r| !satisfied(r))
+                .map(describe)
+                .filter(|d| seen.insert(d.clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether a provide can satisfy a require, as rpm's `rpmdsCompare` decides.
+///
+/// An unversioned provide or require matches anything. A release missing from either
+/// side matches any release on the other.
+fn ranges_overlap(provide_flags: u32, provide_version: &str, require: &Dependency) -> bool {
+    let (provide_sense, require_sense) = (provide_flags & SENSE_MASK, require.flags & SENSE_MASK);
+    if provide_sense == 0 || require_sense == 0 || provide_version.is_empty() {
+        return true;
+    }
+
+    let (provide_epoch, provide_ver, provide_rel) = split_evr(provide_version);
+    let (require_epoch, require_ver, require_rel) = split_evr(&require.version);
+    let (provide_rel, require_rel) = match (provide_rel, require_rel) {
+        (Some(p), Some(r)) => (p, r),
+        _ => ("", ""),
+    };
+    let epoch = |e: Option<&str>| e.and_then(|e| e.parse().ok()).unwrap_or(0);
+    let sense = compare_evr(
+        &Evr {
+            epoch: epoch(provide_epoch),
+            version: provide_ver,
+            release: provide_rel,
+        },
+        &Evr {
+            epoch: epoch(require_epoch),
+            version: require_ver,
+            release: require_rel,
+        },
+    );
+
+    match sense {
+        Ordering::Less => {
+            provide_sense & RPMSENSE_GREATER != 0 || require_sense & RPMSENSE_LESS != 0
+        }
+        Ordering::Greater => {
+            provide_sense & RPMSENSE_LESS != 0 || require_sense & RPMSENSE_GREATER != 0
+        }
+        // Both include equality, or both extend the same way
+        Ordering::Equal => provide_sense & require_sense != 0,
+    }
+}
+
+/// `name [op version]`, as written in a spec file.
+fn describe(dep: &Dependency) -> String {
+    let op = match dep.flags & SENSE_MASK {
+        RPMSENSE_LESS => "<",
+        RPMSENSE_GREATER => ">",
+        RPMSENSE_EQUAL => "=",
+        f if f == RPMSENSE_LESS | RPMSENSE_EQUAL => "<=",
+        f if f == RPMSENSE_GREATER | RPMSENSE_EQUAL => ">=",
+        _ => return dep.name.clone(),
+    };
+    format!("{} {op} {}", dep.name, dep.version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::testing::RpmBuilder;
+    use crate::header::{
+        parse_rpm, RPMTAG_PROVIDEFLAGS, RPMTAG_PROVIDENAME, RPMTAG_PROVIDEVERSION,
+        RPMTAG_REQUIREFLAGS, RPMTAG_REQUIRENAME, RPMTAG_REQUIREVERSION,
+    };
+
+    const GE: u32 = RPMSENSE_GREATER | RPMSENSE_EQUAL;
+
+    fn package(
+        name: &str,
+        version: &str,
+        provides: &[(&str, u32, &str)],
+        requires: &[(&str, u32, &str)],
+        files: &[(&str, u16, u32)],
+    ) -> RpmHeaderInfo {
+        let data = RpmBuilder::new(name, version, "1.el9", "x86_64")
+            .dependencies(
+                [
+                    RPMTAG_PROVIDENAME,
+                    RPMTAG_PROVIDEFLAGS,
+                    RPMTAG_PROVIDEVERSION,
+                ],
+                provides,
+            )
+            .dependencies(
+                [
+                    RPMTAG_REQUIRENAME,
+                    RPMTAG_REQUIREFLAGS,
+                    RPMTAG_REQUIREVERSION,
+                ],
+                requires,
+            )
+            .files(files)
+            .build();
+        RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap()
+    }
+
+    fn dep(flags: u32, version: &str) -> Dependency {
+        Dependency {
+            name: "x".to_string(),
+            flags,
+            version: version.to_string(),
+        }
+    }
+
+    #[test]
+    fn reports_unresolved_requires() {
+        let openssl = package(
+            "openssl-libs",
+            "3.0.7",
+            &[("libssl.so.3()(64bit)", 0, "")],
+            &[],
+            &[("/usr/lib64/libssl.so.3", 0o100755, 0)],
+        );
+        let nginx = package(
+            "nginx",
+            "1.24.0",
+            &[],
+            &[
+                ("libssl.so.3()(64bit)", 0, ""),
+                ("openssl-libs", GE, "1:3.0.7"),
+                ("/usr/lib64/libssl.so.3", 0, ""),
+                ("nginx-filesystem", RPMSENSE_EQUAL, "1.24.0-1.el9"),
+                ("nginx-filesystem", RPMSENSE_EQUAL, "1.24.0-1.el9"),
+                ("/usr/sbin/useradd", 0, ""),
+                (
+                    "rpmlib(PayloadFilesHavePrefix)",
+                    RPMSENSE_LESS | RPMSENSE_EQUAL,
+                    "4.0-1",
+                ),
+                ("(nginx-mod-stream if selinux-policy)", 0, ""),
+            ],
+            &[],
+        );
+
+        let unresolved = unresolved_requires(&[&openssl, &nginx]);
+        assert!(unresolved[0].is_empty());
+        // openssl-libs is 0:3.0.7, older than the required epoch 1
+        assert_eq!(
+            unresolved[1],
+            [
+                "openssl-libs >= 1:3.0.7",
+                "nginx-filesystem = 1.24.0-1.el9",
+                "/usr/sbin/useradd"
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_ranges_like_rpm() {
+        let provide =
+            |flags, version| move |require: Dependency| ranges_overlap(flags, version, &require);
+        let exact = provide(RPMSENSE_EQUAL, "1.2-3");
+        assert!(exact(dep(GE, "1.2")));
+        assert!(exact(dep(RPMSENSE_EQUAL, "1.2")));
+        assert!(exact(dep(RPMSENSE_LESS, "1.10")));
+        assert!(!exact(dep(RPMSENSE_GREATER, "1.2-3")));
+        assert!(!exact(dep(RPMSENSE_EQUAL, "1.2-4")));
+        assert!(!exact(dep(GE, "1:1.0")));
+        assert!(exact(dep(0, "")));
+
+        assert!(provide(0, "")(dep(GE, "99")));
+        assert!(provide(GE, "2.0")(dep(RPMSENSE_GREATER, "3.0")));
+        assert!(!provide(RPMSENSE_LESS, "2.0")(dep(GE, "2.0")));
+    }
+
+    #[test]
+    fn describes_dependencies() {
+        assert_eq!(describe(&dep(0, "")), "x");
+        assert_eq!(describe(&dep(GE, "1:2.0")), "x >= 1:2.0");
+        assert_eq!(describe(&dep(RPMSENSE_LESS, "3")), "x < 3");
+    }
+}
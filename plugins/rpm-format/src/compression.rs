@@ -0,0 +1,296 @@
+//! Repodata compression.
+//!
+//! createrepo_c compresses primary, filelists, and other with gzip by default, and
+//! with zstd for Fedora 38 and later. dnf picks the file named in repomd.xml, so only
+//! the configured format is advertised, but every format is served so clients holding
+//! an older repomd.xml can still fetch what it names.
+
+use std::io::Write;
+
+use lzma_rust2::{XzOptions, XzWriter};
+use ruzstd::encoding::{compress_to_vec, CompressionLevel};
+
+/// DEFLATE level for gzip, matching gzip's own default.
+const GZIP_LEVEL: u8 = 6;
+
+/// xz preset. xz's default of 6 needs about 92 MiB for its 8 MiB dictionary; preset 0's
+/// 256 KiB dictionary keeps the encoder near 2.5 MiB, inside the plugin's memory limit,
+/// and repodata documents gain little from a larger window.
+const XZ_PRESET: u32 = 0;
+
+/// A compression format for repodata files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Every supported format.
+    pub(crate) const ALL: [Compression; 3] =
+        [Compression::Gzip, Compression::Zstd, Compression::Xz];
+
+    /// Parse a format name as accepted by createrepo_c's `--compress-type`.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// The format for a file extension, without the dot.
+    pub(crate) fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.extension() == extension)
+    }
+
+    /// File extension, without the dot.
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Xz => "xz",
+        }
+    }
+
+    /// MIME type of the compressed file.
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Compression::Gzip => "application/gzip",
+            Compression::Zstd => "application/zstd",
+            Compression::Xz => "application/x-xz",
+        }
+    }
+
+    /// Compress `data` in this format.
+    pub(crate) fn compress(self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match self {
+            Compression::Gzip => gzip_compress(data),
+            Compression::Zstd => Ok(compress_to_vec(data, CompressionLevel::Fastest)),
+            Compression::Xz => xz_compress(data),
+        }
+    }
+}
+
+/// Compress `data` as a single-member gzip stream (RFC 1952).
+///
+/// The header carries no mtime, so the output depends only on the input and the
+/// checksums in repomd.xml stay stable between requests.
+fn gzip_compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let deflated = miniz_oxide::deflate::compress_to_vec(data, GZIP_LEVEL);
+    let mut output = Vec::with_capacity(deflated.len() + 18);
+
+    // Gzip header (10 bytes)
+    output.extend_from_slice(&[
+        0x1f, 0x8b, // magic
+        0x08, // method: deflate
+        0x00, // flags: none
+        0x00, 0x00, 0x00, 0x00, // mtime
+        0x00, // extra flags
+        0xff, // OS: unknown
+    ]);
+
+    output.extend_from_slice(&deflated);
+
+    // CRC32 and original size (ISIZE)
+    let crc = crc32(data);
+    let size = data.len() as u32;
+    output.extend_from_slice(&crc.to_le_bytes());
+    output.extend_from_slice(&size.to_le_bytes());
+
+    Ok(output)
+}
+
+/// Compress `data` as an xz stream with a CRC64 check, as `xz` does by default.
+fn xz_compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut writer = XzWriter::new(Vec::new(), XzOptions::with_preset(XZ_PRESET))
+        .map_err(|e| format!("xz compression failed: {e}"))?;
+    writer
+        .write_all(data)
+        .map_err(|e| format!("xz compression failed: {e}"))?;
+    writer
+        .finish()
+        .map_err(|e| format!("xz compression failed: {e}"))
+}
+
+/// CRC32 (ISO 3309 / ITU-T V.42) used by gzip.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            if crc & 1 != 0 {
+                crc = (crc >> 1) ^ 0xEDB8_8320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::io::Read;
+
+    thread_local! {
+        static LIVE_BYTES: Cell<usize> = const { Cell::new(0) };
+        static PEAK_BYTES: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Tracks the bytes each thread has allocated, so a test can measure its own peak.
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                let live = LIVE_BYTES.get().wrapping_add(layout.size());
+                LIVE_BYTES.set(live);
+                PEAK_BYTES.set(PEAK_BYTES.get().max(live));
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            LIVE_BYTES.set(LIVE_BYTES.get().wrapping_sub(layout.size()));
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// The most memory an encoder may use, leaving most of the plugin's 16 MB for the
+    /// documents themselves.
+    const MAX_ENCODER_MEMORY: usize = 4 * 1024 * 1024;
+
+    /// The most memory `f` had allocated at once on this thread.
+    fn peak_allocation(f: impl FnOnce()) -> usize {
+        let start = LIVE_BYTES.get();
+        PEAK_BYTES.set(start);
+        f();
+        PEAK_BYTES.get().wrapping_sub(start)
+    }
+
+    /// Repetitive XML, like real repodata, so compression has something to find.
+    fn sample() -> Vec<u8> {
+        (0..500)
+            .map(|i| format!("<package type=\"rpm\"><name>pkg{i}</name></package>\n"))
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    fn gunzip(data: &[u8]) -> Vec<u8> {
+        assert_eq!(&data[..3], &[0x1f, 0x8b, 0x08]);
+        let body = &data[10..data.len() - 8];
+        let out = miniz_oxide::inflate::decompress_to_vec(body).unwrap();
+        let trailer = &data[data.len() - 8..];
+        assert_eq!(trailer[..4], crc32(&out).to_le_bytes());
+        assert_eq!(trailer[4..], (out.len() as u32).to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn gzip_compress_produces_valid_header() {
+        let result = gzip_compress(b"hello").unwrap();
+        assert_eq!(result[0], 0x1f);
+        assert_eq!(result[1], 0x8b);
+        assert_eq!(result[2], 0x08); // deflate
+    }
+
+    #[test]
+    fn gzip_compress_empty_input() {
+        let result = gzip_compress(b"").unwrap();
+        assert!(result.len() > 10); // header + trailer at minimum
+        assert_eq!(gunzip(&result), b"");
+    }
+
+    #[test]
+    fn gzip_round_trips_and_shrinks() {
+        let data = sample();
+        let compressed = gzip_compress(&data).unwrap();
+        assert!(compressed.len() < data.len() / 4);
+        assert_eq!(gunzip(&compressed), data);
+    }
+
+    #[test]
+    fn zstd_round_trips() {
+        let data = sample();
+        let compressed = Compression::Zstd.compress(&data).unwrap();
+        assert_eq!(&compressed[..4], &[0x28, 0xb5, 0x2f, 0xfd]);
+        assert!(compressed.len() < data.len() / 4);
+
+        let mut out = Vec::new();
+        ruzstd::decoding::StreamingDecoder::new(compressed.as_slice())
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn xz_round_trips() {
+        let data = sample();
+        let compressed = Compression::Xz.compress(&data).unwrap();
+        assert_eq!(&compressed[..6], b"\xfd7zXZ\x00");
+        assert!(compressed.len() < data.len() / 4);
+
+        let mut out = Vec::new();
+        lzma_rust2::XzReader::new(compressed.as_slice(), false)
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn compression_fits_in_plugin_memory() {
+        let data = sample();
+        for compression in Compression::ALL {
+            let peak = peak_allocation(|| {
+                compression.compress(&data).unwrap();
+            });
+            assert!(
+                peak <= MAX_ENCODER_MEMORY,
+                "{compression:?} used {peak} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let data = sample();
+        for compression in Compression::ALL {
+            assert_eq!(
+                compression.compress(&data).unwrap(),
+                compression.compress(&data).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn parses_names_and_extensions() {
+        assert_eq!(Compression::from_name("ZSTD"), Some(Compression::Zstd));
+        assert_eq!(Compression::from_name("gz"), Some(Compression::Gzip));
+        assert_eq!(Compression::from_name("bz2"), None);
+        for compression in Compression::ALL {
+            assert_eq!(
+                Compression::from_extension(compression.extension()),
+                Some(compression)
+            );
+        }
+    }
+
+    #[test]
+    fn crc32_known_value() {
+        // CRC32 of empty string is 0x00000000
+        assert_eq!(crc32(b""), 0x0000_0000);
+        // CRC32 of "123456789" is 0xCBF43926
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+}
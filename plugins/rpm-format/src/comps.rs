@@ -0,0 +1,271 @@
+//! Package group metadata (comps).
+//!
+//! A comps document is uploaded to the repository as `comps.xml` or `<anything>-comps.xml`.
+//! It is validated on upload and stored as an artifact property. Every comps document
+//! in the repository is merged into `repodata/comps.xml`, which `dnf group` reads
+//! through the `group` and `group_gz` entries in repomd.xml.
+
+use std::collections::HashSet;
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+/// `type` attributes a `<packagereq>` may have.
+const PACKAGEREQ_TYPES: [&str; 4] = ["mandatory", "default", "optional", "conditional"];
+
+/// Whether an artifact path names a comps document.
+pub(crate) fn is_comps_path(path: &str) -> bool {
+    let filename = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+    filename == "comps.xml" || filename.ends_with("-comps.xml") || filename.ends_with(".comps.xml")
+}
+
+/// Parse and validate an uploaded comps document, returning its text.
+///
+/// Every group, category, and environment needs an ID and a name, IDs must be unique
+/// within the document, and package requirements need a package name and a known type.
+pub(crate) fn parse_comps(data: &[u8]) -> Result<String, String> {
+    let text =
+        std::str::from_utf8(data).map_err(|_| "Invalid comps document: not UTF-8".to_string())?;
+    let doc = parse(text).map_err(|e| format!("Invalid comps XML: {e}"))?;
+
+    let root = doc.root_element();
+    if !root.has_tag_name("comps") {
+        return Err(format!(
+            "Invalid comps document: root element is <{}>, expected <comps>",
+            root.tag_name().name()
+        ));
+    }
+
+    let mut ids = HashSet::new();
+    for element in root.children().filter(Node::is_element) {
+        let tag = element.tag_name().name();
+        match tag {
+            "group" | "category" | "environment" => {
+                let id = child_text(element, "id")
+                    .ok_or_else(|| format!("Comps {tag} is missing an <id>"))?;
+                if child_text(element, "name").is_none() {
+                    return Err(format!("Comps {tag} '{id}' is missing a <name>"));
+                }
+                if !ids.insert((tag, id)) {
+                    return Err(format!("Duplicate comps {tag} '{id}'"));
+                }
+                if tag == "group" {
+                    check_packagelist(element, id)?;
+                } else {
+                    check_grouplists(element, tag, id)?;
+                }
+            }
+            "langpacks" | "blacklist" | "whiteout" => {}
+            other => {
+                return Err(format!(
+                    "Invalid comps document: unexpected element <{other}>"
+                ))
+            }
+        }
+    }
+
+    if ids.is_empty() {
+        return Err("Comps document defines no groups, categories, or environments".to_string());
+    }
+
+    Ok(text.to_string())
+}
+
+/// Merge comps documents into one.
+///
+/// Elements are copied verbatim in document order. When several documents define the
+/// same group, category, or environment, the first definition wins.
+pub(crate) fn merge_comps<'a>(documents: impl IntoIterator<Item = &'a str>) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE comps PUBLIC \"-//Red Hat, Inc.//DTD Comps info//EN\" \"comps.dtd\">\n\
+         <comps>\n",
+    );
+    let mut seen = HashSet::new();
+
+    for text in documents {
+        // Stored documents were validated on upload
+        let Ok(doc) = parse(text) else {
+            continue;
+        };
+        for element in doc.root_element().children().filter(Node::is_element) {
+            let key = (
+                element.tag_name().name().to_string(),
+                child_text(element, "id").unwrap_or("").to_string(),
+            );
+            if seen.insert(key) {
+                xml.push_str("  ");
+                xml.push_str(&text[element.range()]);
+                xml.push('\n');
+            }
+        }
+    }
+
+    xml.push_str("</comps>\n");
+    xml
+}
+
+fn parse(text: &str) -> Result<Document<'_>, roxmltree::Error> {
+    Document::parse_with_options(
+        text,
+        ParsingOptions {
+            allow_dtd: true,
+            ..ParsingOptions::default()
+        },
+    )
+}
+
+/// The trimmed text of the first `tag` child of `node`, if it isn't empty.
+fn child_text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(tag))
+        .and_then(|c| c.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+fn check_packagelist(group: Node<'_, '_>, id: &str) -> Result<(), String> {
+    let reqs = group
+        .children()
+        .filter(|c| c.has_tag_name("packagelist"))
+        .flat_map(|list| list.children().filter(|c| c.has_tag_name("packagereq")));
+
+    for req in reqs {
+        let name = req.text().map(str::trim).unwrap_or("");
+        if name.is_empty() {
+            return Err(format!("Comps group '{id}' has an empty <packagereq>"));
+        }
+        match req.attribute("type") {
+            Some("conditional") if req.attribute("requires").is_none() => {
+                return Err(format!(
+                    "Comps group '{id}': conditional package '{name}' has no requires attribute"
+                ))
+            }
+            Some(kind) if !PACKAGEREQ_TYPES.contains(&kind) => {
+                return Err(format!(
+                    "Comps group '{id}': package '{name}' has unknown type '{kind}'"
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_grouplists(element: Node<'_, '_>, tag: &str, id: &str) -> Result<(), String> {
+    let group_ids = element
+        .children()
+        .filter(|c| c.has_tag_name("grouplist") || c.has_tag_name("optionlist"))
+        .flat_map(|list| list.children().filter(|c| c.has_tag_name("groupid")));
+
+    for group_id in group_ids {
+        if group_id.text().map(str::trim).unwrap_or("").is_empty() {
+            return Err(format!("Comps {tag} '{id}' has an empty <groupid>"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE comps PUBLIC "-//Red Hat, Inc.//DTD Comps info//EN" "comps.dtd">
+<comps>
+  <group>
+    <id>web-server</id>
+    <name>Web Server</name>
+    <name xml:lang="de">Webserver</name>
+    <packagelist>
+      <packagereq type="mandatory">nginx</packagereq>
+      <packagereq type="conditional" requires="nginx">nginx-mod-stream</packagereq>
+    </packagelist>
+  </group>
+  <category>
+    <id>servers</id>
+    <name>Servers</name>
+    <grouplist><groupid>web-server</groupid></grouplist>
+  </category>
+</comps>
+"#;
+
+    #[test]
+    fn accepts_valid_comps() {
+        assert_eq!(parse_comps(COMPS.as_bytes()).unwrap(), COMPS);
+    }
+
+    #[test]
+    fn rejects_invalid_comps() {
+        let cases = [
+            ("<comps><group>", "Invalid comps XML"),
+            ("<groups/>", "expected <comps>"),
+            ("<comps/>", "defines no groups"),
+            (
+                "<comps><group><name>A</name></group></comps>",
+                "missing an <id>",
+            ),
+            (
+                "<comps><group><id>a</id></group></comps>",
+                "'a' is missing a <name>",
+            ),
+            (
+                "<comps><group><id>a</id><name>A</name></group>\
+                 <group><id>a</id><name>B</name></group></comps>",
+                "Duplicate comps group 'a'",
+            ),
+            (
+                "<comps><group><id>a</id><name>A</name><packagelist>\
+                 <packagereq type=\"required\">x</packagereq></packagelist></group></comps>",
+                "unknown type 'required'",
+            ),
+            (
+                "<comps><group><id>a</id><name>A</name><packagelist>\
+                 <packagereq type=\"conditional\">x</packagereq></packagelist></group></comps>",
+                "no requires attribute",
+            ),
+            (
+                "<comps><category><id>c</id><name>C</name>\
+                 <grouplist><groupid/></grouplist></category></comps>",
+                "empty <groupid>",
+            ),
+            (
+                "<comps><packages/></comps>",
+                "unexpected element <packages>",
+            ),
+        ];
+        for (xml, expected) in cases {
+            let err = parse_comps(xml.as_bytes()).unwrap_err();
+            assert!(err.contains(expected), "{xml}: {err}");
+        }
+    }
+
+    #[test]
+    fn merges_documents() {
+        let extra = "<comps>\
+                     <group><id>web-server</id><name>Duplicate</name></group>\
+                     <group><id>database</id><name>Database</name></group>\
+                     </comps>";
+        let merged = merge_comps([COMPS, extra]);
+
+        let doc = parse(&merged).unwrap();
+        let ids: Vec<_> = doc
+            .root_element()
+            .children()
+            .filter(Node::is_element)
+            .map(|e| child_text(e, "id").unwrap())
+            .collect();
+        assert_eq!(ids, ["web-server", "servers", "database"]);
+        assert!(merged.contains("<name>Web Server</name>"));
+        assert!(!merged.contains("Duplicate"));
+        assert!(parse_comps(merged.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn comps_paths() {
+        assert!(is_comps_path("comps.xml"));
+        assert!(is_comps_path("groups/fedora-comps.xml"));
+        assert!(is_comps_path("extras.comps.xml"));
+        assert!(!is_comps_path("mycomps.xml"));
+        assert!(!is_comps_path("repodata/primary.xml"));
+    }
+}
@@ -0,0 +1,182 @@
+//! Plugin configuration.
+//!
+//! The WIT contract has no configuration channel, so settings are read from
+//! environment variables the host sets for the plugin instance. Every setting
+//! is optional; unset or unparsable values fall back to the defaults below, except
+//! `RPM_REQUIRE_SIGNATURE`, which fails closed: any value other than a recognized
+//! false (`0`, `false`, `no`, `off`) requires signatures.
+//!
+//! | Variable | Default | Meaning |
+//! |----------|---------|---------|
+//! | `RPM_CHANGELOG_LIMIT` | `10` | Changelog entries per package in other.xml |
+//! | `RPM_REQUIRE_SIGNATURE` | `false` | Reject unsigned packages on upload |
+//! | `RPM_TRUSTED_KEYS` | (none) | ASCII-armored OpenPGP public keys; when set, only packages whose RSA or Ed25519 signature verifies against one of them are accepted |
+//! | `RPM_SIGNING_KEY` | (none) | ASCII-armored, unprotected Ed25519 OpenPGP secret key that signs repomd.xml |
+//! | `RPM_REPODATA_COMPRESSION` | `gzip` | Format of the primary, filelists, and other files advertised in repomd.xml: `gzip`, `zstd`, or `xz` |
+//! | `RPM_MIRRORS` | (none) | Comma- or space-separated base URLs of mirrors of this repository, listed ahead of the repository itself in `/metalink` and `/mirrorlist` |
+
+use crate::compression::Compression;
+
+/// Default number of changelog entries per package, matching createrepo_c.
+const DEFAULT_CHANGELOG_LIMIT: usize = 10;
+
+/// Settings for the RPM plugin.
+pub(crate) struct RpmConfig {
+    /// Maximum changelog entries emitted per package in other.xml.
+    pub(crate) changelog_limit: usize,
+    /// Whether unsigned packages are rejected on upload.
+    pub(crate) require_signature: bool,
+    /// Armored public keys that package signatures must verify against.
+    pub(crate) trusted_keys: Option<String>,
+    /// Armored secret key for signing repomd.xml; unsigned repodata when unset.
+    pub(crate) signing_key: Option<String>,
+    /// Compression of the repodata files advertised in repomd.xml.
+    pub(crate) repodata_compression: Compression,
+    /// Mirror base URLs in order of preference, without trailing slashes.
+    pub(crate) mirrors: Vec<String>,
+}
+
+impl Default for RpmConfig {
+    fn default() -> Self {
+        RpmConfig {
+            changelog_limit: DEFAULT_CHANGELOG_LIMIT,
+            require_signature: false,
+            trusted_keys: None,
+            signing_key: None,
+            repodata_compression: Compression::Gzip,
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+impl RpmConfig {
+    /// Read the configuration from the plugin's environment.
+    pub(crate) fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Read the configuration through `lookup`, which maps a variable name to its value.
+    pub(crate) fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = RpmConfig::default();
+        RpmConfig {
+            changelog_limit: lookup("RPM_CHANGELOG_LIMIT")
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(defaults.changelog_limit),
+            // A typo must not silently turn signature checks off
+            require_signature: lookup("RPM_REQUIRE_SIGNATURE")
+                .map(|v| parse_bool(&v).unwrap_or(true))
+                .unwrap_or(defaults.require_signature),
+            trusted_keys: lookup("RPM_TRUSTED_KEYS").filter(|v| !v.trim().is_empty()),
+            signing_key: lookup("RPM_SIGNING_KEY").filter(|v| !v.trim().is_empty()),
+            repodata_compression: lookup("RPM_REPODATA_COMPRESSION")
+                .and_then(|v| Compression::from_name(&v))
+                .unwrap_or(defaults.repodata_compression),
+            mirrors: lookup("RPM_MIRRORS")
+                .map(|v| parse_mirrors(&v))
+                .unwrap_or(defaults.mirrors),
+        }
+    }
+
+    /// Whether uploads must be signed, either explicitly or because trusted keys are set.
+    pub(crate) fn requires_signature(&self) -> bool {
+        self.require_signature || self.trusted_keys.is_some()
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Split a mirror list on commas and whitespace, keeping absolute URLs.
+fn parse_mirrors(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|url| url.contains("://"))
+        .map(|url| url.trim_end_matches('/').to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_when_unset() {
+        let config = RpmConfig::from_lookup(|_| None);
+        assert_eq!(config.changelog_limit, 10);
+        assert_eq!(config.repodata_compression, Compression::Gzip);
+        assert!(config.mirrors.is_empty());
+    }
+
+    #[test]
+    fn reads_mirrors() {
+        let config = RpmConfig::from_lookup(|k| {
+            (k == "RPM_MIRRORS").then(|| {
+                "https://eu.example.com/rpm/, https://us.example.com/rpm\nnot-a-url".into()
+            })
+        });
+        assert_eq!(
+            config.mirrors,
+            ["https://eu.example.com/rpm", "https://us.example.com/rpm"]
+        );
+    }
+
+    #[test]
+    fn reads_repodata_compression() {
+        let config =
+            RpmConfig::from_lookup(|k| (k == "RPM_REPODATA_COMPRESSION").then(|| "zstd".into()));
+        assert_eq!(config.repodata_compression, Compression::Zstd);
+        let config = RpmConfig::from_lookup(|_| Some("bzip2".into()));
+        assert_eq!(config.repodata_compression, Compression::Gzip);
+    }
+
+    #[test]
+    fn reads_changelog_limit() {
+        let config = RpmConfig::from_lookup(|k| (k == "RPM_CHANGELOG_LIMIT").then(|| "3".into()));
+        assert_eq!(config.changelog_limit, 3);
+    }
+
+    #[test]
+    fn ignores_invalid_changelog_limit() {
+        let config = RpmConfig::from_lookup(|_| Some("many".into()));
+        assert_eq!(config.changelog_limit, 10);
+    }
+
+    #[test]
+    fn signatures_optional_by_default() {
+        let config = RpmConfig::from_lookup(|_| None);
+        assert!(!config.requires_signature());
+        assert_eq!(config.trusted_keys, None);
+    }
+
+    #[test]
+    fn reads_require_signature() {
+        let config =
+            RpmConfig::from_lookup(|k| (k == "RPM_REQUIRE_SIGNATURE").then(|| "yes".into()));
+        assert!(config.requires_signature());
+        let config =
+            RpmConfig::from_lookup(|k| (k == "RPM_REQUIRE_SIGNATURE").then(|| "off".into()));
+        assert!(!config.requires_signature());
+    }
+
+    #[test]
+    fn unparsable_require_signature_fails_closed() {
+        for value in ["ture", "", "enabled"] {
+            let config =
+                RpmConfig::from_lookup(|k| (k == "RPM_REQUIRE_SIGNATURE").then(|| value.into()));
+            assert!(config.requires_signature(), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn trusted_keys_require_signatures() {
+        let config = RpmConfig::from_lookup(|k| (k == "RPM_TRUSTED_KEYS").then(|| "key".into()));
+        assert!(config.requires_signature());
+        let config = RpmConfig::from_lookup(|k| (k == "RPM_TRUSTED_KEYS").then(|| " \n".into()));
+        assert!(!config.requires_signature());
+    }
+}
@@ -0,0 +1,193 @@
+//! Per-distribution repository trees.
+//!
+//! One repository can hold builds for several distributions. Besides the flat tree at
+//! the repository root, packages are served in `/{releasever}/{basearch}/` trees, the
+//! layout dnf's `$releasever` and `$basearch` variables expand to in a `baseurl`. A tree
+//! holds the packages whose dist tag matches the release and whose arch belongs to the
+//! base architecture; `noarch` packages and packages without a dist tag appear in every
+//! tree. `/{releasever}/SRPMS/` holds the release's source packages.
+
+/// The `basearch` segment that selects a release's source packages.
+const SOURCE_BASEARCH: &str = "SRPMS";
+
+/// Dist tag prefixes recognized in a release, such as `el9` or `fc39`.
+const DIST_PREFIXES: [&str; 5] = ["el", "fc", "amzn", "ol", "mga"];
+
+/// Arches that share a base architecture other than themselves, as dnf groups them.
+const ARCH_FAMILIES: [(&str, &[&str]); 3] = [
+    (
+        "i386",
+        &[
+            "i486", "i586", "i686", "athlon", "geode", "pentium3", "pentium4",
+        ],
+    ),
+    ("armhfp", &["armv6hl", "armv7hl", "armv7hnl"]),
+    ("x86_64", &["amd64", "ia32e"]),
+];
+
+/// A `/{releasever}/{basearch}` repository tree.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Tree<'a> {
+    /// A bare version (`9`, `9.4`, `39`) or a dist tag (`el9`, `fc39`).
+    pub(crate) releasever: &'a str,
+    pub(crate) basearch: &'a str,
+}
+
+impl<'a> Tree<'a> {
+    /// Split a request path into its tree and the path within the tree.
+    ///
+    /// Only paths that continue into `repodata` or `packages`, or name the tree's
+    /// metalink or mirrorlist, name a tree, so the flat routes and `/SRPMS/` are never
+    /// mistaken for one.
+    pub(crate) fn split(path: &'a str) -> Option<(Self, &'a str)> {
+        let mut segments = path.strip_prefix('/')?.splitn(3, '/');
+        let releasever = segments.next()?;
+        let basearch = segments.next()?;
+        let rest = &path[releasever.len() + basearch.len() + 2..];
+
+        let is_tree_path = ["/repodata/", "/packages/", "/Packages/"]
+            .iter()
+            .any(|p| rest.starts_with(p))
+            || rest == "/metalink"
+            || rest == "/mirrorlist";
+        let valid_basearch = !basearch.is_empty()
+            && basearch
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_tree_path || !valid_basearch || release_major(releasever).is_none() {
+            return None;
+        }
+        Some((
+            Tree {
+                releasever,
+                basearch,
+            },
+            rest,
+        ))
+    }
+
+    /// Whether this is a release's source package tree.
+    pub(crate) fn is_source(&self) -> bool {
+        self.basearch == SOURCE_BASEARCH
+    }
+
+    /// Whether a package with this release and arch belongs in the tree.
+    pub(crate) fn contains(&self, release: &str, arch: &str) -> bool {
+        let release_matches = dist_tag(release).is_none_or(|tag| self.matches_dist(tag));
+        let arch_matches = self.is_source() || arch == "noarch" || basearch(arch) == self.basearch;
+        release_matches && arch_matches
+    }
+
+    /// A bare version matches any dist tag with that major version; a dist tag
+    /// matches only itself.
+    fn matches_dist(&self, tag: &str) -> bool {
+        if self.releasever.starts_with(|c: char| c.is_ascii_digit()) {
+            release_major(self.releasever) == release_major(tag)
+        } else {
+            self.releasever.eq_ignore_ascii_case(tag)
+        }
+    }
+}
+
+/// The dist tag in a package release, such as `el9` in `1.el9_3` or
+/// `1.module+el9.4.0+1234+abcdef`.
+fn dist_tag(release: &str) -> Option<&str> {
+    release.split(['.', '+', '_']).find(|token| {
+        DIST_PREFIXES.iter().any(|prefix| {
+            token
+                .strip_prefix(prefix)
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        })
+    })
+}
+
+/// The major version in a releasever or dist tag: `9` in `9.4`, `el9`, or `el9_3`.
+fn release_major(release: &str) -> Option<&str> {
+    let digits = release.trim_start_matches(|c: char| c.is_ascii_lowercase());
+    let major = digits.split(['.', '_']).next()?;
+    let valid = !major.is_empty() && major.bytes().all(|b| b.is_ascii_digit());
+    valid.then_some(major)
+}
+
+/// The base architecture a package arch is served under.
+fn basearch(arch: &str) -> &str {
+    ARCH_FAMILIES
+        .iter()
+        .find(|(_, members)| members.contains(&arch))
+        .map_or(arch, |(base, _)| base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(path: &str) -> Tree<'_> {
+        Tree::split(path).unwrap().0
+    }
+
+    #[test]
+    fn splits_tree_paths() {
+        assert_eq!(
+            Tree::split("/el9/x86_64/repodata/repomd.xml"),
+            Some((
+                Tree {
+                    releasever: "el9",
+                    basearch: "x86_64",
+                },
+                "/repodata/repomd.xml"
+            ))
+        );
+        assert_eq!(
+            Tree::split("/9/SRPMS/packages/bash-5.2.26-1.el9.src.rpm")
+                .unwrap()
+                .1,
+            "/packages/bash-5.2.26-1.el9.src.rpm"
+        );
+        assert_eq!(Tree::split("/el9/aarch64/metalink").unwrap().1, "/metalink");
+        assert_eq!(Tree::split("/repodata/repomd.xml"), None);
+        assert_eq!(Tree::split("/SRPMS/repodata/repomd.xml"), None);
+        assert_eq!(Tree::split("/stable/x86_64/repodata/repomd.xml"), None);
+        assert_eq!(Tree::split("/el9/x86_64/other/file"), None);
+    }
+
+    #[test]
+    fn finds_dist_tags() {
+        assert_eq!(dist_tag("1.el9"), Some("el9"));
+        assert_eq!(dist_tag("1.el9_3"), Some("el9"));
+        assert_eq!(dist_tag("3.fc39"), Some("fc39"));
+        assert_eq!(dist_tag("1.module+el8.9.0+1234+abcdef"), Some("el8"));
+        assert_eq!(dist_tag("1"), None);
+        assert_eq!(dist_tag("0.1.git20240101"), None);
+    }
+
+    #[test]
+    fn filters_by_release() {
+        let el9 = tree("/el9/x86_64/repodata/repomd.xml");
+        assert!(el9.contains("1.el9", "x86_64"));
+        assert!(el9.contains("1.el9_3", "x86_64"));
+        assert!(!el9.contains("1.el8", "x86_64"));
+        assert!(!el9.contains("1.fc39", "x86_64"));
+        // Packages without a dist tag aren't tied to a release
+        assert!(el9.contains("1", "x86_64"));
+
+        let nine = tree("/9.4/x86_64/repodata/repomd.xml");
+        assert!(nine.contains("1.el9", "x86_64"));
+        assert!(!nine.contains("1.el8", "x86_64"));
+        assert!(tree("/39/x86_64/repodata/repomd.xml").contains("1.fc39", "x86_64"));
+    }
+
+    #[test]
+    fn filters_by_basearch() {
+        let x86 = tree("/el9/x86_64/repodata/repomd.xml");
+        assert!(x86.contains("1.el9", "noarch"));
+        assert!(!x86.contains("1.el9", "aarch64"));
+        assert!(!x86.contains("1.el9", "i686"));
+        assert!(tree("/el9/i386/repodata/repomd.xml").contains("1.el9", "i686"));
+        assert!(tree("/el9/aarch64/repodata/repomd.xml").contains("1.el9", "noarch"));
+
+        let source = tree("/el9/SRPMS/repodata/repomd.xml");
+        assert!(source.is_source());
+        assert!(source.contains("1.el9", "src"));
+        assert!(!source.contains("1.el8", "src"));
+    }
+}
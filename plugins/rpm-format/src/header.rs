@@ -0,0 +1,1609 @@
+//! RPM binary layout parsing.
+//!
+//! An RPM package is laid out as:
+//!
+//! ```text
+//! lead (96 bytes) | signature header (padded to 8 bytes) | header | payload
+//! ```
+//!
+//! The signature header and the main header share the same structure: a 16-byte
+//! intro (magic, reserved bytes, index entry count, store size), followed by
+//! 16-byte index entries, followed by the data store the entries point into.
+//! All integers are big-endian.
+
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use super::openpgp;
+use super::{RPM_LEAD_SIZE, RPM_MAGIC};
+
+/// Header structure magic: 0x8e 0xad 0xe8 followed by header version 0x01.
+pub(crate) const HEADER_MAGIC: [u8; 4] = [0x8e, 0xad, 0xe8, 0x01];
+
+/// Size of the header intro (magic, reserved, nindex, hsize).
+const HEADER_INTRO_SIZE: usize = 16;
+
+/// Size of a single header index entry (tag, type, offset, count).
+const INDEX_ENTRY_SIZE: usize = 16;
+
+// Header data types.
+const RPM_NULL_TYPE: u32 = 0;
+const RPM_CHAR_TYPE: u32 = 1;
+const RPM_INT8_TYPE: u32 = 2;
+const RPM_INT16_TYPE: u32 = 3;
+const RPM_INT32_TYPE: u32 = 4;
+const RPM_INT64_TYPE: u32 = 5;
+const RPM_STRING_TYPE: u32 = 6;
+const RPM_BIN_TYPE: u32 = 7;
+const RPM_STRING_ARRAY_TYPE: u32 = 8;
+const RPM_I18NSTRING_TYPE: u32 = 9;
+
+/// Lead major versions rpm can read.
+const SUPPORTED_LEAD_MAJORS: [u8; 2] = [3, 4];
+
+/// Lead signature type for a header-style signature, the only kind rpm has written since 3.0.
+const RPMSIGTYPE_HEADERSIG: u16 = 5;
+
+/// Limits rpm itself enforces on a header's index entry count and store size.
+const MAX_HEADER_ENTRIES: usize = 0xffff;
+const MAX_HEADER_STORE: usize = 256 * 1024 * 1024;
+
+// Signature header tags.
+pub(crate) const RPMSIGTAG_DSA: u32 = 267;
+pub(crate) const RPMSIGTAG_RSA: u32 = 268;
+pub(crate) const RPMSIGTAG_SHA1: u32 = 269;
+pub(crate) const RPMSIGTAG_SHA256: u32 = 273;
+pub(crate) const RPMSIGTAG_SIZE: u32 = 1000;
+pub(crate) const RPMSIGTAG_PGP: u32 = 1002;
+pub(crate) const RPMSIGTAG_GPG: u32 = 1005;
+
+/// OpenPGP signature tags, in the order rpm prefers them: header-only signatures
+/// first, then the legacy header+payload signatures.
+const SIGNATURE_TAGS: [u32; 4] = [RPMSIGTAG_RSA, RPMSIGTAG_DSA, RPMSIGTAG_PGP, RPMSIGTAG_GPG];
+
+// Main header tags.
+pub(crate) const RPMTAG_NAME: u32 = 1000;
+pub(crate) const RPMTAG_VERSION: u32 = 1001;
+pub(crate) const RPMTAG_RELEASE: u32 = 1002;
+pub(crate) const RPMTAG_EPOCH: u32 = 1003;
+pub(crate) const RPMTAG_SUMMARY: u32 = 1004;
+pub(crate) const RPMTAG_DESCRIPTION: u32 = 1005;
+pub(crate) const RPMTAG_BUILDTIME: u32 = 1006;
+pub(crate) const RPMTAG_LICENSE: u32 = 1014;
+pub(crate) const RPMTAG_PACKAGER: u32 = 1015;
+pub(crate) const RPMTAG_URL: u32 = 1020;
+pub(crate) const RPMTAG_ARCH: u32 = 1022;
+pub(crate) const RPMTAG_OLDFILENAMES: u32 = 1027;
+pub(crate) const RPMTAG_FILEMODES: u32 = 1030;
+pub(crate) const RPMTAG_FILEFLAGS: u32 = 1037;
+pub(crate) const RPMTAG_SOURCERPM: u32 = 1044;
+pub(crate) const RPMTAG_PROVIDENAME: u32 = 1047;
+pub(crate) const RPMTAG_REQUIREFLAGS: u32 = 1048;
+pub(crate) const RPMTAG_REQUIRENAME: u32 = 1049;
+pub(crate) const RPMTAG_REQUIREVERSION: u32 = 1050;
+pub(crate) const RPMTAG_NOSOURCE: u32 = 1051;
+pub(crate) const RPMTAG_NOPATCH: u32 = 1052;
+pub(crate) const RPMTAG_CONFLICTFLAGS: u32 = 1053;
+pub(crate) const RPMTAG_CONFLICTNAME: u32 = 1054;
+pub(crate) const RPMTAG_CONFLICTVERSION: u32 = 1055;
+pub(crate) const RPMTAG_CHANGELOGTIME: u32 = 1080;
+pub(crate) const RPMTAG_CHANGELOGNAME: u32 = 1081;
+pub(crate) const RPMTAG_CHANGELOGTEXT: u32 = 1082;
+pub(crate) const RPMTAG_OBSOLETENAME: u32 = 1090;
+pub(crate) const RPMTAG_PROVIDEFLAGS: u32 = 1112;
+pub(crate) const RPMTAG_PROVIDEVERSION: u32 = 1113;
+pub(crate) const RPMTAG_OBSOLETEFLAGS: u32 = 1114;
+pub(crate) const RPMTAG_OBSOLETEVERSION: u32 = 1115;
+pub(crate) const RPMTAG_DIRINDEXES: u32 = 1116;
+pub(crate) const RPMTAG_BASENAMES: u32 = 1117;
+pub(crate) const RPMTAG_DIRNAMES: u32 = 1118;
+pub(crate) const RPMTAG_PAYLOADFORMAT: u32 = 1124;
+pub(crate) const RPMTAG_PAYLOADCOMPRESSOR: u32 = 1125;
+pub(crate) const RPMTAG_RECOMMENDNAME: u32 = 5046;
+pub(crate) const RPMTAG_RECOMMENDVERSION: u32 = 5047;
+pub(crate) const RPMTAG_RECOMMENDFLAGS: u32 = 5048;
+pub(crate) const RPMTAG_SUGGESTNAME: u32 = 5049;
+pub(crate) const RPMTAG_SUGGESTVERSION: u32 = 5050;
+pub(crate) const RPMTAG_SUGGESTFLAGS: u32 = 5051;
+pub(crate) const RPMTAG_SUPPLEMENTNAME: u32 = 5052;
+pub(crate) const RPMTAG_SUPPLEMENTVERSION: u32 = 5053;
+pub(crate) const RPMTAG_SUPPLEMENTFLAGS: u32 = 5054;
+pub(crate) const RPMTAG_ENHANCENAME: u32 = 5055;
+pub(crate) const RPMTAG_ENHANCEVERSION: u32 = 5056;
+pub(crate) const RPMTAG_ENHANCEFLAGS: u32 = 5057;
+pub(crate) const RPMTAG_PAYLOADDIGEST: u32 = 5092;
+pub(crate) const RPMTAG_PAYLOADDIGESTALGO: u32 = 5093;
+
+// OpenPGP hash algorithm IDs, as used by PAYLOADDIGESTALGO.
+const PGPHASHALGO_SHA1: u32 = 2;
+const PGPHASHALGO_SHA256: u32 = 8;
+const PGPHASHALGO_SHA384: u32 = 9;
+const PGPHASHALGO_SHA512: u32 = 10;
+const PGPHASHALGO_SHA224: u32 = 11;
+
+/// FILEFLAGS bit marking a file the package owns but doesn't ship.
+const RPMFILE_GHOST: u32 = 1 << 6;
+
+// RPMSENSE_* dependency flag bits.
+pub(crate) const RPMSENSE_LESS: u32 = 1 << 1;
+pub(crate) const RPMSENSE_GREATER: u32 = 1 << 2;
+pub(crate) const RPMSENSE_EQUAL: u32 = 1 << 3;
+pub(crate) const RPMSENSE_PREREQ: u32 = 1 << 6;
+pub(crate) const RPMSENSE_SCRIPT_PRE: u32 = 1 << 9;
+pub(crate) const RPMSENSE_SCRIPT_POST: u32 = 1 << 10;
+
+/// File type bits of a FILEMODES entry, and the directory type.
+const S_IFMT: u16 = 0o170000;
+const S_IFDIR: u16 = 0o040000;
+
+/// The fixed-size lead at the start of every RPM file.
+pub(crate) struct Lead {
+    pub(crate) major: u8,
+    pub(crate) minor: u8,
+    /// 0 for binary packages, 1 for source packages.
+    pub(crate) package_type: u16,
+    pub(crate) signature_type: u16,
+}
+
+/// A single header index entry.
+struct IndexEntry {
+    tag: u32,
+    kind: u32,
+    offset: u32,
+    count: u32,
+}
+
+/// A parsed header structure: its index entries and the data store they reference.
+pub(crate) struct Header<'a> {
+    entries: Vec<IndexEntry>,
+    store: &'a [u8],
+}
+
+/// The structural parts of an RPM file.
+pub(crate) struct RpmFile<'a> {
+    pub(crate) lead: Lead,
+    pub(crate) signature: Header<'a>,
+    /// Bytes between the end of the signature header and the 8-byte aligned main header.
+    pub(crate) signature_padding: &'a [u8],
+    pub(crate) header: Header<'a>,
+    /// The main header exactly as stored, from its intro to the end of its store.
+    pub(crate) header_bytes: &'a [u8],
+    pub(crate) payload: &'a [u8],
+}
+
+/// Package identity and descriptive fields read from the main header.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct RpmHeaderInfo {
+    pub(crate) name: String,
+    pub(crate) epoch: Option<u32>,
+    pub(crate) version: String,
+    pub(crate) release: String,
+    pub(crate) arch: String,
+    pub(crate) summary: Option<String>,
+    pub(crate) description: Option<String>,
+    pub(crate) license: Option<String>,
+    pub(crate) url: Option<String>,
+    pub(crate) packager: Option<String>,
+    pub(crate) is_source: bool,
+    /// Whether a source package leaves out some of its sources or patches (a `nosrc` package).
+    #[serde(default)]
+    pub(crate) no_source: bool,
+    /// Filename of the source package a binary package was built from.
+    #[serde(default)]
+    pub(crate) source_rpm: Option<String>,
+    /// The package's OpenPGP signature, from the signature header.
+    #[serde(default)]
+    pub(crate) signature: Option<PackageSignature>,
+    /// Build time in seconds since the Unix epoch.
+    #[serde(default)]
+    pub(crate) build_time: Option<u32>,
+    #[serde(default)]
+    pub(crate) files: Vec<PackageFile>,
+    /// Changelog entries, newest first as stored in the header.
+    #[serde(default)]
+    pub(crate) changelogs: Vec<ChangelogEntry>,
+    #[serde(default)]
+    pub(crate) provides: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) requires: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) conflicts: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) obsoletes: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) recommends: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) suggests: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) supplements: Vec<Dependency>,
+    #[serde(default)]
+    pub(crate) enhances: Vec<Dependency>,
+}
+
+/// Who signed a package, and how.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PackageSignature {
+    /// Issuer key ID as 16 lowercase hex digits.
+    pub(crate) key_id: String,
+    /// Public-key and hash algorithm, e.g. `RSA/SHA256`.
+    pub(crate) algorithm: String,
+}
+
+/// A dependency from one of the NAME/FLAGS/VERSION tag families.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Dependency {
+    pub(crate) name: String,
+    /// RPMSENSE_* bits: comparison operator and prerequisite markers.
+    pub(crate) flags: u32,
+    /// `[epoch:]version[-release]`, empty for unversioned dependencies.
+    pub(crate) version: String,
+}
+
+/// A single `%changelog` entry.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ChangelogEntry {
+    pub(crate) author: String,
+    pub(crate) date: u32,
+    pub(crate) text: String,
+}
+
+/// A path owned by a package, as listed in filelists.xml.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PackageFile {
+    pub(crate) path: String,
+    #[serde(rename = "type")]
+    pub(crate) file_type: FileType,
+}
+
+/// The `type` attribute of a filelists.xml `<file>` element.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FileType {
+    File,
+    Dir,
+    Ghost,
+}
+
+/// Parse the lead, signature header, and main header of an RPM file.
+pub(crate) fn parse_rpm(data: &[u8]) -> Result<RpmFile<'_>, String> {
+    let lead = read_lead(data)?;
+
+    let (signature, signature_len) = parse_header(data, RPM_LEAD_SIZE, "signature header")?;
+
+    // The signature header store is padded so the main header starts on an 8-byte boundary.
+    let signature_end = RPM_LEAD_SIZE + signature_len;
+    let header_offset = align8(signature_end);
+    let (header, header_len) = parse_header(data, header_offset, "header")?;
+    let header_end = header_offset + header_len;
+
+    Ok(RpmFile {
+        lead,
+        signature,
+        signature_padding: &data[signature_end..header_offset],
+        header,
+        header_bytes: &data[header_offset..header_end],
+        payload: &data[header_end..],
+    })
+}
+
+/// Read the 96-byte lead.
+fn read_lead(data: &[u8]) -> Result<Lead, String> {
+    if data.len() < RPM_LEAD_SIZE {
+        return Err(format!(
+            "File too small for RPM lead: {} bytes (minimum {})",
+            data.len(),
+            RPM_LEAD_SIZE
+        ));
+    }
+    if data[..4] != RPM_MAGIC {
+        return Err("Invalid RPM magic".to_string());
+    }
+
+    Ok(Lead {
+        major: data[4],
+        minor: data[5],
+        package_type: u16::from_be_bytes([data[6], data[7]]),
+        signature_type: u16::from_be_bytes([data[78], data[79]]),
+    })
+}
+
+/// Parse a header structure starting at `offset`, returning it and its total length in bytes.
+fn parse_header<'a>(
+    data: &'a [u8],
+    offset: usize,
+    what: &str,
+) -> Result<(Header<'a>, usize), String> {
+    let intro = data
+        .get(offset..offset + HEADER_INTRO_SIZE)
+        .ok_or_else(|| format!("Truncated {what}: missing intro at offset {offset}"))?;
+    if intro[..4] != HEADER_MAGIC {
+        return Err(format!(
+            "Invalid {what} magic at offset {offset}: expected [8e, ad, e8, 01], \
+             got [{:02x}, {:02x}, {:02x}, {:02x}]",
+            intro[0], intro[1], intro[2], intro[3]
+        ));
+    }
+
+    let nindex = read_u32(intro, 8) as usize;
+    let hsize = read_u32(intro, 12) as usize;
+
+    let index_start = offset + HEADER_INTRO_SIZE;
+    let store_start = nindex
+        .checked_mul(INDEX_ENTRY_SIZE)
+        .and_then(|n| n.checked_add(index_start))
+        .ok_or_else(|| format!("Invalid {what}: index count {nindex} overflows"))?;
+    let store_end = store_start
+        .checked_add(hsize)
+        .filter(|&end| end <= data.len())
+        .ok_or_else(|| {
+            format!(
+                "Truncated {what}: {nindex} index entries and {hsize} store bytes \
+                 exceed file size {}",
+                data.len()
+            )
+        })?;
+
+    let entries = data[index_start..store_start]
+        .as_chunks::<INDEX_ENTRY_SIZE>()
+        .0
+        .iter()
+        .map(|e| IndexEntry {
+            tag: read_u32(e, 0),
+            kind: read_u32(e, 4),
+            offset: read_u32(e, 8),
+            count: read_u32(e, 12),
+        })
+        .collect();
+
+    let header = Header {
+        entries,
+        store: &data[store_start..store_end],
+    };
+    Ok((header, store_end - offset))
+}
+
+impl Header<'_> {
+    fn entry(&self, tag: u32) -> Option<&IndexEntry> {
+        self.entries.iter().find(|e| e.tag == tag)
+    }
+
+    /// Read a STRING tag, or the first value of an I18NSTRING tag.
+    pub(crate) fn string(&self, tag: u32) -> Option<String> {
+        let entry = self.entry(tag)?;
+        match entry.kind {
+            RPM_STRING_TYPE | RPM_I18NSTRING_TYPE | RPM_STRING_ARRAY_TYPE => {
+                read_cstr(self.store, entry.offset as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read a STRING_ARRAY or I18NSTRING tag as a list of strings.
+    pub(crate) fn string_array(&self, tag: u32) -> Vec<String> {
+        let Some(entry) = self.entry(tag) else {
+            return Vec::new();
+        };
+        if !matches!(
+            entry.kind,
+            RPM_STRING_ARRAY_TYPE | RPM_I18NSTRING_TYPE | RPM_STRING_TYPE
+        ) {
+            return Vec::new();
+        }
+
+        let mut values = Vec::new();
+        let mut pos = entry.offset as usize;
+        for _ in 0..entry.count {
+            let Some(bytes) = cstr_bytes(self.store, pos) else {
+                break;
+            };
+            pos += bytes.len() + 1;
+            values.push(String::from_utf8_lossy(bytes).into_owned());
+        }
+        values
+    }
+
+    /// Read a BIN tag.
+    pub(crate) fn bytes(&self, tag: u32) -> Option<&[u8]> {
+        let entry = self.entry(tag).filter(|e| e.kind == RPM_BIN_TYPE)?;
+        let start = entry.offset as usize;
+        self.store
+            .get(start..start.checked_add(entry.count as usize)?)
+    }
+
+    /// Read an INT32 tag as a list of values.
+    pub(crate) fn u32_array(&self, tag: u32) -> Vec<u32> {
+        match self.entry(tag) {
+            Some(e) if e.kind == RPM_INT32_TYPE => self
+                .store
+                .get(e.offset as usize..)
+                .map(|s| {
+                    s.as_chunks::<4>()
+                        .0
+                        .iter()
+                        .take(e.count as usize)
+                        .map(|c| u32::from_be_bytes(*c))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Read the first value of an INT32 tag.
+    pub(crate) fn u32(&self, tag: u32) -> Option<u32> {
+        self.u32_array(tag).first().copied()
+    }
+
+    /// Read an INT16 tag as a list of values.
+    pub(crate) fn u16_array(&self, tag: u32) -> Vec<u16> {
+        match self.entry(tag) {
+            Some(e) if e.kind == RPM_INT16_TYPE => self
+                .store
+                .get(e.offset as usize..)
+                .map(|s| {
+                    s.as_chunks::<2>()
+                        .0
+                        .iter()
+                        .take(e.count as usize)
+                        .map(|c| u16::from_be_bytes(*c))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl RpmHeaderInfo {
+    /// Extract package identity and descriptive fields from a parsed RPM.
+    pub(crate) fn from_rpm(rpm: &RpmFile<'_>) -> Result<Self, String> {
+        let h = &rpm.header;
+        let required = |tag: u32, label: &str| {
+            h.string(tag)
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| format!("RPM header is missing the {label} tag"))
+        };
+
+        Ok(RpmHeaderInfo {
+            name: required(RPMTAG_NAME, "NAME")?,
+            epoch: h.u32(RPMTAG_EPOCH),
+            version: required(RPMTAG_VERSION, "VERSION")?,
+            release: required(RPMTAG_RELEASE, "RELEASE")?,
+            arch: required(RPMTAG_ARCH, "ARCH")?,
+            summary: h.string(RPMTAG_SUMMARY),
+            description: h.string(RPMTAG_DESCRIPTION),
+            license: h.string(RPMTAG_LICENSE),
+            url: h.string(RPMTAG_URL),
+            packager: h.string(RPMTAG_PACKAGER),
+            is_source: rpm.lead.package_type == 1,
+            no_source: rpm.lead.package_type == 1
+                && (h.entry(RPMTAG_NOSOURCE).is_some() || h.entry(RPMTAG_NOPATCH).is_some()),
+            source_rpm: h.string(RPMTAG_SOURCERPM).filter(|s| !s.is_empty()),
+            signature: read_signature(&rpm.signature)?,
+            build_time: h.u32(RPMTAG_BUILDTIME),
+            files: read_files(h),
+            changelogs: read_changelogs(h),
+            provides: read_dependencies(
+                h,
+                RPMTAG_PROVIDENAME,
+                RPMTAG_PROVIDEFLAGS,
+                RPMTAG_PROVIDEVERSION,
+            ),
+            requires: read_dependencies(
+                h,
+                RPMTAG_REQUIRENAME,
+                RPMTAG_REQUIREFLAGS,
+                RPMTAG_REQUIREVERSION,
+            ),
+            conflicts: read_dependencies(
+                h,
+                RPMTAG_CONFLICTNAME,
+                RPMTAG_CONFLICTFLAGS,
+                RPMTAG_CONFLICTVERSION,
+            ),
+            obsoletes: read_dependencies(
+                h,
+                RPMTAG_OBSOLETENAME,
+                RPMTAG_OBSOLETEFLAGS,
+                RPMTAG_OBSOLETEVERSION,
+            ),
+            recommends: read_dependencies(
+                h,
+                RPMTAG_RECOMMENDNAME,
+                RPMTAG_RECOMMENDFLAGS,
+                RPMTAG_RECOMMENDVERSION,
+            ),
+            suggests: read_dependencies(
+                h,
+                RPMTAG_SUGGESTNAME,
+                RPMTAG_SUGGESTFLAGS,
+                RPMTAG_SUGGESTVERSION,
+            ),
+            supplements: read_dependencies(
+                h,
+                RPMTAG_SUPPLEMENTNAME,
+                RPMTAG_SUPPLEMENTFLAGS,
+                RPMTAG_SUPPLEMENTVERSION,
+            ),
+            enhances: read_dependencies(
+                h,
+                RPMTAG_ENHANCENAME,
+                RPMTAG_ENHANCEFLAGS,
+                RPMTAG_ENHANCEVERSION,
+            ),
+        })
+    }
+
+    /// The architecture component used in this package's canonical filename.
+    ///
+    /// Source packages use `src`, or `nosrc` when built with NoSource/NoPatch.
+    pub(crate) fn filename_arch(&self) -> &str {
+        match (self.is_source, self.no_source) {
+            (true, true) => "nosrc",
+            (true, false) => "src",
+            _ => &self.arch,
+        }
+    }
+
+    /// The canonical `name-version-release.arch.rpm` filename for this package.
+    pub(crate) fn canonical_filename(&self) -> String {
+        format!(
+            "{}-{}-{}.{}.rpm",
+            self.name,
+            self.version,
+            self.release,
+            self.filename_arch()
+        )
+    }
+}
+
+/// Read the package file list from BASENAMES/DIRNAMES/DIRINDEXES, falling back to
+/// OLDFILENAMES for packages built without compressed file names.
+fn read_files(h: &Header<'_>) -> Vec<PackageFile> {
+    let basenames = h.string_array(RPMTAG_BASENAMES);
+    let paths: Vec<String> = if basenames.is_empty() {
+        h.string_array(RPMTAG_OLDFILENAMES)
+    } else {
+        let dirnames = h.string_array(RPMTAG_DIRNAMES);
+        let dirindexes = h.u32_array(RPMTAG_DIRINDEXES);
+        basenames
+            .iter()
+            .zip(&dirindexes)
+            .filter_map(|(base, &idx)| dirnames.get(idx as usize).map(|dir| format!("{dir}{base}")))
+            .collect()
+    };
+
+    let flags = h.u32_array(RPMTAG_FILEFLAGS);
+    let modes = h.u16_array(RPMTAG_FILEMODES);
+
+    paths
+        .into_iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let is_ghost = flags.get(i).is_some_and(|f| f & RPMFILE_GHOST != 0);
+            let is_dir = modes.get(i).is_some_and(|m| m & S_IFMT == S_IFDIR);
+            let file_type = if is_ghost {
+                FileType::Ghost
+            } else if is_dir {
+                FileType::Dir
+            } else {
+                FileType::File
+            };
+            PackageFile { path, file_type }
+        })
+        .collect()
+}
+
+/// Decode the first OpenPGP signature tag present in the signature header.
+fn read_signature(sig: &Header<'_>) -> Result<Option<PackageSignature>, String> {
+    let Some((tag, packet)) = SIGNATURE_TAGS
+        .iter()
+        .find_map(|&tag| sig.bytes(tag).map(|b| (tag, b)))
+    else {
+        return Ok(None);
+    };
+
+    let info = openpgp::parse_signature(packet)
+        .map_err(|e| format!("Invalid signature in signature header tag {tag}: {e}"))?;
+    Ok(Some(PackageSignature {
+        algorithm: info.algorithm(),
+        key_id: info.key_id,
+    }))
+}
+
+/// The package's OpenPGP signature packet and the data it covers: the main header for
+/// RSAHEADER and DSAHEADER, or the header and payload for the legacy PGP and GPG tags.
+///
+/// A header-only signature protects the payload only through the header's
+/// PAYLOADDIGEST, so packages without one fall back to their header-and-payload
+/// signature, if they have one.
+pub(crate) fn signed_data<'r>(rpm: &'r RpmFile<'_>) -> Option<(&'r [u8], Vec<&'r [u8]>)> {
+    let covers_payload = rpm.header.string(RPMTAG_PAYLOADDIGEST).is_some();
+    SIGNATURE_TAGS.iter().find_map(|&tag| {
+        let packet = rpm.signature.bytes(tag)?;
+        match tag {
+            RPMSIGTAG_RSA | RPMSIGTAG_DSA if covers_payload => {
+                Some((packet, vec![rpm.header_bytes]))
+            }
+            RPMSIGTAG_RSA | RPMSIGTAG_DSA => None,
+            _ => Some((packet, vec![rpm.header_bytes, rpm.payload])),
+        }
+    })
+}
+
+/// Read the changelog from the parallel CHANGELOGTIME/NAME/TEXT arrays.
+fn read_changelogs(h: &Header<'_>) -> Vec<ChangelogEntry> {
+    let times = h.u32_array(RPMTAG_CHANGELOGTIME);
+    let names = h.string_array(RPMTAG_CHANGELOGNAME);
+    let texts = h.string_array(RPMTAG_CHANGELOGTEXT);
+
+    times
+        .into_iter()
+        .zip(names)
+        .zip(texts)
+        .map(|((date, author), text)| ChangelogEntry { author, date, text })
+        .collect()
+}
+
+/// Read one dependency tag family from its parallel NAME/FLAGS/VERSION arrays.
+fn read_dependencies(
+    h: &Header<'_>,
+    name_tag: u32,
+    flags_tag: u32,
+    version_tag: u32,
+) -> Vec<Dependency> {
+    let flags = h.u32_array(flags_tag);
+    let versions = h.string_array(version_tag);
+
+    h.string_array(name_tag)
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| Dependency {
+            name,
+            flags: flags.get(i).copied().unwrap_or(0),
+            version: versions.get(i).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Structural validation
+// ---------------------------------------------------------------------------
+
+/// Check an RPM file's structure beyond what parsing needs.
+///
+/// Covers the lead version, package type, and signature type; every index entry of both
+/// headers; the signature header padding; the header+payload size recorded in the
+/// signature; the payload format and compressor; and the header and payload digests.
+pub(crate) fn validate_rpm(data: &[u8]) -> Result<(), String> {
+    check_lead(&read_lead(data)?)?;
+
+    let rpm = parse_rpm(data)?;
+    rpm.signature.check_entries("signature header")?;
+    if rpm.signature_padding.iter().any(|&b| b != 0) {
+        return Err(format!(
+            "Invalid signature header: {} bytes of padding before the main header are not zero",
+            rpm.signature_padding.len()
+        ));
+    }
+    rpm.header.check_entries("header")?;
+
+    if let Some(size) = rpm.signature.u32(RPMSIGTAG_SIZE) {
+        let actual = rpm.header_bytes.len() + rpm.payload.len();
+        if size as usize != actual {
+            return Err(format!(
+                "Truncated or corrupted RPM: signature header records {size} bytes of header \
+                 and payload, found {actual}"
+            ));
+        }
+    }
+
+    check_payload(&rpm)?;
+    verify_digests(&rpm)
+}
+
+/// Recompute the header and payload digests and compare them with the recorded values.
+///
+/// SHA256HEADER and SHA1HEADER in the signature header cover the main header;
+/// PAYLOADDIGEST in the main header covers the compressed payload, hashed with
+/// PAYLOADDIGESTALGO. Packages built before a digest existed simply lack its tag.
+fn verify_digests(rpm: &RpmFile<'_>) -> Result<(), String> {
+    let header_digests = [
+        (RPMSIGTAG_SHA256, PGPHASHALGO_SHA256, "SHA256HEADER"),
+        (RPMSIGTAG_SHA1, PGPHASHALGO_SHA1, "SHA1HEADER"),
+    ];
+    for (tag, algo, label) in header_digests {
+        if let Some(expected) = rpm.signature.string(tag) {
+            compare_digest(label, &expected, algo, rpm.header_bytes)?;
+        }
+    }
+
+    if let Some(expected) = rpm.header.string(RPMTAG_PAYLOADDIGEST) {
+        let algo = rpm
+            .header
+            .u32(RPMTAG_PAYLOADDIGESTALGO)
+            .ok_or("RPM header has a PAYLOADDIGEST but no PAYLOADDIGESTALGO")?;
+        compare_digest("PAYLOADDIGEST", &expected, algo, rpm.payload)?;
+    }
+    Ok(())
+}
+
+fn compare_digest(label: &str, expected: &str, algo: u32, data: &[u8]) -> Result<(), String> {
+    let actual = hex_digest(algo, data)
+        .ok_or_else(|| format!("Unsupported {label} digest algorithm {algo}"))?;
+    if !expected.eq_ignore_ascii_case(&actual) {
+        return Err(format!(
+            "{label} mismatch: package records {expected}, computed {actual}"
+        ));
+    }
+    Ok(())
+}
+
+/// Lowercase hex digest of `data` with an OpenPGP hash algorithm, if supported.
+fn hex_digest(algo: u32, data: &[u8]) -> Option<String> {
+    let digest = match algo {
+        PGPHASHALGO_SHA1 => Sha1::digest(data).to_vec(),
+        PGPHASHALGO_SHA224 => Sha224::digest(data).to_vec(),
+        PGPHASHALGO_SHA256 => Sha256::digest(data).to_vec(),
+        PGPHASHALGO_SHA384 => Sha384::digest(data).to_vec(),
+        PGPHASHALGO_SHA512 => Sha512::digest(data).to_vec(),
+        _ => return None,
+    };
+    Some(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+fn check_lead(lead: &Lead) -> Result<(), String> {
+    if !SUPPORTED_LEAD_MAJORS.contains(&lead.major) {
+        return Err(format!(
+            "Unsupported RPM lead version {}.{}: expected major version 3 or 4",
+            lead.major, lead.minor
+        ));
+    }
+    if lead.minor != 0 {
+        return Err(format!(
+            "Unsupported RPM lead version {}.{}: expected minor version 0",
+            lead.major, lead.minor
+        ));
+    }
+    if lead.package_type > 1 {
+        return Err(format!(
+            "Invalid RPM lead type {}: expected 0 (binary) or 1 (source)",
+            lead.package_type
+        ));
+    }
+    if lead.signature_type != RPMSIGTYPE_HEADERSIG {
+        return Err(format!(
+            "Unsupported RPM signature type {}: expected {RPMSIGTYPE_HEADERSIG} (header-style signature)",
+            lead.signature_type
+        ));
+    }
+    Ok(())
+}
+
+/// Check the payload format and compressor tags, and that the payload matches the compressor.
+fn check_payload(rpm: &RpmFile<'_>) -> Result<(), String> {
+    let format = rpm
+        .header
+        .string(RPMTAG_PAYLOADFORMAT)
+        .ok_or("RPM header is missing the PAYLOADFORMAT tag")?;
+    if format != "cpio" {
+        return Err(format!(
+            "Unsupported RPM payload format '{format}': expected 'cpio'"
+        ));
+    }
+
+    // rpm assumes gzip for packages that predate the compressor tag
+    let compressor = rpm
+        .header
+        .string(RPMTAG_PAYLOADCOMPRESSOR)
+        .unwrap_or_else(|| "gzip".to_string());
+    let magic: &[u8] = match compressor.as_str() {
+        "gzip" => &[0x1f, 0x8b],
+        "bzip2" => b"BZh",
+        "xz" => &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+        "lzma" => &[0x5d, 0x00, 0x00],
+        "zstd" => &[0x28, 0xb5, 0x2f, 0xfd],
+        other => return Err(format!("Unsupported RPM payload compressor '{other}'")),
+    };
+
+    if rpm.payload.is_empty() {
+        return Err("Truncated RPM: the payload is missing".to_string());
+    }
+    if !rpm.payload.starts_with(magic) {
+        return Err(format!(
+            "Invalid RPM payload: data does not start with the {compressor} magic bytes"
+        ));
+    }
+    Ok(())
+}
+
+impl Header<'_> {
+    /// Check that the header is within rpm's limits, and that every index entry has a
+    /// known type, is aligned for it, and lies entirely inside the store.
+    fn check_entries(&self, what: &str) -> Result<(), String> {
+        if self.entries.is_empty() {
+            return Err(format!("Invalid {what}: no index entries"));
+        }
+        if self.entries.len() > MAX_HEADER_ENTRIES {
+            return Err(format!(
+                "Invalid {what}: {} index entries exceed the limit of {MAX_HEADER_ENTRIES}",
+                self.entries.len()
+            ));
+        }
+        if self.store.len() > MAX_HEADER_STORE {
+            return Err(format!(
+                "Invalid {what}: {}-byte store exceeds the limit of {MAX_HEADER_STORE}",
+                self.store.len()
+            ));
+        }
+
+        for (i, e) in self.entries.iter().enumerate() {
+            let invalid = |problem: String| {
+                format!("Invalid {what}: index entry {i} (tag {}) {problem}", e.tag)
+            };
+            let offset = e.offset as usize;
+            let count = e.count as usize;
+
+            if count == 0 {
+                return Err(invalid("has a count of 0".to_string()));
+            }
+
+            let end = match e.kind {
+                RPM_STRING_TYPE if count != 1 => {
+                    return Err(invalid(format!("is a STRING with a count of {count}")));
+                }
+                RPM_STRING_TYPE | RPM_STRING_ARRAY_TYPE | RPM_I18NSTRING_TYPE => {
+                    strings_end(self.store, offset, count)
+                }
+                kind => {
+                    let size = fixed_type_size(kind)
+                        .ok_or_else(|| invalid(format!("has unknown type {kind}")))?;
+                    if !offset.is_multiple_of(size.max(1)) {
+                        return Err(invalid(format!(
+                            "offset {offset} is not aligned to {size} bytes"
+                        )));
+                    }
+                    count.checked_mul(size).and_then(|n| n.checked_add(offset))
+                }
+            };
+
+            if !end.is_some_and(|end| end <= self.store.len()) {
+                return Err(invalid(format!(
+                    "data at offset {offset} extends past the {}-byte store",
+                    self.store.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Size in bytes of one value of a fixed-size header data type.
+fn fixed_type_size(kind: u32) -> Option<usize> {
+    match kind {
+        RPM_NULL_TYPE => Some(0),
+        RPM_CHAR_TYPE | RPM_INT8_TYPE | RPM_BIN_TYPE => Some(1),
+        RPM_INT16_TYPE => Some(2),
+        RPM_INT32_TYPE => Some(4),
+        RPM_INT64_TYPE => Some(8),
+        _ => None,
+    }
+}
+
+/// The end offset of `count` consecutive NUL-terminated strings, if they all fit in `store`.
+fn strings_end(store: &[u8], offset: usize, count: usize) -> Option<usize> {
+    let mut pos = offset;
+    for _ in 0..count {
+        pos += cstr_bytes(store, pos)?.len() + 1;
+    }
+    Some(pos)
+}
+
+fn align8(n: usize) -> usize {
+    n.div_ceil(8) * 8
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+/// Read a NUL-terminated string from `store` starting at `offset`.
+fn read_cstr(store: &[u8], offset: usize) -> Option<String> {
+    cstr_bytes(store, offset).map(|b| String::from_utf8_lossy(b).into_owned())
+}
+
+/// The bytes of a NUL-terminated string, without the terminator.
+fn cstr_bytes(store: &[u8], offset: usize) -> Option<&[u8]> {
+    let rest = store.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(&rest[..end])
+}
+
+// ---------------------------------------------------------------------------
+// Test support
+// ---------------------------------------------------------------------------
+
+/// Builders for synthetic RPM files used by unit tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    /// A tag value to encode into a header store.
+    #[derive(Clone)]
+    pub(crate) enum Value {
+        Int16(Vec<u16>),
+        Int32(Vec<u32>),
+        Str(String),
+        StrArray(Vec<String>),
+        I18n(String),
+        Bin(Vec<u8>),
+    }
+
+    /// Builds a header structure (intro + index + store).
+    #[derive(Default, Clone)]
+    pub(crate) struct HeaderBuilder {
+        tags: Vec<(u32, Value)>,
+    }
+
+    impl HeaderBuilder {
+        pub(crate) fn new() -> Self {
+            Self::default()
+        }
+
+        pub(crate) fn tag(mut self, tag: u32, value: Value) -> Self {
+            self.tags.push((tag, value));
+            self
+        }
+
+        pub(crate) fn string(self, tag: u32, s: &str) -> Self {
+            self.tag(tag, Value::Str(s.to_string()))
+        }
+
+        pub(crate) fn has(&self, tag: u32) -> bool {
+            self.tags.iter().any(|(t, _)| *t == tag)
+        }
+
+        pub(crate) fn build(&self) -> Vec<u8> {
+            let mut index = Vec::new();
+            let mut store = Vec::new();
+            for (tag, value) in &self.tags {
+                let (kind, align, count, bytes) = match value {
+                    Value::Int16(v) => (
+                        RPM_INT16_TYPE,
+                        2,
+                        v.len(),
+                        v.iter().flat_map(|x| x.to_be_bytes()).collect::<Vec<_>>(),
+                    ),
+                    Value::Int32(v) => (
+                        RPM_INT32_TYPE,
+                        4,
+                        v.len(),
+                        v.iter().flat_map(|x| x.to_be_bytes()).collect(),
+                    ),
+                    Value::Str(s) => (RPM_STRING_TYPE, 1, 1, cstr(s)),
+                    Value::StrArray(v) => (
+                        RPM_STRING_ARRAY_TYPE,
+                        1,
+                        v.len(),
+                        v.iter().flat_map(|s| cstr(s)).collect(),
+                    ),
+                    Value::I18n(s) => (RPM_I18NSTRING_TYPE, 1, 1, cstr(s)),
+                    Value::Bin(b) => (RPM_BIN_TYPE, 1, b.len(), b.clone()),
+                };
+                while !store.len().is_multiple_of(align) {
+                    store.push(0);
+                }
+                index.extend_from_slice(&tag.to_be_bytes());
+                index.extend_from_slice(&kind.to_be_bytes());
+                index.extend_from_slice(&(store.len() as u32).to_be_bytes());
+                index.extend_from_slice(&(count as u32).to_be_bytes());
+                store.extend_from_slice(&bytes);
+            }
+
+            let mut out = HEADER_MAGIC.to_vec();
+            out.extend_from_slice(&[0; 4]);
+            out.extend_from_slice(&(self.tags.len() as u32).to_be_bytes());
+            out.extend_from_slice(&(store.len() as u32).to_be_bytes());
+            out.extend_from_slice(&index);
+            out.extend_from_slice(&store);
+            out
+        }
+    }
+
+    fn cstr(s: &str) -> Vec<u8> {
+        let mut v = s.as_bytes().to_vec();
+        v.push(0);
+        v
+    }
+
+    /// Builds a complete RPM file: lead, signature header, header, payload.
+    pub(crate) struct RpmBuilder {
+        pub(crate) package_type: u16,
+        /// Whether `build` records header and payload digests the test didn't set itself.
+        pub(crate) digests: bool,
+        pub(crate) signature: HeaderBuilder,
+        pub(crate) header: HeaderBuilder,
+        pub(crate) payload: Vec<u8>,
+    }
+
+    impl RpmBuilder {
+        /// A binary package with the given NEVRA and a few descriptive tags.
+        pub(crate) fn new(name: &str, version: &str, release: &str, arch: &str) -> Self {
+            let header = HeaderBuilder::new()
+                .string(RPMTAG_NAME, name)
+                .string(RPMTAG_VERSION, version)
+                .string(RPMTAG_RELEASE, release)
+                .tag(RPMTAG_SUMMARY, Value::I18n(format!("The {name} package")))
+                .tag(
+                    RPMTAG_DESCRIPTION,
+                    Value::I18n(format!("{name} is a test package.")),
+                )
+                .string(RPMTAG_LICENSE, "MIT")
+                .string(RPMTAG_URL, "https://example.com")
+                .string(RPMTAG_PACKAGER, "Artifact Keeper <team@example.com>")
+                .string(RPMTAG_ARCH, arch)
+                .string(RPMTAG_PAYLOADFORMAT, "cpio")
+                .string(RPMTAG_PAYLOADCOMPRESSOR, "gzip");
+            RpmBuilder {
+                package_type: 0,
+                digests: true,
+                signature: HeaderBuilder::new(),
+                header,
+                payload: [&[0x1f, 0x8b, 0x08, 0x00][..], b"payload"].concat(),
+            }
+        }
+
+        /// Add a compressed file list: `(path, mode, flags)` per file.
+        pub(crate) fn files(self, files: &[(&str, u16, u32)]) -> Self {
+            let mut dirnames: Vec<String> = Vec::new();
+            let mut basenames = Vec::new();
+            let mut dirindexes = Vec::new();
+            for (path, _, _) in files {
+                let (dir, base) = path.rsplit_once('/').unwrap();
+                let dir = format!("{dir}/");
+                let idx = match dirnames.iter().position(|d| *d == dir) {
+                    Some(i) => i,
+                    None => {
+                        dirnames.push(dir);
+                        dirnames.len() - 1
+                    }
+                };
+                basenames.push(base.to_string());
+                dirindexes.push(idx as u32);
+            }
+            self.header_tag(
+                RPMTAG_FILEMODES,
+                Value::Int16(files.iter().map(|f| f.1).collect()),
+            )
+            .header_tag(
+                RPMTAG_FILEFLAGS,
+                Value::Int32(files.iter().map(|f| f.2).collect()),
+            )
+            .header_tag(RPMTAG_DIRINDEXES, Value::Int32(dirindexes))
+            .header_tag(RPMTAG_BASENAMES, Value::StrArray(basenames))
+            .header_tag(RPMTAG_DIRNAMES, Value::StrArray(dirnames))
+        }
+
+        /// Add a dependency family: `(name, flags, version)` per entry.
+        pub(crate) fn dependencies(self, tags: [u32; 3], deps: &[(&str, u32, &str)]) -> Self {
+            let [name_tag, flags_tag, version_tag] = tags;
+            self.header_tag(
+                name_tag,
+                Value::StrArray(deps.iter().map(|d| d.0.to_string()).collect()),
+            )
+            .header_tag(flags_tag, Value::Int32(deps.iter().map(|d| d.1).collect()))
+            .header_tag(
+                version_tag,
+                Value::StrArray(deps.iter().map(|d| d.2.to_string()).collect()),
+            )
+        }
+
+        pub(crate) fn header_tag(mut self, tag: u32, value: Value) -> Self {
+            self.header = self.header.tag(tag, value);
+            self
+        }
+
+        /// Encode the package. The signature header gets a SIZE tag matching the header
+        /// and payload, and (with `digests`) matching digests, unless the test set them itself.
+        pub(crate) fn build(&self) -> Vec<u8> {
+            let mut header = self.header.clone();
+            if self.digests && !header.has(RPMTAG_PAYLOADDIGEST) {
+                let digest = hex_digest(PGPHASHALGO_SHA256, &self.payload).unwrap();
+                header = header
+                    .tag(RPMTAG_PAYLOADDIGEST, Value::StrArray(vec![digest]))
+                    .tag(
+                        RPMTAG_PAYLOADDIGESTALGO,
+                        Value::Int32(vec![PGPHASHALGO_SHA256]),
+                    );
+            }
+            let header = header.build();
+
+            let mut signature = self.signature.clone();
+            if self.digests && !signature.has(RPMSIGTAG_SHA256) {
+                let digest = hex_digest(PGPHASHALGO_SHA256, &header).unwrap();
+                signature = signature.string(RPMSIGTAG_SHA256, &digest);
+            }
+            if self.digests && !signature.has(RPMSIGTAG_SHA1) {
+                let digest = hex_digest(PGPHASHALGO_SHA1, &header).unwrap();
+                signature = signature.string(RPMSIGTAG_SHA1, &digest);
+            }
+            if !signature.has(RPMSIGTAG_SIZE) {
+                let size = (header.len() + self.payload.len()) as u32;
+                signature = signature.tag(RPMSIGTAG_SIZE, Value::Int32(vec![size]));
+            }
+
+            let mut out = vec![0u8; RPM_LEAD_SIZE];
+            out[..4].copy_from_slice(&RPM_MAGIC);
+            out[4] = 3; // major
+            out[5] = 0; // minor
+            out[6..8].copy_from_slice(&self.package_type.to_be_bytes());
+            out[78..80].copy_from_slice(&5u16.to_be_bytes()); // signature type
+            out.extend_from_slice(&signature.build());
+            while !out.len().is_multiple_of(8) {
+                out.push(0);
+            }
+            out.extend_from_slice(&header);
+            out.extend_from_slice(&self.payload);
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn parse_header_fields() {
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .header_tag(RPMTAG_EPOCH, Value::Int32(vec![2]))
+            .build();
+        let rpm = parse_rpm(&data).unwrap();
+        let info = RpmHeaderInfo::from_rpm(&rpm).unwrap();
+        assert_eq!(info.name, "nginx");
+        assert_eq!(info.epoch, Some(2));
+        assert_eq!(info.version, "1.24.0");
+        assert_eq!(info.release, "1.el9");
+        assert_eq!(info.arch, "x86_64");
+        assert_eq!(info.summary.as_deref(), Some("The nginx package"));
+        assert_eq!(info.license.as_deref(), Some("MIT"));
+        assert_eq!(info.url.as_deref(), Some("https://example.com"));
+        assert!(info.packager.unwrap().contains("Artifact Keeper"));
+        assert!(!info.is_source);
+    }
+
+    #[test]
+    fn array_and_string_tags() {
+        let data = HeaderBuilder::new()
+            .tag(1, Value::StrArray(vec!["a".into(), "bc".into()]))
+            .tag(2, Value::Int16(vec![7, 8]))
+            .tag(3, Value::Int32(vec![1, 2, 3]))
+            .string(5, "abc")
+            .build();
+        let (header, len) = parse_header(&data, 0, "header").unwrap();
+        assert_eq!(len, data.len());
+        assert_eq!(header.string_array(1), vec!["a", "bc"]);
+        assert_eq!(header.u16_array(2), vec![7, 8]);
+        assert_eq!(header.u32_array(3), vec![1, 2, 3]);
+        assert_eq!(header.string(5).as_deref(), Some("abc"));
+        assert!(header.string(4).is_none());
+    }
+
+    #[test]
+    fn file_list_from_compressed_names() {
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .files(&[
+                ("/etc/nginx", 0o040755, 0),
+                ("/etc/nginx/nginx.conf", 0o100644, 1),
+                ("/usr/sbin/nginx", 0o100755, 0),
+                ("/var/log/nginx/access.log", 0o100644, RPMFILE_GHOST),
+            ])
+            .build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        let files: Vec<(&str, FileType)> = info
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.file_type))
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                ("/etc/nginx", FileType::Dir),
+                ("/etc/nginx/nginx.conf", FileType::File),
+                ("/usr/sbin/nginx", FileType::File),
+                ("/var/log/nginx/access.log", FileType::Ghost),
+            ]
+        );
+    }
+
+    #[test]
+    fn changelog_entries() {
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .header_tag(
+                RPMTAG_CHANGELOGTIME,
+                Value::Int32(vec![1700000000, 1600000000]),
+            )
+            .header_tag(
+                RPMTAG_CHANGELOGNAME,
+                Value::StrArray(vec![
+                    "Jane <jane@example.com> - 1.24.0-1".into(),
+                    "John <john@example.com> - 1.22.0-1".into(),
+                ]),
+            )
+            .header_tag(
+                RPMTAG_CHANGELOGTEXT,
+                Value::StrArray(vec!["- Update to 1.24.0".into(), "- Initial".into()]),
+            )
+            .build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        assert_eq!(info.changelogs.len(), 2);
+        assert_eq!(info.changelogs[0].date, 1700000000);
+        assert!(info.changelogs[0].author.starts_with("Jane"));
+        assert_eq!(info.changelogs[1].text, "- Initial");
+    }
+
+    #[test]
+    fn dependency_families() {
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .dependencies(
+                [
+                    RPMTAG_REQUIRENAME,
+                    RPMTAG_REQUIREFLAGS,
+                    RPMTAG_REQUIREVERSION,
+                ],
+                &[("openssl-libs", 12, "1:3.0.7"), ("/bin/sh", 0, "")],
+            )
+            .dependencies(
+                [
+                    RPMTAG_ENHANCENAME,
+                    RPMTAG_ENHANCEFLAGS,
+                    RPMTAG_ENHANCEVERSION,
+                ],
+                &[("nginx-mod", 0, "")],
+            )
+            .build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        assert_eq!(info.requires.len(), 2);
+        assert_eq!(info.requires[0].name, "openssl-libs");
+        assert_eq!(info.requires[0].flags, 12);
+        assert_eq!(info.requires[0].version, "1:3.0.7");
+        assert_eq!(info.requires[1].version, "");
+        assert_eq!(info.enhances[0].name, "nginx-mod");
+        assert!(info.provides.is_empty());
+    }
+
+    #[test]
+    fn file_list_from_old_filenames() {
+        let data = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64")
+            .header_tag(
+                RPMTAG_OLDFILENAMES,
+                Value::StrArray(vec!["/bin/bash".into()]),
+            )
+            .build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        assert_eq!(info.files.len(), 1);
+        assert_eq!(info.files[0].path, "/bin/bash");
+        assert_eq!(info.files[0].file_type, FileType::File);
+    }
+
+    #[test]
+    fn signature_header_is_padded() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        // A 3-byte store leaves the signature header unaligned.
+        builder.signature = HeaderBuilder::new().string(1000, "ab");
+        let data = builder.build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        assert_eq!(info.name, "bash");
+    }
+
+    #[test]
+    fn missing_required_tag() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.header = HeaderBuilder::new().string(RPMTAG_NAME, "bash");
+        let data = builder.build();
+        let err = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap())
+            .err()
+            .unwrap();
+        assert!(err.contains("VERSION"));
+    }
+
+    #[test]
+    fn truncated_header_rejected() {
+        let data = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64").build();
+        let err = parse_rpm(&data[..data.len() - 20]).err().unwrap();
+        assert!(err.contains("Truncated header"));
+    }
+
+    #[test]
+    fn lead_only_rejected() {
+        let mut data = vec![0; RPM_LEAD_SIZE];
+        data[..4].copy_from_slice(&RPM_MAGIC);
+        let err = parse_rpm(&data).err().unwrap();
+        assert!(err.contains("signature header"));
+    }
+
+    #[test]
+    fn canonical_filename_for_source_package() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.package_type = 1;
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&builder.build()).unwrap()).unwrap();
+        assert_eq!(info.canonical_filename(), "bash-5.2.26-1.el9.src.rpm");
+    }
+
+    #[test]
+    fn canonical_filename_for_nosrc_package() {
+        let mut builder = RpmBuilder::new("java", "1.8", "1", "x86_64")
+            .header_tag(RPMTAG_NOSOURCE, Value::Int32(vec![0]));
+        builder.package_type = 1;
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&builder.build()).unwrap()).unwrap();
+        assert!(info.is_source);
+        assert_eq!(info.canonical_filename(), "java-1.8-1.nosrc.rpm");
+    }
+
+    // -- structural validation --
+
+    /// Offset of the main header within an encoded RPM.
+    fn header_offset(data: &[u8]) -> usize {
+        let rpm = parse_rpm(data).unwrap();
+        data.len() - rpm.payload.len() - rpm.header_bytes.len()
+    }
+
+    fn bash_rpm() -> Vec<u8> {
+        RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64").build()
+    }
+
+    #[test]
+    fn valid_rpm_passes_validation() {
+        assert!(validate_rpm(&bash_rpm()).is_ok());
+
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.header = HeaderBuilder::new()
+            .string(RPMTAG_PAYLOADFORMAT, "cpio")
+            .string(RPMTAG_PAYLOADCOMPRESSOR, "zstd");
+        builder.payload = vec![0x28, 0xb5, 0x2f, 0xfd, 0x00];
+        assert!(validate_rpm(&builder.build()).is_ok());
+    }
+
+    #[test]
+    fn validation_rejects_bad_lead() {
+        let mut data = bash_rpm();
+        data[4] = 2;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("lead version 2.0"), "{err}");
+
+        let mut data = bash_rpm();
+        data[5] = 1;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("expected minor version 0"), "{err}");
+
+        let mut data = bash_rpm();
+        data[7] = 2;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("Invalid RPM lead type 2"), "{err}");
+
+        let mut data = bash_rpm();
+        data[79] = 0;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("Unsupported RPM signature type 0"), "{err}");
+    }
+
+    #[test]
+    fn validation_rejects_bad_header_magic() {
+        let mut data = bash_rpm();
+        let offset = header_offset(&data);
+        data[offset + 3] = 0x02;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("Invalid header magic"), "{err}");
+    }
+
+    #[test]
+    fn validation_rejects_entry_outside_store() {
+        let mut data = bash_rpm();
+        let entry = header_offset(&data) + HEADER_INTRO_SIZE;
+        data[entry + 8..entry + 12].copy_from_slice(&0x00ff_ffffu32.to_be_bytes());
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(
+            err.contains("Invalid header: index entry 0 (tag 1000)"),
+            "{err}"
+        );
+        assert!(err.contains("extends past"), "{err}");
+    }
+
+    #[test]
+    fn validation_rejects_unknown_type_and_misalignment() {
+        let mut data = bash_rpm();
+        let entry = header_offset(&data) + HEADER_INTRO_SIZE;
+        data[entry + 4..entry + 8].copy_from_slice(&42u32.to_be_bytes());
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("unknown type 42"), "{err}");
+
+        let mut data = bash_rpm();
+        let entry = header_offset(&data) + HEADER_INTRO_SIZE;
+        data[entry + 4..entry + 8].copy_from_slice(&RPM_INT32_TYPE.to_be_bytes());
+        data[entry + 8..entry + 12].copy_from_slice(&1u32.to_be_bytes());
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("not aligned to 4 bytes"), "{err}");
+    }
+
+    #[test]
+    fn validation_rejects_empty_signature_header() {
+        let mut data = bash_rpm();
+        // Claim zero index entries and an empty store; the main header is then misplaced,
+        // so rewrite it right after the (aligned) empty signature header.
+        let rpm = parse_rpm(&data).unwrap();
+        let rest = [rpm.header_bytes, rpm.payload].concat();
+        data.truncate(RPM_LEAD_SIZE + HEADER_INTRO_SIZE);
+        data[RPM_LEAD_SIZE + 8..RPM_LEAD_SIZE + 16].copy_from_slice(&[0; 8]);
+        data.extend_from_slice(&rest);
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(
+            err.contains("Invalid signature header: no index entries"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validation_rejects_nonzero_signature_padding() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.signature = HeaderBuilder::new().string(1004, "abcd");
+        let mut data = builder.build();
+        let offset = header_offset(&data);
+        assert!(!parse_rpm(&data).unwrap().signature_padding.is_empty());
+        data[offset - 1] = 0xff;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("padding before the main header"), "{err}");
+    }
+
+    #[test]
+    fn validation_rejects_truncated_payload() {
+        let mut data = bash_rpm();
+        data.truncate(data.len() - 3);
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("Truncated or corrupted RPM"), "{err}");
+    }
+
+    #[test]
+    fn validation_checks_payload_tags() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.header = HeaderBuilder::new().string(RPMTAG_NAME, "bash");
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("missing the PAYLOADFORMAT tag"), "{err}");
+
+        builder.header = HeaderBuilder::new().string(RPMTAG_PAYLOADFORMAT, "drpm");
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("payload format 'drpm'"), "{err}");
+
+        builder.header = HeaderBuilder::new()
+            .string(RPMTAG_PAYLOADFORMAT, "cpio")
+            .string(RPMTAG_PAYLOADCOMPRESSOR, "lz4");
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("payload compressor 'lz4'"), "{err}");
+
+        builder.header = HeaderBuilder::new()
+            .string(RPMTAG_PAYLOADFORMAT, "cpio")
+            .string(RPMTAG_PAYLOADCOMPRESSOR, "xz");
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("xz magic bytes"), "{err}");
+
+        builder.payload.clear();
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("payload is missing"), "{err}");
+    }
+
+    #[test]
+    fn validation_verifies_header_digests() {
+        let mut data = bash_rpm();
+        let rpm = parse_rpm(&data).unwrap();
+        // Flip a byte near the end of the header store, leaving the structure intact
+        let store_end = data.len() - rpm.payload.len();
+        data[store_end - 2] ^= 0x01;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("SHA256HEADER mismatch"), "{err}");
+
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.signature = HeaderBuilder::new().string(RPMSIGTAG_SHA1, &"0".repeat(40));
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("SHA1HEADER mismatch"), "{err}");
+    }
+
+    #[test]
+    fn validation_verifies_payload_digest() {
+        let mut data = bash_rpm();
+        let last = data.len() - 1;
+        data[last] ^= 0x01;
+        let err = validate_rpm(&data).unwrap_err();
+        assert!(err.contains("PAYLOADDIGEST mismatch"), "{err}");
+    }
+
+    #[test]
+    fn payload_digest_uses_payload_digest_algo() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        let sha512 = hex_digest(PGPHASHALGO_SHA512, &builder.payload).unwrap();
+        builder = builder
+            .header_tag(RPMTAG_PAYLOADDIGEST, Value::StrArray(vec![sha512]))
+            .header_tag(
+                RPMTAG_PAYLOADDIGESTALGO,
+                Value::Int32(vec![PGPHASHALGO_SHA512]),
+            );
+        assert!(validate_rpm(&builder.build()).is_ok());
+
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64")
+            .header_tag(RPMTAG_PAYLOADDIGEST, Value::StrArray(vec!["00".into()]))
+            .header_tag(RPMTAG_PAYLOADDIGESTALGO, Value::Int32(vec![1]));
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(
+            err.contains("Unsupported PAYLOADDIGEST digest algorithm 1"),
+            "{err}"
+        );
+
+        builder.digests = false;
+        builder.header = HeaderBuilder::new()
+            .string(RPMTAG_PAYLOADFORMAT, "cpio")
+            .tag(RPMTAG_PAYLOADDIGEST, Value::StrArray(vec!["00".into()]));
+        let err = validate_rpm(&builder.build()).unwrap_err();
+        assert!(err.contains("no PAYLOADDIGESTALGO"), "{err}");
+    }
+
+    #[test]
+    fn packages_without_digests_pass_validation() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.digests = false;
+        assert!(validate_rpm(&builder.build()).is_ok());
+    }
+
+    #[test]
+    fn parse_signature_from_signature_header() {
+        let key_id = [0x19, 0x9e, 0x27, 0x40, 0x05, 0x00, 0x2e, 0x8f];
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.signature = HeaderBuilder::new()
+            .tag(
+                RPMSIGTAG_PGP,
+                Value::Bin(openpgp::testing::v4_signature([0xaa; 8], 17, 2)),
+            )
+            .tag(
+                RPMSIGTAG_RSA,
+                Value::Bin(openpgp::testing::v4_signature(key_id, 1, 8)),
+            );
+        let data = builder.build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        let signature = info.signature.unwrap();
+        // RSAHEADER wins over the legacy header+payload signature
+        assert_eq!(signature.key_id, "199e274005002e8f");
+        assert_eq!(signature.algorithm, "RSA/SHA256");
+        assert!(validate_rpm(&data).is_ok());
+    }
+
+    #[test]
+    fn unsigned_package_has_no_signature() {
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&bash_rpm()).unwrap()).unwrap();
+        assert!(info.signature.is_none());
+    }
+
+    #[test]
+    fn malformed_signature_is_rejected() {
+        let mut builder = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        builder.signature = HeaderBuilder::new().tag(RPMSIGTAG_RSA, Value::Bin(vec![0xc2, 0x40]));
+        let err = RpmHeaderInfo::from_rpm(&parse_rpm(&builder.build()).unwrap())
+            .err()
+            .unwrap();
+        assert!(err.contains("signature header tag 268"), "{err}");
+    }
+
+    #[test]
+    fn parse_source_rpm_tag() {
+        let data = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64")
+            .header_tag(
+                RPMTAG_SOURCERPM,
+                Value::Str("bash-5.2.26-1.el9.src.rpm".into()),
+            )
+            .build();
+        let info = RpmHeaderInfo::from_rpm(&parse_rpm(&data).unwrap()).unwrap();
+        assert!(!info.is_source);
+        assert_eq!(
+            info.source_rpm.as_deref(),
+            Some("bash-5.2.26-1.el9.src.rpm")
+        );
+    }
+}
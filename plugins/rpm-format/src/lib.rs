@@ -1,8 +1,39 @@
 //! RPM Package Format Plugin for Artifact Keeper
 //!
 //! Handles `.rpm` files used by Red Hat, Fedora, SUSE, and other RPM-based Linux distributions.
-//! This plugin demonstrates binary format validation (RPM lead magic bytes) and right-to-left
-//! filename parsing to extract structured metadata from RPM naming conventions.
+//! This plugin demonstrates binary format validation (RPM lead magic bytes), RPM header
+//! parsing, and right-to-left filename parsing to extract structured metadata from RPM
+//! naming conventions.
+//!
+//! Package identity (name, version, release, arch) is read from the RPM header. Uploads
+//! whose filename disagrees with the header are rejected, so a renamed file can't publish
+//! the wrong metadata. Filename parsing is only used as a fallback for artifacts whose
+//! content isn't available.
+//!
+//! Source packages (`.src.rpm`, identified by the lead's package type) are kept out of the
+//! binary repository metadata and served as a separate repository under `/SRPMS/`, which
+//! is where `dnf download --source` looks for them.
+//!
+//! Builds for several distributions can share one repository. Each is also served as a
+//! `/{releasever}/{basearch}/` tree (for example `/el9/x86_64/` or `/39/aarch64/`)
+//! holding only the packages whose dist tag and arch match, plus `noarch` packages, so
+//! a single `baseurl` ending in `$releasever/$basearch/` works on every host.
+//!
+//! Alongside `rpm-index.json`, the generated index includes `repoclosure.json`, which lists
+//! every package requirement that nothing in the repository provides.
+//!
+//! Every tree also serves `metalink` and `mirrorlist` documents listing the mirrors
+//! configured through `RPM_MIRRORS` ahead of the repository itself, for `metalink=`
+//! and `mirrorlist=` in a `.repo` file.
+//!
+//! Update advisories are uploaded as `*.advisory.json` documents alongside the packages
+//! and published as `repodata/updateinfo.xml`, for `dnf updateinfo` and
+//! `dnf upgrade --security`.
+//! Package groups are uploaded as `comps.xml` (or `*-comps.xml`) documents and published
+//! as `repodata/comps.xml`, for `dnf group`.
+//! Module streams are uploaded as modulemd YAML (`modules.yaml`, `*-modules.yaml`, or
+//! `*.modulemd.yaml`) and published as `repodata/modules.yaml`, so dnf can tell which
+//! packages belong to which stream.
 //!
 //! ## RPM filename convention
 //!
@@ -16,21 +47,74 @@
 //! - `bash-completion-2.11-5.el9.noarch.rpm`
 
 wit_bindgen::generate!({
-    world: "format-plugin-v2",
+    world: "format-plugin-v3",
     path: "../../wit/format-plugin.wit",
 });
 
+use exports::artifact_keeper::format::artifact_properties::{
+    Artifact, Guest as ArtifactPropertiesGuest,
+};
 use exports::artifact_keeper::format::handler::{Guest as HandlerGuest, Metadata};
 use exports::artifact_keeper::format::request_handler::{
     Guest as RequestHandlerGuest, HttpRequest, HttpResponse, RepoContext,
 };
 
+use sha2::{Digest, Sha256};
+
+mod closure;
+mod compression;
+mod comps;
+mod config;
+mod distro;
+mod header;
+mod metalink;
+mod modules;
+mod openpgp;
+mod updateinfo;
+mod vercmp;
+
+use std::collections::HashMap;
+
+use closure::unresolved_requires;
+use compression::Compression;
+use comps::{is_comps_path, merge_comps, parse_comps};
+use config::RpmConfig;
+use distro::Tree;
+use header::{
+    Dependency, FileType, PackageFile, RpmHeaderInfo, RPMSENSE_EQUAL, RPMSENSE_GREATER,
+    RPMSENSE_LESS, RPMSENSE_PREREQ, RPMSENSE_SCRIPT_POST, RPMSENSE_SCRIPT_PRE,
+};
+use metalink::{metalink_xml, mirrorlist};
+use modules::{is_modulemd_path, merge_modulemd, module_streams, parse_modulemd};
+use openpgp::SigningKey;
+use updateinfo::{is_advisory_path, parse_advisory, parse_nevra, Advisory, Nevra};
+use vercmp::{compare_evr, Evr};
+
 /// RPM lead magic bytes: 0xed 0xab 0xee 0xdb
 const RPM_MAGIC: [u8; 4] = [0xed, 0xab, 0xee, 0xdb];
 
 /// RPM lead is exactly 96 bytes.
 const RPM_LEAD_SIZE: usize = 96;
 
+/// Index files as (path, content) pairs, as returned by `generate-index`.
+type IndexFiles = Vec<(String, Vec<u8>)>;
+
+/// Artifact property holding the JSON-encoded RPM header fields.
+const HEADER_PROPERTY: &str = "rpm.header";
+
+/// Artifact properties naming the key and algorithm a package was signed with.
+const SIGNATURE_KEY_ID_PROPERTY: &str = "rpm.signature.key_id";
+const SIGNATURE_ALGORITHM_PROPERTY: &str = "rpm.signature.algorithm";
+
+/// Artifact property holding a validated, JSON-encoded update advisory.
+const ADVISORY_PROPERTY: &str = "rpm.advisory";
+
+/// Artifact property holding a validated comps XML document.
+const COMPS_PROPERTY: &str = "rpm.comps";
+
+/// Artifact property holding a validated modulemd YAML file.
+const MODULEMD_PROPERTY: &str = "rpm.modulemd";
+
 struct RpmFormatHandler;
 
 impl HandlerGuest for RpmFormatHandler {
@@ -43,6 +127,23 @@ impl HandlerGuest for RpmFormatHandler {
             return Err("Empty file".to_string());
         }
 
+        if document_property(&path, &data)?.is_some() {
+            let content_type = if is_comps_path(&path) {
+                "application/xml"
+            } else if is_modulemd_path(&path) {
+                "application/yaml"
+            } else {
+                "application/json"
+            };
+            return Ok(Metadata {
+                path,
+                version: None,
+                content_type: content_type.to_string(),
+                size_bytes: data.len() as u64,
+                checksum_sha256: None,
+            });
+        }
+
         let has_rpm_magic = data.len() >= 4 && data[..4] == RPM_MAGIC;
 
         let content_type = if has_rpm_magic {
@@ -51,7 +152,12 @@ impl HandlerGuest for RpmFormatHandler {
             "application/octet-stream"
         };
 
-        let version = extract_version_from_rpm_filename(&path);
+        let version = if has_rpm_magic {
+            let info = read_header_info(&path, &data)?;
+            Some(format_evr(info.epoch, &info.version, &info.release))
+        } else {
+            extract_version_from_rpm_filename(&path)
+        };
 
         Ok(Metadata {
             path,
@@ -63,374 +169,1327 @@ impl HandlerGuest for RpmFormatHandler {
     }
 
     fn validate(path: String, data: Vec<u8>) -> Result<(), String> {
-        if data.is_empty() {
-            return Err("RPM package cannot be empty".to_string());
+        if document_property(&path, &data)?.is_some() {
+            return Ok(());
         }
+        validate_package(&path, &data, &RpmConfig::from_env())
+    }
 
-        if path.is_empty() {
-            return Err("Artifact path cannot be empty".to_string());
-        }
+    fn generate_index(artifacts: Vec<Metadata>) -> Result<Option<Vec<(String, Vec<u8>)>>, String> {
+        let mut artifacts: Vec<RpmArtifact> = artifacts
+            .into_iter()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+        sort_newest_first(&mut artifacts);
+        generate_rpm_index(&artifacts)
+    }
+}
 
-        // Verify .rpm extension
-        if !path.to_lowercase().ends_with(".rpm") {
-            return Err(format!(
-                "Expected .rpm extension, got: {}",
-                path.rsplit('/').next().unwrap_or(&path)
-            ));
-        }
+impl RequestHandlerGuest for RpmFormatHandler {
+    fn handle_request(
+        request: HttpRequest,
+        context: RepoContext,
+        artifacts: Vec<Metadata>,
+    ) -> Result<HttpResponse, String> {
+        let mut artifacts: Vec<RpmArtifact> = artifacts
+            .into_iter()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+        sort_newest_first(&mut artifacts);
+        route_request(&request, &context, artifacts, &RpmConfig::from_env())
+    }
+}
 
-        // RPM lead is 96 bytes minimum
-        if data.len() < RPM_LEAD_SIZE {
-            return Err(format!(
-                "File too small for RPM lead: {} bytes (minimum {})",
-                data.len(),
-                RPM_LEAD_SIZE
-            ));
+impl ArtifactPropertiesGuest for RpmFormatHandler {
+    fn extract_properties(path: String, data: Vec<u8>) -> Result<Vec<(String, String)>, String> {
+        if let Some(property) = document_property(&path, &data)? {
+            return Ok(vec![property]);
         }
 
-        // Verify RPM magic bytes
-        if data[..4] != RPM_MAGIC {
-            return Err(format!(
-                "Invalid RPM magic: expected [ed, ab, ee, db], got [{:02x}, {:02x}, {:02x}, {:02x}]",
-                data[0], data[1], data[2], data[3]
-            ));
+        // Non-RPM content has no header to store; filename parsing covers it.
+        if data.len() < 4 || data[..4] != RPM_MAGIC {
+            return Ok(Vec::new());
         }
 
-        Ok(())
-    }
+        let info = read_header_info(&path, &data)?;
+        let json = serde_json::to_string(&info)
+            .map_err(|e| format!("Failed to serialize RPM header: {e}"))?;
 
-    fn generate_index(artifacts: Vec<Metadata>) -> Result<Option<Vec<(String, Vec<u8>)>>, String> {
-        if artifacts.is_empty() {
-            return Ok(None);
+        let mut properties = vec![(HEADER_PROPERTY.to_string(), json)];
+        if let Some(sig) = &info.signature {
+            properties.push((SIGNATURE_KEY_ID_PROPERTY.to_string(), sig.key_id.clone()));
+            properties.push((
+                SIGNATURE_ALGORITHM_PROPERTY.to_string(),
+                sig.algorithm.clone(),
+            ));
         }
+        Ok(properties)
+    }
 
-        let entries: Vec<serde_json::Value> = artifacts
-            .iter()
-            .map(|a| {
-                let filename = a.path.rsplit('/').next().unwrap_or(&a.path);
-                let info = parse_rpm_filename(filename);
-
-                let mut entry = serde_json::Map::new();
-                entry.insert("path".into(), serde_json::Value::String(a.path.clone()));
-                if let Some(ref v) = a.version {
-                    entry.insert("version".into(), serde_json::Value::String(v.clone()));
-                }
-                if let Some(name) = info.name {
-                    entry.insert("name".into(), serde_json::Value::String(name));
-                }
-                if let Some(arch) = info.arch {
-                    entry.insert("arch".into(), serde_json::Value::String(arch));
-                }
-                if let Some(release) = info.release {
-                    entry.insert("release".into(), serde_json::Value::String(release));
-                }
-                entry.insert(
-                    "size_bytes".into(),
-                    serde_json::Value::Number(a.size_bytes.into()),
-                );
-                serde_json::Value::Object(entry)
-            })
-            .collect();
-
-        let index = serde_json::json!({
-            "format": "rpm-custom",
-            "total_count": artifacts.len(),
-            "total_size_bytes": artifacts.iter().map(|a| a.size_bytes).sum::<u64>(),
-            "packages": entries,
-        });
-
-        let json_bytes = serde_json::to_vec_pretty(&index)
-            .map_err(|e| format!("Failed to serialize index: {e}"))?;
-
-        Ok(Some(vec![("rpm-index.json".to_string(), json_bytes)]))
+    fn generate_index_with_properties(
+        artifacts: Vec<Artifact>,
+    ) -> Result<Option<Vec<(String, Vec<u8>)>>, String> {
+        let mut artifacts: Vec<RpmArtifact> = artifacts.into_iter().map(RpmArtifact::new).collect();
+        sort_newest_first(&mut artifacts);
+        generate_rpm_index(&artifacts)
     }
-}
 
-impl RequestHandlerGuest for RpmFormatHandler {
-    fn handle_request(
+    fn handle_request_with_properties(
         request: HttpRequest,
         context: RepoContext,
-        artifacts: Vec<Metadata>,
+        artifacts: Vec<Artifact>,
     ) -> Result<HttpResponse, String> {
-        let path = request.path.as_str();
+        let mut artifacts: Vec<RpmArtifact> = artifacts.into_iter().map(RpmArtifact::new).collect();
+        sort_newest_first(&mut artifacts);
+        route_request(&request, &context, artifacts, &RpmConfig::from_env())
+    }
+}
 
-        // Only handle GET and HEAD
-        if request.method != "GET" && request.method != "HEAD" {
-            return Ok(HttpResponse {
-                status: 405,
-                headers: vec![("allow".to_string(), "GET, HEAD".to_string())],
-                body: b"Method Not Allowed".to_vec(),
-            });
-        }
+export!(RpmFormatHandler);
 
-        let trimmed = path.trim_end_matches('/');
+/// Validate a metadata document uploaded alongside the packages, returning the property
+/// that stores it, or `None` if `path` doesn't name an advisory, comps, or modulemd document.
+fn document_property(path: &str, data: &[u8]) -> Result<Option<(String, String)>, String> {
+    if is_advisory_path(path) {
+        let advisory = parse_advisory(data)?;
+        let json = serde_json::to_string(&advisory)
+            .map_err(|e| format!("Failed to serialize advisory: {e}"))?;
+        return Ok(Some((ADVISORY_PROPERTY.to_string(), json)));
+    }
+    if is_comps_path(path) {
+        return Ok(Some((COMPS_PROPERTY.to_string(), parse_comps(data)?)));
+    }
+    if is_modulemd_path(path) {
+        return Ok(Some((MODULEMD_PROPERTY.to_string(), parse_modulemd(data)?)));
+    }
+    Ok(None)
+}
 
-        // Route: /repodata/repomd.xml
-        if trimmed == "/repodata/repomd.xml" {
-            return handle_repomd_xml(&context, &artifacts);
+/// A repository artifact together with the RPM header fields stored for it on upload.
+///
+/// Artifacts uploaded before properties were available (or served by a host that
+/// doesn't support them) have no header, and fall back to filename parsing. Advisory,
+/// comps, and modulemd documents are artifacts too, carrying their content instead of a
+/// header.
+struct RpmArtifact {
+    metadata: Metadata,
+    header: Option<RpmHeaderInfo>,
+    advisory: Option<Advisory>,
+    comps: Option<String>,
+    modulemd: Option<String>,
+}
+
+impl RpmArtifact {
+    fn new(artifact: Artifact) -> Self {
+        let property = |key: &str| {
+            artifact
+                .properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str())
+        };
+        let header = property(HEADER_PROPERTY).and_then(|v| serde_json::from_str(v).ok());
+        let advisory = property(ADVISORY_PROPERTY).and_then(|v| serde_json::from_str(v).ok());
+        let comps = property(COMPS_PROPERTY).map(str::to_string);
+        let modulemd = property(MODULEMD_PROPERTY).map(str::to_string);
+        RpmArtifact {
+            metadata: artifact.metadata,
+            header,
+            advisory,
+            comps,
+            modulemd,
         }
+    }
 
-        // Route: /repodata/primary.xml.gz
-        if trimmed == "/repodata/primary.xml.gz" {
-            return handle_primary_xml_gz(&context, &artifacts);
+    fn from_metadata(metadata: Metadata) -> Self {
+        RpmArtifact {
+            metadata,
+            header: None,
+            advisory: None,
+            comps: None,
+            modulemd: None,
         }
+    }
 
-        // Route: /repodata/filelists.xml.gz
-        if trimmed == "/repodata/filelists.xml.gz" {
-            return handle_filelists_xml_gz();
+    /// `name-epoch:version-release.arch`, with the same fallbacks as the repodata.
+    fn nevra(&self) -> String {
+        let info = self.file_info();
+        Nevra {
+            name: info.name.as_deref().unwrap_or("unknown"),
+            epoch: info.epoch.unwrap_or(0),
+            version: info.version.as_deref().unwrap_or("0"),
+            release: info.release.as_deref().unwrap_or("0"),
+            arch: info.arch.as_deref().unwrap_or("x86_64"),
         }
+        .to_string()
+    }
+
+    /// Whether this is an advisory, comps, or modulemd document rather than a package.
+    fn is_document(&self) -> bool {
+        let path = &self.metadata.path;
+        is_advisory_path(path) || is_comps_path(path) || is_modulemd_path(path)
+    }
+
+    fn filename(&self) -> &str {
+        self.metadata
+            .path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.metadata.path)
+    }
+
+    /// The package identifier shared by primary, filelists, and other metadata.
+    fn pkgid(&self) -> &str {
+        self.metadata.checksum_sha256.as_deref().unwrap_or("")
+    }
 
-        // Route: /repodata/other.xml.gz
-        if trimmed == "/repodata/other.xml.gz" {
-            return handle_other_xml_gz();
+    /// Whether this is a source package.
+    ///
+    /// Read from the lead's package type when a header is stored, otherwise from the
+    /// filename's `src`/`nosrc` arch.
+    fn is_source(&self) -> bool {
+        match &self.header {
+            Some(h) => h.is_source,
+            None => matches!(
+                parse_rpm_filename(self.filename()).arch.as_deref(),
+                Some("src" | "nosrc")
+            ),
         }
+    }
+
+    /// Whether the package belongs in a `/{releasever}/{basearch}` tree.
+    fn in_tree(&self, tree: &Tree<'_>) -> bool {
+        let info = self.file_info();
+        tree.contains(
+            info.release.as_deref().unwrap_or(""),
+            info.arch.as_deref().unwrap_or(""),
+        )
+    }
+
+    /// Files owned by the package, empty when no header is stored.
+    fn files(&self) -> impl Iterator<Item = &PackageFile> {
+        self.header.iter().flat_map(|h| h.files.iter())
+    }
 
-        // Route: /packages/{filename} or /Packages/{filename} - redirect to download
-        if let Some(filename) = trimmed
-            .strip_prefix("/packages/")
-            .or_else(|| trimmed.strip_prefix("/Packages/"))
-        {
-            if !filename.contains('/') && !filename.is_empty() {
-                return handle_package_download(filename, &context, &artifacts);
+    /// Name, epoch, version, release, and arch, from the header when available.
+    ///
+    /// Without a header, the epoch is recovered from the `E:V-R` version string
+    /// `parse_metadata` stored for the artifact.
+    fn file_info(&self) -> RpmFileInfo {
+        match &self.header {
+            Some(h) => RpmFileInfo {
+                name: Some(h.name.clone()),
+                epoch: h.epoch,
+                version: Some(h.version.clone()),
+                release: Some(h.release.clone()),
+                arch: Some(h.filename_arch().to_string()),
+            },
+            None => {
+                let mut info = parse_rpm_filename(self.filename());
+                info.epoch = self
+                    .metadata
+                    .version
+                    .as_deref()
+                    .and_then(|v| split_evr(v).0)
+                    .and_then(|e| e.parse().ok());
+                info
             }
         }
+    }
+}
 
-        // 404 for everything else
-        Ok(HttpResponse {
-            status: 404,
-            headers: vec![("content-type".to_string(), "text/plain".to_string())],
-            body: b"Not Found".to_vec(),
+/// Order artifacts so each name.arch group lists its newest version first.
+///
+/// Groups keep the order in which they first appear; only the versions within a
+/// group are reordered, using rpm's EVR comparison.
+fn sort_newest_first(artifacts: &mut Vec<RpmArtifact>) {
+    let mut groups: HashMap<(Option<String>, Option<String>), usize> = HashMap::new();
+    let mut keyed: Vec<(usize, RpmFileInfo, RpmArtifact)> = artifacts
+        .drain(..)
+        .map(|a| {
+            let info = a.file_info();
+            let next = groups.len();
+            let group = *groups
+                .entry((info.name.clone(), info.arch.clone()))
+                .or_insert(next);
+            (group, info, a)
         })
+        .collect();
+
+    keyed.sort_by(|(ga, ia, _), (gb, ib, _)| {
+        ga.cmp(gb).then_with(|| compare_evr(&ib.evr(), &ia.evr()))
+    });
+    artifacts.extend(keyed.into_iter().map(|(_, _, a)| a));
+}
+
+// ---------------------------------------------------------------------------
+// Index generation
+// ---------------------------------------------------------------------------
+
+/// Generate `rpm-index.json` listing every package in the repository, and
+/// `repoclosure.json` reporting requires the repository can't satisfy.
+///
+/// Expects artifacts in `sort_newest_first` order: packages are listed newest-first per
+/// name.arch, and `latest` holds the first (newest) package of each group. Source
+/// packages are listed separately under `source_packages`, and advisories under
+/// `advisories`.
+fn generate_rpm_index(artifacts: &[RpmArtifact]) -> Result<Option<IndexFiles>, String> {
+    if artifacts.is_empty() {
+        return Ok(None);
     }
+
+    let (documents, packages): (Vec<&RpmArtifact>, Vec<&RpmArtifact>) =
+        artifacts.iter().partition(|a| a.is_document());
+
+    let mut seen = std::collections::HashSet::new();
+    let latest: Vec<serde_json::Value> = packages
+        .iter()
+        .filter_map(|a| {
+            let info = a.file_info();
+            if !seen.insert((info.name.clone(), info.arch.clone())) {
+                return None;
+            }
+            Some(serde_json::json!({
+                "name": info.name,
+                "arch": info.arch,
+                "version": a.metadata.version,
+                "path": a.metadata.path,
+            }))
+        })
+        .collect();
+
+    // Link each module stream's artifacts to the packages in the repository, both ways
+    let paths: HashMap<String, &str> = packages
+        .iter()
+        .map(|a| (a.nevra(), a.metadata.path.as_str()))
+        .collect();
+    let mut package_modules: HashMap<String, Vec<String>> = HashMap::new();
+    let modules: Vec<serde_json::Value> =
+        module_streams(documents.iter().filter_map(|a| a.modulemd.as_deref()))
+            .into_iter()
+            .map(|stream| {
+                let rpms: Vec<serde_json::Value> = stream
+                    .rpms
+                    .iter()
+                    .map(|rpm| {
+                        let key = parse_nevra(rpm).map(|n| n.to_string()).unwrap_or_default();
+                        package_modules
+                            .entry(key.clone())
+                            .or_default()
+                            .push(stream.nsvca());
+                        serde_json::json!({ "nevra": rpm, "path": paths.get(&key) })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "nsvca": stream.nsvca(),
+                    "name": stream.name,
+                    "stream": stream.stream,
+                    "version": stream.version,
+                    "context": stream.context,
+                    "arch": stream.arch,
+                    "rpms": rpms,
+                })
+            })
+            .collect();
+
+    let (sources, binaries): (Vec<&RpmArtifact>, Vec<&RpmArtifact>) =
+        packages.into_iter().partition(|a| a.is_source());
+    let closure = repoclosure(&binaries);
+    let entry = |a: &RpmArtifact| index_entry(a, &package_modules);
+
+    let index = serde_json::json!({
+        "format": "rpm-custom",
+        "total_count": artifacts.len(),
+        "total_size_bytes": artifacts.iter().map(|a| a.metadata.size_bytes).sum::<u64>(),
+        "packages": binaries.into_iter().map(entry).collect::<Vec<_>>(),
+        "source_packages": sources.into_iter().map(entry).collect::<Vec<_>>(),
+        "latest": latest,
+        "advisories": documents.iter().copied().filter_map(advisory_entry).collect::<Vec<_>>(),
+        "modules": modules,
+    });
+
+    let json_bytes =
+        serde_json::to_vec_pretty(&index).map_err(|e| format!("Failed to serialize index: {e}"))?;
+    let closure_bytes = serde_json::to_vec_pretty(&closure)
+        .map_err(|e| format!("Failed to serialize repoclosure: {e}"))?;
+
+    Ok(Some(vec![
+        ("rpm-index.json".to_string(), json_bytes),
+        ("repoclosure.json".to_string(), closure_bytes),
+    ]))
 }
 
-export!(RpmFormatHandler);
+/// The `repoclosure.json` report for the repository's binary packages.
+///
+/// Each package with unresolved requires is listed under `broken`. Packages stored
+/// without a header carry no dependency data, so they can't be checked and are listed
+/// under `unchecked`; `complete` is only true when neither list has entries.
+fn repoclosure(packages: &[&RpmArtifact]) -> serde_json::Value {
+    let (checked, unchecked): (Vec<&RpmArtifact>, Vec<&RpmArtifact>) =
+        packages.iter().copied().partition(|a| a.header.is_some());
+    let headers: Vec<&RpmHeaderInfo> = checked.iter().filter_map(|a| a.header.as_ref()).collect();
+
+    let broken: Vec<serde_json::Value> = checked
+        .iter()
+        .zip(unresolved_requires(&headers))
+        .filter(|(_, unresolved)| !unresolved.is_empty())
+        .map(|(a, unresolved)| {
+            serde_json::json!({
+                "nevra": a.nevra(),
+                "path": a.metadata.path,
+                "unresolved": unresolved,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "complete": broken.is_empty() && unchecked.is_empty(),
+        "checked_count": checked.len(),
+        "broken_count": broken.len(),
+        "broken": broken,
+        "unchecked": unchecked.iter().map(|a| &a.metadata.path).collect::<Vec<_>>(),
+    })
+}
+
+/// A single advisory entry in `rpm-index.json`, or `None` if its content wasn't stored.
+fn advisory_entry(a: &RpmArtifact) -> Option<serde_json::Value> {
+    let advisory = a.advisory.as_ref()?;
+    Some(serde_json::json!({
+        "path": a.metadata.path,
+        "id": advisory.id,
+        "type": advisory.kind.as_str(),
+        "severity": advisory.severity,
+        "issued": advisory.issued,
+        "packages": advisory.packages,
+    }))
+}
+
+/// A single package entry in `rpm-index.json`.
+///
+/// `package_modules` maps a package NEVRA to the module streams that list it.
+fn index_entry(
+    a: &RpmArtifact,
+    package_modules: &HashMap<String, Vec<String>>,
+) -> serde_json::Value {
+    let info = a.file_info();
+
+    let mut entry = serde_json::Map::new();
+    entry.insert(
+        "path".into(),
+        serde_json::Value::String(a.metadata.path.clone()),
+    );
+    if let Some(ref v) = a.metadata.version {
+        entry.insert("version".into(), serde_json::Value::String(v.clone()));
+    }
+    if let Some(name) = info.name {
+        entry.insert("name".into(), serde_json::Value::String(name));
+    }
+    entry.insert(
+        "epoch".into(),
+        serde_json::Value::Number(info.epoch.unwrap_or(0).into()),
+    );
+    if let Some(arch) = info.arch {
+        entry.insert("arch".into(), serde_json::Value::String(arch));
+    }
+    if let Some(release) = info.release {
+        entry.insert("release".into(), serde_json::Value::String(release));
+    }
+    if let Some(summary) = a.header.as_ref().and_then(|h| h.summary.clone()) {
+        entry.insert("summary".into(), serde_json::Value::String(summary));
+    }
+    if let Some(license) = a.header.as_ref().and_then(|h| h.license.clone()) {
+        entry.insert("license".into(), serde_json::Value::String(license));
+    }
+    if let Some(srpm) = a.header.as_ref().and_then(|h| h.source_rpm.clone()) {
+        entry.insert("source_rpm".into(), serde_json::Value::String(srpm));
+    }
+    if let Some(sig) = a.header.as_ref().and_then(|h| h.signature.as_ref()) {
+        entry.insert(
+            "signature".into(),
+            serde_json::json!({ "key_id": sig.key_id, "algorithm": sig.algorithm }),
+        );
+    }
+    if let Some(modules) = package_modules.get(&a.nevra()) {
+        entry.insert("modules".into(), serde_json::json!(modules));
+    }
+    entry.insert(
+        "size_bytes".into(),
+        serde_json::Value::Number(a.metadata.size_bytes.into()),
+    );
+    serde_json::Value::Object(entry)
+}
 
 // ---------------------------------------------------------------------------
 // Request handler helpers
 // ---------------------------------------------------------------------------
 
-/// Generate repomd.xml pointing to the primary, filelists, and other metadata files.
-fn handle_repomd_xml(
-    _context: &RepoContext,
-    _artifacts: &[Metadata],
+/// Route a native dnf/yum request to the matching repodata or package handler.
+///
+/// Paths under `/SRPMS` address the source package repository; everything else addresses
+/// the binary repository. Advisories, comps groups, and module metadata are published in
+/// both.
+fn route_request(
+    request: &HttpRequest,
+    context: &RepoContext,
+    artifacts: Vec<RpmArtifact>,
+    config: &RpmConfig,
 ) -> Result<HttpResponse, String> {
-    // Simple repomd.xml - in production you'd compute checksums of each data file,
-    // but for serving purposes we use a static structure with timestamps.
-    let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
-<repomd xmlns="http://linux.duke.edu/metadata/repo" xmlns:rpm="http://linux.duke.edu/metadata/rpm">
-  <revision>1</revision>
-  <data type="primary">
-    <location href="repodata/primary.xml.gz"/>
-  </data>
-  <data type="filelists">
-    <location href="repodata/filelists.xml.gz"/>
-  </data>
-  <data type="other">
-    <location href="repodata/other.xml.gz"/>
-  </data>
-</repomd>
-"#;
+    let path = request.path.as_str();
+
+    // Only handle GET and HEAD
+    if request.method != "GET" && request.method != "HEAD" {
+        return Ok(HttpResponse {
+            status: 405,
+            headers: vec![("allow".to_string(), "GET, HEAD".to_string())],
+            body: b"Method Not Allowed".to_vec(),
+        });
+    }
+
+    let trimmed = path.trim_end_matches('/');
+
+    let (documents, packages): (Vec<RpmArtifact>, Vec<RpmArtifact>) =
+        artifacts.into_iter().partition(RpmArtifact::is_document);
+    let (sources, binaries): (Vec<RpmArtifact>, Vec<RpmArtifact>) =
+        packages.into_iter().partition(RpmArtifact::is_source);
+    let full_path = trimmed;
+    let (trimmed, artifacts) = if let Some((tree, rest)) = Tree::split(trimmed) {
+        // Route: /{releasever}/{basearch}/... - one distribution's packages
+        let packages = if tree.is_source() { sources } else { binaries };
+        let packages = packages.into_iter().filter(|a| a.in_tree(&tree)).collect();
+        (rest, packages)
+    } else if let Some(rest) = trimmed
+        .strip_prefix("/SRPMS")
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+    {
+        (rest, sources)
+    } else {
+        (trimmed, binaries)
+    };
+    let repo = Repo {
+        packages: &artifacts,
+        documents: &documents,
+    };
+
+    // Route: /metalink and /mirrorlist - where this tree is served, mirrors first
+    if trimmed == "/metalink" || trimmed == "/mirrorlist" {
+        let tree = &full_path[..full_path.len() - trimmed.len()];
+        let base_urls: Vec<String> = config
+            .mirrors
+            .iter()
+            .chain(std::iter::once(&context.base_url))
+            .map(|base| format!("{}{tree}", base.trim_end_matches('/')))
+            .collect();
+        return if trimmed == "/metalink" {
+            handle_metalink(&repo, &base_urls, config)
+        } else {
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![("content-type".to_string(), "text/plain".to_string())],
+                body: mirrorlist(&base_urls).into_bytes(),
+            })
+        };
+    }
+
+    // Route: /repodata/repomd.xml
+    if trimmed == "/repodata/repomd.xml" {
+        return handle_repomd_xml(&repo, config);
+    }
+
+    // Route: /repodata/repomd.xml.asc - detached signature for repo_gpgcheck
+    if trimmed == "/repodata/repomd.xml.asc" {
+        return handle_repomd_signature(&repo, config);
+    }
+
+    // Route: /repodata/repomd.xml.key - the public key that signature verifies with
+    if trimmed == "/repodata/repomd.xml.key" {
+        return handle_repomd_key(config);
+    }
+
+    // Route: /repodata/comps.xml - package groups, uncompressed for the `group` entry
+    if trimmed == "/repodata/comps.xml" {
+        if let Some(xml) = repodata_document("comps.xml", &repo, config) {
+            return Ok(HttpResponse {
+                status: 200,
+                headers: vec![("content-type".to_string(), "application/xml".to_string())],
+                body: xml.into_bytes(),
+            });
+        }
+    }
+
+    // Route: /repodata/{primary,filelists,other,updateinfo,comps}.xml.{gz,zst,xz}
+    // and /repodata/modules.yaml.{gz,zst,xz}
+    if let Some((file, compression)) = trimmed
+        .strip_prefix("/repodata/")
+        .and_then(parse_repodata_filename)
+    {
+        if let Some(document) = repodata_document(file, &repo, config) {
+            return handle_repodata_file(&document, compression);
+        }
+    }
+
+    // Route: /packages/{filename} or /Packages/{filename} - redirect to download
+    if let Some(filename) = trimmed
+        .strip_prefix("/packages/")
+        .or_else(|| trimmed.strip_prefix("/Packages/"))
+    {
+        if !filename.contains('/') && !filename.is_empty() {
+            return handle_package_download(filename, context, &artifacts);
+        }
+    }
 
+    // 404 for everything else
     Ok(HttpResponse {
-        status: 200,
-        headers: vec![("content-type".to_string(), "application/xml".to_string())],
-        body: xml.as_bytes().to_vec(),
+        status: 404,
+        headers: vec![("content-type".to_string(), "text/plain".to_string())],
+        body: b"Not Found".to_vec(),
     })
 }
 
-/// Generate primary.xml.gz with package entries.
-fn handle_primary_xml_gz(
-    _context: &RepoContext,
-    artifacts: &[Metadata],
-) -> Result<HttpResponse, String> {
-    let mut xml = String::from(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
-         <metadata xmlns=\"http://linux.duke.edu/metadata/common\" \
-         xmlns:rpm=\"http://linux.duke.edu/metadata/rpm\" \
-         packages=\"",
-    );
-    xml.push_str(&artifacts.len().to_string());
-    xml.push_str("\">\n");
-
-    for artifact in artifacts {
-        let filename = artifact.path.rsplit('/').next().unwrap_or(&artifact.path);
-        let info = parse_rpm_filename(filename);
+/// One repository tree: the packages it serves and the advisory, comps, and modulemd
+/// documents published with them.
+struct Repo<'a> {
+    packages: &'a [RpmArtifact],
+    documents: &'a [RpmArtifact],
+}
 
-        let name = info.name.as_deref().unwrap_or("unknown");
-        let version = info.version.as_deref().unwrap_or("0");
-        let release = info.release.as_deref().unwrap_or("0");
-        let arch = info.arch.as_deref().unwrap_or("x86_64");
+impl Repo<'_> {
+    /// Advisories whose content was stored on upload.
+    fn advisories(&self) -> impl Iterator<Item = &Advisory> {
+        self.documents.iter().filter_map(|a| a.advisory.as_ref())
+    }
 
-        xml.push_str("  <package type=\"rpm\">\n");
-        xml.push_str(&format!("    <name>{}</name>\n", xml_escape(name)));
-        xml.push_str(&format!("    <arch>{}</arch>\n", xml_escape(arch)));
-        xml.push_str(&format!(
-            "    <version epoch=\"0\" ver=\"{}\" rel=\"{}\"/>\n",
-            xml_escape(version),
-            xml_escape(release)
-        ));
-        xml.push_str(&format!(
-            "    <checksum type=\"sha256\" pkgid=\"YES\">{}</checksum>\n",
-            artifact.checksum_sha256.as_deref().unwrap_or("")
-        ));
-        xml.push_str("    <summary/>\n");
-        xml.push_str("    <description/>\n");
-        xml.push_str("    <packager/>\n");
-        xml.push_str("    <url/>\n");
-        xml.push_str(&format!(
-            "    <size package=\"{}\" installed=\"0\" archive=\"0\"/>\n",
-            artifact.size_bytes
-        ));
-        xml.push_str(&format!(
-            "    <location href=\"packages/{}\"/>\n",
-            xml_escape(filename)
-        ));
-        xml.push_str("    <format>\n");
-        xml.push_str(&format!(
-            "      <rpm:provides>\n        <rpm:entry name=\"{}\" flags=\"EQ\" epoch=\"0\" ver=\"{}\" rel=\"{}\"/>\n      </rpm:provides>\n",
-            xml_escape(name),
-            xml_escape(version),
-            xml_escape(release)
-        ));
-        xml.push_str("    </format>\n");
-        xml.push_str("  </package>\n");
+    /// Comps documents whose content was stored on upload.
+    fn comps(&self) -> impl Iterator<Item = &str> {
+        self.documents.iter().filter_map(|a| a.comps.as_deref())
     }
 
-    xml.push_str("</metadata>\n");
+    /// Modulemd files whose content was stored on upload.
+    fn modulemd(&self) -> impl Iterator<Item = &str> {
+        self.documents.iter().filter_map(|a| a.modulemd.as_deref())
+    }
+}
 
-    // gzip the XML
-    let compressed = gzip_compress(xml.as_bytes())?;
+/// Generate repomd.xml describing the package metadata and supplementary repodata files.
+fn handle_repomd_xml(repo: &Repo<'_>, config: &RpmConfig) -> Result<HttpResponse, String> {
+    let xml = repomd_xml(repo, config)?;
 
     Ok(HttpResponse {
         status: 200,
-        headers: vec![("content-type".to_string(), "application/gzip".to_string())],
-        body: compressed,
+        headers: vec![("content-type".to_string(), "application/xml".to_string())],
+        body: xml.into_bytes(),
     })
 }
 
-/// Generate empty filelists.xml.gz.
-fn handle_filelists_xml_gz() -> Result<HttpResponse, String> {
-    let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
-               <filelists xmlns=\"http://linux.duke.edu/metadata/filelists\" packages=\"0\">\n\
-               </filelists>\n";
-
-    let compressed = gzip_compress(xml.as_bytes())?;
+/// Describe repomd.xml in a metalink listing every URL it's served at.
+fn handle_metalink(
+    repo: &Repo<'_>,
+    base_urls: &[String],
+    config: &RpmConfig,
+) -> Result<HttpResponse, String> {
+    let repomd = repomd_xml(repo, config)?;
+    let timestamp = repo_timestamp(repo.packages, config);
+    let xml = metalink_xml(repomd.as_bytes(), timestamp, base_urls);
 
     Ok(HttpResponse {
         status: 200,
-        headers: vec![("content-type".to_string(), "application/gzip".to_string())],
-        body: compressed,
+        headers: vec![(
+            "content-type".to_string(),
+            "application/metalink+xml".to_string(),
+        )],
+        body: xml.into_bytes(),
     })
 }
 
-/// Generate empty other.xml.gz.
-fn handle_other_xml_gz() -> Result<HttpResponse, String> {
-    let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
-               <otherdata xmlns=\"http://linux.duke.edu/metadata/other\" packages=\"0\">\n\
-               </otherdata>\n";
+/// Sign repomd.xml with the configured repository key.
+///
+/// The signature is dated with the repodata timestamp, so repeated requests for the
+/// same artifacts return the same signature, but never before the key was created:
+/// gpg rejects such signatures outright.
+fn handle_repomd_signature(repo: &Repo<'_>, config: &RpmConfig) -> Result<HttpResponse, String> {
+    let Some(key) = signing_key(config)? else {
+        return Ok(signing_not_configured());
+    };
+    let xml = repomd_xml(repo, config)?;
+    let timestamp = u32::try_from(repo_timestamp(repo.packages, config)).unwrap_or(u32::MAX);
+    let signature = key.sign_detached(xml.as_bytes(), timestamp.max(key.created()));
+
+    Ok(HttpResponse {
+        status: 200,
+        headers: vec![(
+            "content-type".to_string(),
+            "application/pgp-signature".to_string(),
+        )],
+        body: signature.into_bytes(),
+    })
+}
 
-    let compressed = gzip_compress(xml.as_bytes())?;
+/// Serve the public half of the configured repository key.
+fn handle_repomd_key(config: &RpmConfig) -> Result<HttpResponse, String> {
+    let Some(key) = signing_key(config)? else {
+        return Ok(signing_not_configured());
+    };
 
     Ok(HttpResponse {
         status: 200,
-        headers: vec![("content-type".to_string(), "application/gzip".to_string())],
-        body: compressed,
+        headers: vec![(
+            "content-type".to_string(),
+            "application/pgp-keys".to_string(),
+        )],
+        body: key.public_key_armored().into_bytes(),
     })
 }
 
-/// Redirect package download to the artifact storage download endpoint.
-fn handle_package_download(
-    filename: &str,
-    context: &RepoContext,
-    artifacts: &[Metadata],
-) -> Result<HttpResponse, String> {
-    let artifact = artifacts
-        .iter()
-        .find(|a| a.path.rsplit('/').next().unwrap_or(&a.path) == filename);
+/// Load the configured repository signing key, if any.
+fn signing_key(config: &RpmConfig) -> Result<Option<SigningKey>, String> {
+    config
+        .signing_key
+        .as_deref()
+        .map(|armored| {
+            SigningKey::from_armored(armored).map_err(|e| format!("Invalid RPM_SIGNING_KEY: {e}"))
+        })
+        .transpose()
+}
 
-    match artifact {
-        Some(a) => {
-            let download_url = format!("{}/{}", context.download_base_url, a.path);
-            Ok(HttpResponse {
-                status: 302,
-                headers: vec![("location".to_string(), download_url)],
-                body: Vec::new(),
-            })
-        }
-        None => Ok(HttpResponse {
-            status: 404,
-            headers: vec![("content-type".to_string(), "text/plain".to_string())],
-            body: format!("Package '{}' not found", filename).into_bytes(),
-        }),
+fn signing_not_configured() -> HttpResponse {
+    HttpResponse {
+        status: 404,
+        headers: vec![("content-type".to_string(), "text/plain".to_string())],
+        body: b"Repository signing is not configured".to_vec(),
     }
 }
 
-// ---------------------------------------------------------------------------
-// Helpers
-// ---------------------------------------------------------------------------
-
-/// Minimal gzip compression using the DEFLATE algorithm.
-///
-/// WASM plugins can't use libflate or flate2 easily, so we produce a valid
-/// gzip stream with STORED blocks (no actual compression, just framing).
-/// This is perfectly valid per RFC 1952 and all tools accept it.
-fn gzip_compress(data: &[u8]) -> Result<Vec<u8>, String> {
-    let mut output = Vec::with_capacity(data.len() + 64);
-
-    // Gzip header (10 bytes)
-    output.extend_from_slice(&[
-        0x1f, 0x8b, // magic
-        0x08, // method: deflate
-        0x00, // flags: none
-        0x00, 0x00, 0x00, 0x00, // mtime
-        0x00, // extra flags
-        0xff, // OS: unknown
-    ]);
-
-    // DEFLATE stored blocks
-    // Each stored block can hold up to 65535 bytes
-    let chunks: Vec<&[u8]> = if data.is_empty() {
-        vec![&[]]
-    } else {
-        data.chunks(65535).collect()
-    };
+/// A generated repodata file, as advertised in repomd.xml.
+struct RepodataFile {
+    /// The repomd `<data type>`, e.g. "primary".
+    data_type: &'static str,
+    /// Location relative to the repository root.
+    href: String,
+    /// Bytes served to clients.
+    data: Vec<u8>,
+    /// The uncompressed document, for compressed files.
+    open: Option<Vec<u8>>,
+}
 
-    for (i, chunk) in chunks.iter().enumerate() {
-        let is_last = i == chunks.len() - 1;
-        // Block header: 1 byte (BFINAL=1 for last, BTYPE=00 for stored)
-        output.push(if is_last { 0x01 } else { 0x00 });
-        let len = chunk.len() as u16;
-        let nlen = !len;
-        output.extend_from_slice(&len.to_le_bytes());
-        output.extend_from_slice(&nlen.to_le_bytes());
-        output.extend_from_slice(chunk);
-    }
-
-    // CRC32 and original size (ISIZE)
-    let crc = crc32(data);
-    let size = data.len() as u32;
-    output.extend_from_slice(&crc.to_le_bytes());
-    output.extend_from_slice(&size.to_le_bytes());
-
-    Ok(output)
-}
-
-/// CRC32 (ISO 3309 / ITU-T V.42) used by gzip.
-fn crc32(data: &[u8]) -> u32 {
-    let mut crc: u32 = 0xFFFF_FFFF;
-    for &byte in data {
-        crc ^= byte as u32;
-        for _ in 0..8 {
-            if crc & 1 != 0 {
-                crc = (crc >> 1) ^ 0xEDB8_8320;
-            } else {
-                crc >>= 1;
-            }
+impl RepodataFile {
+    /// A `repodata/{file}` document served as is.
+    fn plain(data_type: &'static str, file: &str, document: String) -> Self {
+        RepodataFile {
+            data_type,
+            href: format!("repodata/{file}"),
+            data: document.into_bytes(),
+            open: None,
         }
     }
-    !crc
-}
+
+    /// A `repodata/{file}.{ext}` document compressed with `compression`.
+    fn compressed(
+        data_type: &'static str,
+        file: &str,
+        compression: Compression,
+        document: String,
+    ) -> Result<Self, String> {
+        Ok(RepodataFile {
+            data_type,
+            href: format!("repodata/{file}.{}", compression.extension()),
+            data: compression.compress(document.as_bytes())?,
+            open: Some(document.into_bytes()),
+        })
+    }
+}
+
+/// The repodata documents served under `/repodata/`, with their repomd data types, in
+/// repomd.xml order. Each is also served compressed in every supported format.
+const REPODATA_DOCUMENTS: [(&str, &str); 6] = [
+    ("primary", "primary.xml"),
+    ("filelists", "filelists.xml"),
+    ("other", "other.xml"),
+    ("updateinfo", "updateinfo.xml"),
+    ("group", "comps.xml"),
+    ("modules", "modules.yaml"),
+];
+
+/// Split a repodata filename like `primary.xml.zst` into its document and compression.
+fn parse_repodata_filename(filename: &str) -> Option<(&'static str, Compression)> {
+    let (stem, extension) = filename.rsplit_once('.')?;
+    let (_, file) = REPODATA_DOCUMENTS.into_iter().find(|(_, f)| *f == stem)?;
+    Some((file, Compression::from_extension(extension)?))
+}
+
+/// Render the uncompressed repodata document with the given filename.
+///
+/// Returns `None` for updateinfo, comps, and modules when the repository has no
+/// advisories, comps, or modulemd documents; createrepo_c leaves them out of repomd.xml
+/// in that case too.
+fn repodata_document(file: &str, repo: &Repo<'_>, config: &RpmConfig) -> Option<String> {
+    match file {
+        "primary.xml" => Some(primary_xml(repo.packages)),
+        "filelists.xml" => Some(filelists_xml(repo.packages)),
+        "other.xml" => Some(other_xml(repo.packages, config.changelog_limit)),
+        "updateinfo.xml" => (repo.advisories().next().is_some()).then(|| updateinfo_xml(repo)),
+        "comps.xml" => (repo.comps().next().is_some()).then(|| merge_comps(repo.comps())),
+        _ => (repo.modulemd().next().is_some()).then(|| merge_modulemd(repo.modulemd())),
+    }
+}
+
+/// Generate every repodata file for the current artifact set.
+///
+/// Documents use the configured compression, except comps: it's published uncompressed
+/// as `group` and gzipped as `group_gz`, the entries dnf looks for.
+///
+/// The output is deterministic for a given artifact set, so the checksums in
+/// repomd.xml match the files served on later requests.
+fn repodata_files(repo: &Repo<'_>, config: &RpmConfig) -> Result<Vec<RepodataFile>, String> {
+    let mut files = Vec::new();
+    for (data_type, file) in REPODATA_DOCUMENTS {
+        let Some(document) = repodata_document(file, repo, config) else {
+            continue;
+        };
+        if data_type == "group" {
+            files.push(RepodataFile::plain("group", file, document.clone()));
+            files.push(RepodataFile::compressed(
+                "group_gz",
+                file,
+                Compression::Gzip,
+                document,
+            )?);
+        } else {
+            files.push(RepodataFile::compressed(
+                data_type,
+                file,
+                config.repodata_compression,
+                document,
+            )?);
+        }
+    }
+    Ok(files)
+}
+
+/// Render the repomd.xml document.
+fn repomd_xml(repo: &Repo<'_>, config: &RpmConfig) -> Result<String, String> {
+    let timestamp = repo_timestamp(repo.packages, config);
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <repomd xmlns=\"http://linux.duke.edu/metadata/repo\" \
+         xmlns:rpm=\"http://linux.duke.edu/metadata/rpm\">\n",
+    );
+    xml.push_str(&format!("  <revision>{}</revision>\n", repo_revision(repo)));
+
+    for file in repodata_files(repo, config)? {
+        xml.push_str(&format!("  <data type=\"{}\">\n", file.data_type));
+        xml.push_str(&format!(
+            "    <checksum type=\"sha256\">{}</checksum>\n",
+            sha256_hex(&file.data)
+        ));
+        if let Some(open) = &file.open {
+            xml.push_str(&format!(
+                "    <open-checksum type=\"sha256\">{}</open-checksum>\n",
+                sha256_hex(open)
+            ));
+        }
+        xml.push_str(&format!("    <location href=\"{}\"/>\n", file.href));
+        xml.push_str(&format!("    <timestamp>{timestamp}</timestamp>\n"));
+        xml.push_str(&format!("    <size>{}</size>\n", file.data.len()));
+        if let Some(open) = &file.open {
+            xml.push_str(&format!("    <open-size>{}</open-size>\n", open.len()));
+        }
+        xml.push_str("  </data>\n");
+    }
+
+    xml.push_str("</repomd>\n");
+    Ok(xml)
+}
+
+/// A revision that changes whenever the set of artifacts changes.
+///
+/// The SHA-256 of every package's and document's path, checksum, and size, in path order.
+fn repo_revision(repo: &Repo<'_>) -> String {
+    let mut lines: Vec<String> = repo
+        .packages
+        .iter()
+        .chain(repo.documents)
+        .map(|a| {
+            format!(
+                "{}\t{}\t{}\n",
+                a.metadata.path,
+                a.pkgid(),
+                a.metadata.size_bytes
+            )
+        })
+        .collect();
+    lines.sort();
+    sha256_hex(lines.concat().as_bytes())
+}
+
+/// The repodata timestamp: the newest package build time.
+///
+/// Without any build times (an empty repository, or no stored headers) it falls back to
+/// something that doesn't change between requests, since the metalink publishes
+/// repomd.xml's size and checksum and a signature covers its exact bytes: the signing
+/// key's creation time, so repomd.xml.asc is never dated before its key, or else 0.
+fn repo_timestamp(artifacts: &[RpmArtifact], config: &RpmConfig) -> u64 {
+    artifacts
+        .iter()
+        .filter_map(|a| a.header.as_ref()?.build_time)
+        .max()
+        .map(u64::from)
+        .unwrap_or_else(|| match signing_key(config) {
+            Ok(Some(key)) => u64::from(key.created()),
+            _ => 0,
+        })
+}
+
+/// Serve a compressed repodata document.
+///
+/// Every compression format is served regardless of configuration, so a client still
+/// holding a repomd.xml from before the setting changed can fetch the files it names.
+fn handle_repodata_file(document: &str, compression: Compression) -> Result<HttpResponse, String> {
+    let compressed = compression.compress(document.as_bytes())?;
+
+    Ok(HttpResponse {
+        status: 200,
+        headers: vec![(
+            "content-type".to_string(),
+            compression.content_type().to_string(),
+        )],
+        body: compressed,
+    })
+}
+
+/// Render the primary.xml document.
+fn primary_xml(artifacts: &[RpmArtifact]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <metadata xmlns=\"http://linux.duke.edu/metadata/common\" \
+         xmlns:rpm=\"http://linux.duke.edu/metadata/rpm\" \
+         packages=\"",
+    );
+    xml.push_str(&artifacts.len().to_string());
+    xml.push_str("\">\n");
+
+    for artifact in artifacts {
+        let filename = artifact.filename();
+        let info = artifact.file_info();
+        let header = artifact.header.as_ref();
+
+        let name = info.name.as_deref().unwrap_or("unknown");
+        let version = info.version.as_deref().unwrap_or("0");
+        let release = info.release.as_deref().unwrap_or("0");
+        let arch = info.arch.as_deref().unwrap_or("x86_64");
+
+        xml.push_str("  <package type=\"rpm\">\n");
+        xml.push_str(&format!("    <name>{}</name>\n", xml_escape(name)));
+        xml.push_str(&format!("    <arch>{}</arch>\n", xml_escape(arch)));
+        xml.push_str(&version_element(&info));
+        xml.push_str(&format!(
+            "    <checksum type=\"sha256\" pkgid=\"YES\">{}</checksum>\n",
+            xml_escape(artifact.pkgid())
+        ));
+        xml.push_str(&optional_element(
+            "summary",
+            header.and_then(|h| h.summary.as_deref()),
+        ));
+        xml.push_str(&optional_element(
+            "description",
+            header.and_then(|h| h.description.as_deref()),
+        ));
+        xml.push_str(&optional_element(
+            "packager",
+            header.and_then(|h| h.packager.as_deref()),
+        ));
+        xml.push_str(&optional_element(
+            "url",
+            header.and_then(|h| h.url.as_deref()),
+        ));
+        if let Some(build_time) = header.and_then(|h| h.build_time) {
+            xml.push_str(&format!(
+                "    <time file=\"{build_time}\" build=\"{build_time}\"/>\n"
+            ));
+        }
+        xml.push_str(&format!(
+            "    <size package=\"{}\" installed=\"0\" archive=\"0\"/>\n",
+            artifact.metadata.size_bytes
+        ));
+        xml.push_str(&format!(
+            "    <location href=\"packages/{}\"/>\n",
+            xml_escape(filename)
+        ));
+        xml.push_str("    <format>\n");
+        if let Some(license) = header.and_then(|h| h.license.as_deref()) {
+            xml.push_str(&format!(
+                "      <rpm:license>{}</rpm:license>\n",
+                xml_escape(license)
+            ));
+        }
+        // Links a binary package to the source package it was built from
+        match header.and_then(|h| h.source_rpm.as_deref()) {
+            Some(srpm) => xml.push_str(&format!(
+                "      <rpm:sourcerpm>{}</rpm:sourcerpm>\n",
+                xml_escape(srpm)
+            )),
+            None => xml.push_str("      <rpm:sourcerpm/>\n"),
+        }
+        match header {
+            Some(h) => xml.push_str(&dependency_sections(h)),
+            // Without a header, the package can only be known to provide itself
+            None => xml.push_str(&format!(
+                "      <rpm:provides>\n        <rpm:entry name=\"{}\" flags=\"EQ\" epoch=\"{}\" ver=\"{}\" rel=\"{}\"/>\n      </rpm:provides>\n",
+                xml_escape(name),
+                info.epoch.unwrap_or(0),
+                xml_escape(version),
+                xml_escape(release)
+            )),
+        }
+        for file in artifact.files().filter(|f| is_primary_file(&f.path)) {
+            xml.push_str(&file_element("      ", file));
+        }
+        xml.push_str("    </format>\n");
+        xml.push_str("  </package>\n");
+    }
+
+    xml.push_str("</metadata>\n");
+    xml
+}
+
+/// Render the filelists.xml document.
+///
+/// Packages are keyed by the same pkgid as in primary.xml. Artifacts without a stored
+/// header are listed with no files.
+fn filelists_xml(artifacts: &[RpmArtifact]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <filelists xmlns=\"http://linux.duke.edu/metadata/filelists\" packages=\"{}\">\n",
+        artifacts.len()
+    );
+
+    for artifact in artifacts {
+        let info = artifact.file_info();
+        xml.push_str(&format!(
+            "  <package pkgid=\"{}\" name=\"{}\" arch=\"{}\">\n",
+            xml_escape(artifact.pkgid()),
+            xml_escape(info.name.as_deref().unwrap_or("unknown")),
+            xml_escape(info.arch.as_deref().unwrap_or("x86_64"))
+        ));
+        xml.push_str(&version_element(&info));
+        for file in artifact.files() {
+            xml.push_str(&file_element("    ", file));
+        }
+        xml.push_str("  </package>\n");
+    }
+
+    xml.push_str("</filelists>\n");
+    xml
+}
+
+/// Render a `<file>` element, with a `type` attribute for directories and ghosts.
+fn file_element(indent: &str, file: &PackageFile) -> String {
+    let type_attr = match file.file_type {
+        FileType::File => "",
+        FileType::Dir => " type=\"dir\"",
+        FileType::Ghost => " type=\"ghost\"",
+    };
+    format!(
+        "{indent}<file{type_attr}>{}</file>\n",
+        xml_escape(&file.path)
+    )
+}
+
+/// Whether a file belongs in primary.xml as well as filelists.xml.
+///
+/// Matches createrepo's rule, so file dependencies on common paths resolve without
+/// downloading filelists.
+fn is_primary_file(path: &str) -> bool {
+    path.starts_with("/etc/") || path.contains("bin/") || path == "/usr/lib/sendmail"
+}
+
+/// Render the other.xml document.
+///
+/// Each package gets at most `changelog_limit` of its newest changelog entries, listed
+/// oldest first as createrepo does.
+fn other_xml(artifacts: &[RpmArtifact], changelog_limit: usize) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <otherdata xmlns=\"http://linux.duke.edu/metadata/other\" packages=\"{}\">\n",
+        artifacts.len()
+    );
+
+    for artifact in artifacts {
+        let info = artifact.file_info();
+        xml.push_str(&format!(
+            "  <package pkgid=\"{}\" name=\"{}\" arch=\"{}\">\n",
+            xml_escape(artifact.pkgid()),
+            xml_escape(info.name.as_deref().unwrap_or("unknown")),
+            xml_escape(info.arch.as_deref().unwrap_or("x86_64"))
+        ));
+        xml.push_str(&version_element(&info));
+        if let Some(header) = &artifact.header {
+            for entry in header.changelogs.iter().take(changelog_limit).rev() {
+                xml.push_str(&format!(
+                    "    <changelog author=\"{}\" date=\"{}\">{}</changelog>\n",
+                    xml_escape(&entry.author),
+                    entry.date,
+                    xml_escape(&entry.text)
+                ));
+            }
+        }
+        xml.push_str("  </package>\n");
+    }
+
+    xml.push_str("</otherdata>\n");
+    xml
+}
+
+/// Render the updateinfo.xml document from the repository's advisories.
+///
+/// Packages an advisory names are linked to the repository's copy, when it has one, for
+/// the filename, checksum, and source package; the rest are listed by NEVRA alone.
+fn updateinfo_xml(repo: &Repo<'_>) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<updates>\n");
+
+    for advisory in repo.advisories() {
+        let from = advisory
+            .from
+            .as_deref()
+            .map(|f| format!(" from=\"{}\"", xml_escape(f)))
+            .unwrap_or_default();
+        xml.push_str(&format!(
+            "  <update{from} status=\"final\" type=\"{}\">\n",
+            advisory.kind.as_str()
+        ));
+        xml.push_str(&format!("    <id>{}</id>\n", xml_escape(&advisory.id)));
+        xml.push_str(&format!(
+            "    <title>{}</title>\n",
+            xml_escape(advisory.title.as_deref().unwrap_or(&advisory.id))
+        ));
+        xml.push_str(&format!(
+            "    <issued date=\"{}\"/>\n",
+            xml_escape(&advisory.issued)
+        ));
+        if let Some(updated) = &advisory.updated {
+            xml.push_str(&format!(
+                "    <updated date=\"{}\"/>\n",
+                xml_escape(updated)
+            ));
+        }
+        xml.push_str(&optional_element("severity", advisory.severity.as_deref()));
+        xml.push_str(&optional_element(
+            "description",
+            advisory.description.as_deref(),
+        ));
+
+        xml.push_str("    <references>\n");
+        for reference in &advisory.references {
+            xml.push_str(&format!(
+                "      <reference href=\"{}\" id=\"{}\" type=\"{}\" title=\"{}\"/>\n",
+                xml_escape(reference.href.as_deref().unwrap_or("")),
+                xml_escape(&reference.id),
+                xml_escape(&reference.kind),
+                xml_escape(reference.title.as_deref().unwrap_or(&reference.id))
+            ));
+        }
+        xml.push_str("    </references>\n");
+
+        xml.push_str("    <pkglist>\n      <collection>\n");
+        for nevra in advisory.packages.iter().filter_map(|p| parse_nevra(p)) {
+            let artifact = repo.packages.iter().find(|a| {
+                let info = a.file_info();
+                info.name.as_deref() == Some(nevra.name)
+                    && info.epoch.unwrap_or(0) == nevra.epoch
+                    && info.version.as_deref() == Some(nevra.version)
+                    && info.release.as_deref() == Some(nevra.release)
+                    && info.arch.as_deref() == Some(nevra.arch)
+            });
+            let src = artifact
+                .and_then(|a| a.header.as_ref()?.source_rpm.as_deref())
+                .map(|s| format!(" src=\"{}\"", xml_escape(s)))
+                .unwrap_or_default();
+            xml.push_str(&format!(
+                "        <package name=\"{}\" epoch=\"{}\" version=\"{}\" release=\"{}\" arch=\"{}\"{src}>\n",
+                xml_escape(nevra.name),
+                nevra.epoch,
+                xml_escape(nevra.version),
+                xml_escape(nevra.release),
+                xml_escape(nevra.arch)
+            ));
+            let filename = match artifact {
+                Some(a) => a.filename().to_string(),
+                None => format!(
+                    "{}-{}-{}.{}.rpm",
+                    nevra.name, nevra.version, nevra.release, nevra.arch
+                ),
+            };
+            xml.push_str(&format!(
+                "          <filename>{}</filename>\n",
+                xml_escape(&filename)
+            ));
+            if let Some(a) = artifact.filter(|a| !a.pkgid().is_empty()) {
+                xml.push_str(&format!(
+                    "          <sum type=\"sha256\">{}</sum>\n",
+                    xml_escape(a.pkgid())
+                ));
+            }
+            xml.push_str("        </package>\n");
+        }
+        xml.push_str("      </collection>\n    </pkglist>\n");
+        xml.push_str("  </update>\n");
+    }
+
+    xml.push_str("</updates>\n");
+    xml
+}
+
+/// Redirect package download to the artifact storage download endpoint.
+fn handle_package_download(
+    filename: &str,
+    context: &RepoContext,
+    artifacts: &[RpmArtifact],
+) -> Result<HttpResponse, String> {
+    let artifact = artifacts.iter().find(|a| a.filename() == filename);
+
+    match artifact {
+        Some(a) => {
+            let download_url = format!("{}/{}", context.download_base_url, a.metadata.path);
+            Ok(HttpResponse {
+                status: 302,
+                headers: vec![("location".to_string(), download_url)],
+                body: Vec::new(),
+            })
+        }
+        None => Ok(HttpResponse {
+            status: 404,
+            headers: vec![("content-type".to_string(), "text/plain".to_string())],
+            body: format!("Package '{}' not found", filename).into_bytes(),
+        }),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Lowercase hex SHA-256 digest.
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Render the `<rpm:provides>`, `<rpm:requires>`, etc. sections of a package's `<format>`.
+///
+/// `rpmlib(...)` requires are internal to rpm itself and omitted, as createrepo does.
+fn dependency_sections(header: &RpmHeaderInfo) -> String {
+    let sections: [(&str, &[Dependency]); 8] = [
+        ("provides", &header.provides),
+        ("requires", &header.requires),
+        ("conflicts", &header.conflicts),
+        ("obsoletes", &header.obsoletes),
+        ("suggests", &header.suggests),
+        ("enhances", &header.enhances),
+        ("recommends", &header.recommends),
+        ("supplements", &header.supplements),
+    ];
+
+    let mut xml = String::new();
+    for (section, deps) in sections {
+        let is_requires = section == "requires";
+        let entries: Vec<String> = deps
+            .iter()
+            .filter(|d| !(is_requires && d.name.starts_with("rpmlib(")))
+            .map(|d| dependency_entry(d, is_requires))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        xml.push_str(&format!("      <rpm:{section}>\n"));
+        for entry in entries {
+            xml.push_str(&entry);
+        }
+        xml.push_str(&format!("      </rpm:{section}>\n"));
+    }
+    xml
+}
+
+/// Render a single `<rpm:entry>` element.
+///
+/// Versioned entries carry `flags`, `epoch`, `ver`, and (when present) `rel`. Requires
+/// needed before installation (`Requires(pre)`, `Requires(post)`, legacy `PreReq`) are
+/// marked `pre="1"` when `mark_pre` is set.
+fn dependency_entry(dep: &Dependency, mark_pre: bool) -> String {
+    let mut entry = format!("        <rpm:entry name=\"{}\"", xml_escape(&dep.name));
+
+    if let Some(op) = dependency_flags(dep.flags) {
+        let (epoch, ver, rel) = split_evr(&dep.version);
+        entry.push_str(&format!(
+            " flags=\"{op}\" epoch=\"{}\" ver=\"{}\"",
+            xml_escape(epoch.unwrap_or("0")),
+            xml_escape(ver)
+        ));
+        if let Some(rel) = rel {
+            entry.push_str(&format!(" rel=\"{}\"", xml_escape(rel)));
+        }
+    }
+
+    let prereq_bits = RPMSENSE_PREREQ | RPMSENSE_SCRIPT_PRE | RPMSENSE_SCRIPT_POST;
+    if mark_pre && dep.flags & prereq_bits != 0 {
+        entry.push_str(" pre=\"1\"");
+    }
+
+    entry.push_str("/>\n");
+    entry
+}
+
+/// Map the comparison bits of RPMSENSE flags to the repodata `flags` attribute.
+fn dependency_flags(flags: u32) -> Option<&'static str> {
+    match flags & (RPMSENSE_LESS | RPMSENSE_GREATER | RPMSENSE_EQUAL) {
+        RPMSENSE_LESS => Some("LT"),
+        RPMSENSE_GREATER => Some("GT"),
+        RPMSENSE_EQUAL => Some("EQ"),
+        f if f == RPMSENSE_LESS | RPMSENSE_EQUAL => Some("LE"),
+        f if f == RPMSENSE_GREATER | RPMSENSE_EQUAL => Some("GE"),
+        _ => None,
+    }
+}
+
+/// Format `[epoch:]version-release`, omitting a zero or missing epoch.
+fn format_evr(epoch: Option<u32>, version: &str, release: &str) -> String {
+    match epoch {
+        Some(e) if e != 0 => format!("{e}:{version}-{release}"),
+        _ => format!("{version}-{release}"),
+    }
+}
+
+/// Split `[epoch:]version[-release]` into its components.
+fn split_evr(evr: &str) -> (Option<&str>, &str, Option<&str>) {
+    let (epoch, rest) = match evr.split_once(':') {
+        Some((e, rest)) if !e.is_empty() && e.bytes().all(|b| b.is_ascii_digit()) => {
+            (Some(e), rest)
+        }
+        _ => (None, evr),
+    };
+    match rest.rsplit_once('-') {
+        Some((ver, rel)) => (epoch, ver, Some(rel)),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Render a package's `<version epoch ver rel/>` element.
+fn version_element(info: &RpmFileInfo) -> String {
+    format!(
+        "    <version epoch=\"{}\" ver=\"{}\" rel=\"{}\"/>\n",
+        info.epoch.unwrap_or(0),
+        xml_escape(info.version.as_deref().unwrap_or("0")),
+        xml_escape(info.release.as_deref().unwrap_or("0"))
+    )
+}
+
+/// Render `<tag>value</tag>`, or an empty `<tag/>` when there is no value.
+fn optional_element(tag: &str, value: Option<&str>) -> String {
+    match value {
+        Some(v) if !v.is_empty() => format!("    <{tag}>{}</{tag}>\n", xml_escape(v)),
+        _ => format!("    <{tag}/>\n"),
+    }
+}
 
 /// Escape XML special characters.
 fn xml_escape(s: &str) -> String {
@@ -443,11 +1502,23 @@ fn xml_escape(s: &str) -> String {
 
 struct RpmFileInfo {
     name: Option<String>,
+    epoch: Option<u32>,
     version: Option<String>,
     release: Option<String>,
     arch: Option<String>,
 }
 
+impl RpmFileInfo {
+    /// The EVR used for ordering; missing components compare as `0`.
+    fn evr(&self) -> Evr<'_> {
+        Evr {
+            epoch: self.epoch.unwrap_or(0),
+            version: self.version.as_deref().unwrap_or("0"),
+            release: self.release.as_deref().unwrap_or("0"),
+        }
+    }
+}
+
 /// Parse an RPM filename into its components.
 ///
 /// RPM filenames follow the convention: `name-version-release.arch.rpm`
@@ -462,6 +1533,7 @@ fn parse_rpm_filename(filename: &str) -> RpmFileInfo {
         None => {
             return RpmFileInfo {
                 name: None,
+                epoch: None,
                 version: None,
                 release: None,
                 arch: None,
@@ -487,14 +1559,127 @@ fn parse_rpm_filename(filename: &str) -> RpmFileInfo {
         None => (Some(before_release.to_string()), None),
     };
 
+    // The epoch is never part of the filename
     RpmFileInfo {
         name,
+        epoch: None,
         version,
         release,
         arch,
     }
 }
 
+/// Validate an uploaded package: its structure, its agreement with the filename, and
+/// the configured signing policy.
+fn validate_package(path: &str, data: &[u8], config: &RpmConfig) -> Result<(), String> {
+    if data.is_empty() {
+        return Err("RPM package cannot be empty".to_string());
+    }
+
+    if path.is_empty() {
+        return Err("Artifact path cannot be empty".to_string());
+    }
+
+    // Verify .rpm extension
+    if !path.to_lowercase().ends_with(".rpm") {
+        return Err(format!(
+            "Expected .rpm extension, got: {}",
+            path.rsplit('/').next().unwrap_or(path)
+        ));
+    }
+
+    // RPM lead is 96 bytes minimum
+    if data.len() < RPM_LEAD_SIZE {
+        return Err(format!(
+            "File too small for RPM lead: {} bytes (minimum {})",
+            data.len(),
+            RPM_LEAD_SIZE
+        ));
+    }
+
+    // Verify RPM magic bytes
+    if data[..4] != RPM_MAGIC {
+        return Err(format!(
+            "Invalid RPM magic: expected [ed, ab, ee, db], got [{:02x}, {:02x}, {:02x}, {:02x}]",
+            data[0], data[1], data[2], data[3]
+        ));
+    }
+
+    // Check the lead, headers, and payload in depth
+    header::validate_rpm(data)?;
+
+    // Verify the headers parse and agree with the filename
+    let info = read_header_info(path, data)?;
+
+    check_signature_policy(data, &info, config)
+}
+
+/// Enforce the configured signing policy: unsigned packages are rejected when the
+/// repository requires signatures, and signatures must verify against a trusted key
+/// when `RPM_TRUSTED_KEYS` is set.
+fn check_signature_policy(
+    data: &[u8],
+    info: &RpmHeaderInfo,
+    config: &RpmConfig,
+) -> Result<(), String> {
+    if info.signature.is_none() {
+        if config.requires_signature() {
+            return Err(
+                "RPM package is not signed, and this repository requires signed packages"
+                    .to_string(),
+            );
+        }
+        return Ok(());
+    }
+    let Some(trusted) = &config.trusted_keys else {
+        return Ok(());
+    };
+
+    let keys = openpgp::parse_public_keys(trusted)
+        .map_err(|e| format!("Invalid RPM_TRUSTED_KEYS: {e}"))?;
+    let rpm = header::parse_rpm(data)?;
+    let (packet, signed) = header::signed_data(&rpm).ok_or(
+        "RPM package has only a header signature, which doesn't cover its payload without a \
+         PAYLOADDIGEST",
+    )?;
+    let sig = openpgp::parse_signature(packet)?;
+    let algorithm = sig.algorithm();
+
+    // Key IDs only pick the candidates; the signature itself decides
+    let mut result = Err(format!(
+        "RPM package is signed with key {} ({algorithm}), which is not in RPM_TRUSTED_KEYS",
+        sig.key_id
+    ));
+    for key in keys.iter().filter(|k| k.key_id == sig.key_id) {
+        result = key.verify(packet, &signed).map_err(|e| {
+            format!(
+                "RPM package signature by key {} ({algorithm}) failed verification: {e}",
+                sig.key_id
+            )
+        });
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
+/// Parse the RPM header and check that the artifact filename agrees with it.
+fn read_header_info(path: &str, data: &[u8]) -> Result<RpmHeaderInfo, String> {
+    let rpm = header::parse_rpm(data)?;
+    let info = RpmHeaderInfo::from_rpm(&rpm)?;
+
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    let expected = info.canonical_filename();
+    if filename != expected {
+        return Err(format!(
+            "Filename '{filename}' does not match RPM header: expected '{expected}'"
+        ));
+    }
+
+    Ok(info)
+}
+
 /// Extract the version string from an RPM filename in a path.
 fn extract_version_from_rpm_filename(path: &str) -> Option<String> {
     let filename = path.rsplit('/').next()?;
@@ -513,8 +1698,13 @@ fn extract_version_from_rpm_filename(path: &str) -> Option<String> {
 
 #[cfg(test)]
 mod tests {
+    use super::header::testing::RpmBuilder;
     use super::*;
 
+    fn nginx_rpm() -> Vec<u8> {
+        RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64").build()
+    }
+
     // -- format_key --
 
     #[test]
@@ -579,15 +1769,55 @@ mod tests {
 
     #[test]
     fn parse_metadata_detects_rpm_magic() {
-        let mut data = vec![0; RPM_LEAD_SIZE];
-        data[..4].copy_from_slice(&RPM_MAGIC);
-        let result =
-            RpmFormatHandler::parse_metadata("Packages/nginx-1.24.0-1.el9.x86_64.rpm".into(), data);
+        let result = RpmFormatHandler::parse_metadata(
+            "Packages/nginx-1.24.0-1.el9.x86_64.rpm".into(),
+            nginx_rpm(),
+        );
         let meta = result.unwrap();
         assert_eq!(meta.content_type, "application/x-rpm");
         assert_eq!(meta.version, Some("1.24.0-1.el9".to_string()));
     }
 
+    #[test]
+    fn parse_metadata_version_comes_from_header() {
+        let data = RpmBuilder::new("nginx", "1.26.1", "3.el9", "x86_64").build();
+        let meta =
+            RpmFormatHandler::parse_metadata("Packages/nginx-1.26.1-3.el9.x86_64.rpm".into(), data)
+                .unwrap();
+        assert_eq!(meta.version, Some("1.26.1-3.el9".to_string()));
+    }
+
+    #[test]
+    fn parse_metadata_version_includes_nonzero_epoch() {
+        use super::header::testing::Value;
+        use super::header::RPMTAG_EPOCH;
+
+        let data = RpmBuilder::new("openssl", "3.0.7", "27.el9", "x86_64")
+            .header_tag(RPMTAG_EPOCH, Value::Int32(vec![1]))
+            .build();
+        let meta = RpmFormatHandler::parse_metadata("openssl-3.0.7-27.el9.x86_64.rpm".into(), data)
+            .unwrap();
+        assert_eq!(meta.version, Some("1:3.0.7-27.el9".to_string()));
+
+        let data = RpmBuilder::new("openssl", "3.0.7", "27.el9", "x86_64")
+            .header_tag(RPMTAG_EPOCH, Value::Int32(vec![0]))
+            .build();
+        let meta = RpmFormatHandler::parse_metadata("openssl-3.0.7-27.el9.x86_64.rpm".into(), data)
+            .unwrap();
+        assert_eq!(meta.version, Some("3.0.7-27.el9".to_string()));
+    }
+
+    #[test]
+    fn parse_metadata_rejects_renamed_file() {
+        let result = RpmFormatHandler::parse_metadata(
+            "Packages/nginx-9.9.9-1.el9.x86_64.rpm".into(),
+            nginx_rpm(),
+        );
+        let err = result.unwrap_err();
+        assert!(err.contains("does not match RPM header"));
+        assert!(err.contains("nginx-1.24.0-1.el9.x86_64.rpm"));
+    }
+
     #[test]
     fn parse_metadata_non_rpm_content() {
         let data = vec![0x50, 0x4b, 0x03, 0x04]; // ZIP magic
@@ -607,10 +1837,34 @@ mod tests {
 
     #[test]
     fn validate_accepts_valid_rpm() {
+        let result =
+            RpmFormatHandler::validate("nginx-1.24.0-1.el9.x86_64.rpm".into(), nginx_rpm());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_lead_without_header() {
         let mut data = vec![0; RPM_LEAD_SIZE];
         data[..4].copy_from_slice(&RPM_MAGIC);
+        data[4] = 3; // major
+        data[79] = 5; // signature type
         let result = RpmFormatHandler::validate("test.rpm".into(), data);
-        assert!(result.is_ok());
+        assert!(result.unwrap_err().contains("signature header"));
+    }
+
+    #[test]
+    fn validate_rejects_truncated_upload() {
+        let mut data = nginx_rpm();
+        data.truncate(data.len() - 1);
+        let result = RpmFormatHandler::validate("nginx-1.24.0-1.el9.x86_64.rpm".into(), data);
+        assert!(result.unwrap_err().contains("Truncated or corrupted RPM"));
+    }
+
+    #[test]
+    fn validate_rejects_filename_header_mismatch() {
+        let result =
+            RpmFormatHandler::validate("nginx-1.24.0-1.el9.aarch64.rpm".into(), nginx_rpm());
+        assert!(result.unwrap_err().contains("does not match RPM header"));
     }
 
     #[test]
@@ -678,8 +1932,9 @@ mod tests {
         let result = RpmFormatHandler::generate_index(artifacts)
             .unwrap()
             .unwrap();
-        assert_eq!(result.len(), 1);
+        assert_eq!(result.len(), 2);
         assert_eq!(result[0].0, "rpm-index.json");
+        assert_eq!(result[1].0, "repoclosure.json");
 
         let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
         assert_eq!(json["format"], "rpm-custom");
@@ -747,6 +2002,102 @@ mod tests {
         assert!(body.contains("other.xml.gz"));
     }
 
+    #[test]
+    fn repomd_xml_checksums_match_served_files() {
+        let resp = RpmFormatHandler::handle_request(
+            get_request("/repodata/repomd.xml"),
+            test_context(),
+            test_artifacts(),
+        )
+        .unwrap();
+        let repomd = String::from_utf8(resp.body).unwrap();
+
+        for (data_type, path) in [
+            ("primary", "/repodata/primary.xml.gz"),
+            ("filelists", "/repodata/filelists.xml.gz"),
+            ("other", "/repodata/other.xml.gz"),
+        ] {
+            let served = RpmFormatHandler::handle_request(
+                get_request(path),
+                test_context(),
+                test_artifacts(),
+            )
+            .unwrap()
+            .body;
+            let section = repomd
+                .split(&format!("<data type=\"{data_type}\">"))
+                .nth(1)
+                .unwrap();
+            let section = &section[..section.find("</data>").unwrap()];
+            assert!(section.contains(&format!(
+                "<checksum type=\"sha256\">{}</checksum>",
+                sha256_hex(&served)
+            )));
+            assert!(section.contains(&format!("<size>{}</size>", served.len())));
+            assert!(section.contains("<open-checksum type=\"sha256\">"));
+            assert!(section.contains("<open-size>"));
+            assert!(section.contains("<timestamp>"));
+        }
+    }
+
+    fn packages_repo(packages: &[RpmArtifact]) -> Repo<'_> {
+        Repo {
+            packages,
+            documents: &[],
+        }
+    }
+
+    #[test]
+    fn repomd_revision_tracks_artifact_list() {
+        let config = RpmConfig::default();
+        let artifacts: Vec<RpmArtifact> = test_artifacts()
+            .into_iter()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+        let mut reversed: Vec<RpmArtifact> = test_artifacts()
+            .into_iter()
+            .rev()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+
+        let revision = repo_revision(&packages_repo(&artifacts));
+        assert_eq!(revision, repo_revision(&packages_repo(&reversed)));
+        assert!(repomd_xml(&packages_repo(&artifacts), &config)
+            .unwrap()
+            .contains(&format!("<revision>{revision}</revision>")));
+
+        reversed.pop();
+        assert_ne!(revision, repo_revision(&packages_repo(&reversed)));
+    }
+
+    #[test]
+    fn repomd_timestamp_is_newest_build_time() {
+        use super::header::testing::Value;
+        use super::header::RPMTAG_BUILDTIME;
+
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .header_tag(RPMTAG_BUILDTIME, Value::Int32(vec![1_700_000_000]))
+            .build();
+        let artifacts = vec![
+            RpmArtifact::new(with_properties(test_artifacts().remove(0), &data)),
+            RpmArtifact::from_metadata(test_artifacts().remove(1)),
+        ];
+        assert_eq!(
+            repo_timestamp(&artifacts, &RpmConfig::default()),
+            1_700_000_000
+        );
+        let xml = repomd_xml(&packages_repo(&artifacts), &RpmConfig::default()).unwrap();
+        assert!(xml.contains("<timestamp>1700000000</timestamp>"));
+    }
+
+    #[test]
+    fn sha256_known_value() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn handle_request_primary_xml_gz() {
         let resp = RpmFormatHandler::handle_request(
@@ -838,30 +2189,1306 @@ mod tests {
         assert_eq!(resp.status, 405);
     }
 
-    // -- gzip helpers --
+    // -- artifact properties --
+
+    fn with_properties(metadata: Metadata, data: &[u8]) -> Artifact {
+        let properties =
+            RpmFormatHandler::extract_properties(metadata.path.clone(), data.to_vec()).unwrap();
+        Artifact {
+            metadata,
+            properties,
+        }
+    }
+
+    #[test]
+    fn extract_properties_stores_header() {
+        let props = RpmFormatHandler::extract_properties(
+            "nginx-1.24.0-1.el9.x86_64.rpm".into(),
+            nginx_rpm(),
+        )
+        .unwrap();
+        assert_eq!(props.len(), 1);
+        assert_eq!(props[0].0, HEADER_PROPERTY);
+        assert!(props[0].1.contains("\"summary\":\"The nginx package\""));
+    }
+
+    #[test]
+    fn extract_properties_rejects_renamed_file() {
+        let result =
+            RpmFormatHandler::extract_properties("nginx-2.0-1.x86_64.rpm".into(), nginx_rpm());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn primary_xml_uses_header_fields() {
+        // The stored path disagrees with the header; the header wins.
+        let mut metadata = test_artifacts().remove(0);
+        let artifact = with_properties(metadata.clone(), &nginx_rpm());
+        metadata.path = "renamed.rpm".into();
+        let artifacts = vec![
+            RpmArtifact::new(Artifact {
+                metadata,
+                properties: artifact.properties,
+            }),
+            RpmArtifact::from_metadata(test_artifacts().remove(1)),
+        ];
+
+        let xml = primary_xml(&artifacts);
+        assert!(xml.contains("<name>nginx</name>"));
+        assert!(xml.contains("ver=\"1.24.0\" rel=\"1.el9\""));
+        assert!(xml.contains("<summary>The nginx package</summary>"));
+        assert!(xml.contains("<url>https://example.com</url>"));
+        assert!(xml.contains("<rpm:license>MIT</rpm:license>"));
+        assert!(xml.contains("Artifact Keeper &lt;team@example.com&gt;"));
+        // bash has no stored header and falls back to its filename
+        assert!(xml.contains("<name>bash</name>"));
+    }
+
+    #[test]
+    fn filelists_xml_lists_header_files() {
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .files(&[
+                ("/etc/nginx", 0o040755, 0),
+                ("/usr/sbin/nginx", 0o100755, 0),
+                ("/var/log/nginx/error.log", 0o100644, 1 << 6),
+            ])
+            .build();
+        let artifacts = vec![
+            RpmArtifact::new(with_properties(test_artifacts().remove(0), &data)),
+            RpmArtifact::from_metadata(test_artifacts().remove(1)),
+        ];
+
+        let xml = filelists_xml(&artifacts);
+        assert!(xml.contains("packages=\"2\""));
+        assert!(xml.contains("<package pkgid=\"abc123def456\" name=\"nginx\" arch=\"x86_64\">"));
+        assert!(xml.contains("<file type=\"dir\">/etc/nginx</file>"));
+        assert!(xml.contains("<file>/usr/sbin/nginx</file>"));
+        assert!(xml.contains("<file type=\"ghost\">/var/log/nginx/error.log</file>"));
+        assert!(xml.contains("name=\"bash\""));
+
+        // primary.xml carries only the createrepo subset of files
+        let primary = primary_xml(&artifacts);
+        assert!(primary.contains("<file>/usr/sbin/nginx</file>"));
+        assert!(primary.contains("<file type=\"dir\">/etc/nginx</file>"));
+        assert!(!primary.contains("error.log"));
+    }
+
+    #[test]
+    fn other_xml_lists_newest_changelogs() {
+        use super::header::testing::Value;
+        use super::header::{RPMTAG_CHANGELOGNAME, RPMTAG_CHANGELOGTEXT, RPMTAG_CHANGELOGTIME};
+
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .header_tag(RPMTAG_CHANGELOGTIME, Value::Int32(vec![300, 200, 100]))
+            .header_tag(
+                RPMTAG_CHANGELOGNAME,
+                Value::StrArray(vec!["C <c@x>".into(), "B <b@x>".into(), "A <a@x>".into()]),
+            )
+            .header_tag(
+                RPMTAG_CHANGELOGTEXT,
+                Value::StrArray(vec!["- third".into(), "- second".into(), "- first".into()]),
+            )
+            .build();
+        let artifacts = vec![RpmArtifact::new(with_properties(
+            test_artifacts().remove(0),
+            &data,
+        ))];
+
+        let xml = other_xml(&artifacts, 2);
+        assert!(xml.contains("<package pkgid=\"abc123def456\" name=\"nginx\""));
+        assert!(!xml.contains("- first"));
+        let second = xml.find("- second").unwrap();
+        let third = xml.find("- third").unwrap();
+        assert!(second < third);
+        assert!(
+            xml.contains("<changelog author=\"C &lt;c@x&gt;\" date=\"300\">- third</changelog>")
+        );
+
+        let all = other_xml(&artifacts, 10);
+        assert_eq!(all.matches("<changelog ").count(), 3);
+    }
+
+    #[test]
+    fn primary_xml_dependency_entries() {
+        use super::header::{
+            RPMTAG_OBSOLETEFLAGS, RPMTAG_OBSOLETENAME, RPMTAG_OBSOLETEVERSION, RPMTAG_PROVIDEFLAGS,
+            RPMTAG_PROVIDENAME, RPMTAG_PROVIDEVERSION, RPMTAG_RECOMMENDFLAGS, RPMTAG_RECOMMENDNAME,
+            RPMTAG_RECOMMENDVERSION, RPMTAG_REQUIREFLAGS, RPMTAG_REQUIRENAME,
+            RPMTAG_REQUIREVERSION,
+        };
+
+        let data = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .dependencies(
+                [
+                    RPMTAG_PROVIDENAME,
+                    RPMTAG_PROVIDEFLAGS,
+                    RPMTAG_PROVIDEVERSION,
+                ],
+                &[("nginx", 8, "1:1.24.0-1.el9"), ("webserver", 0, "")],
+            )
+            .dependencies(
+                [
+                    RPMTAG_REQUIRENAME,
+                    RPMTAG_REQUIREFLAGS,
+                    RPMTAG_REQUIREVERSION,
+                ],
+                &[
+                    ("openssl-libs", 12, "1:3.0.7"),
+                    ("glibc", 2, "2.40"),
+                    ("/bin/sh", 512, ""),
+                    ("systemd", 1024 | 8 | 4, "250"),
+                    ("rpmlib(CompressedFileNames)", (1 << 24) | 10, "3.0.4-1"),
+                ],
+            )
+            .dependencies(
+                [
+                    RPMTAG_OBSOLETENAME,
+                    RPMTAG_OBSOLETEFLAGS,
+                    RPMTAG_OBSOLETEVERSION,
+                ],
+                &[("nginx-legacy", 2 | 8, "1.0")],
+            )
+            .dependencies(
+                [
+                    RPMTAG_RECOMMENDNAME,
+                    RPMTAG_RECOMMENDFLAGS,
+                    RPMTAG_RECOMMENDVERSION,
+                ],
+                &[("logrotate", 4, "3.0")],
+            )
+            .build();
+        let artifacts = vec![RpmArtifact::new(with_properties(
+            test_artifacts().remove(0),
+            &data,
+        ))];
+
+        let xml = primary_xml(&artifacts);
+        assert!(xml.contains(
+            "<rpm:entry name=\"nginx\" flags=\"EQ\" epoch=\"1\" ver=\"1.24.0\" rel=\"1.el9\"/>"
+        ));
+        assert!(xml.contains("<rpm:entry name=\"webserver\"/>"));
+        assert!(xml
+            .contains("<rpm:entry name=\"openssl-libs\" flags=\"GE\" epoch=\"1\" ver=\"3.0.7\"/>"));
+        assert!(xml.contains("<rpm:entry name=\"glibc\" flags=\"LT\" epoch=\"0\" ver=\"2.40\"/>"));
+        assert!(xml.contains("<rpm:entry name=\"/bin/sh\" pre=\"1\"/>"));
+        assert!(xml.contains(
+            "<rpm:entry name=\"systemd\" flags=\"GE\" epoch=\"0\" ver=\"250\" pre=\"1\"/>"
+        ));
+        assert!(!xml.contains("rpmlib("));
+        assert!(xml.contains("<rpm:obsoletes>"));
+        assert!(xml.contains("name=\"nginx-legacy\" flags=\"LE\""));
+        assert!(xml.contains("<rpm:recommends>"));
+        assert!(xml.contains("name=\"logrotate\" flags=\"GT\""));
+        assert!(!xml.contains("<rpm:conflicts>"));
+    }
+
+    #[test]
+    fn epoch_is_carried_into_repodata_and_index() {
+        use super::header::testing::Value;
+        use super::header::RPMTAG_EPOCH;
+
+        let data = RpmBuilder::new("openssl", "3.0.7", "27.el9", "x86_64")
+            .header_tag(RPMTAG_EPOCH, Value::Int32(vec![1]))
+            .build();
+        let metadata = Metadata {
+            path: "openssl-3.0.7-27.el9.x86_64.rpm".into(),
+            version: Some("1:3.0.7-27.el9".into()),
+            content_type: "application/x-rpm".into(),
+            size_bytes: 1024,
+            checksum_sha256: Some("0123".into()),
+        };
+        let artifacts = vec![RpmArtifact::new(with_properties(metadata.clone(), &data))];
+        let expected = "<version epoch=\"1\" ver=\"3.0.7\" rel=\"27.el9\"/>";
+        assert!(primary_xml(&artifacts).contains(expected));
+        assert!(filelists_xml(&artifacts).contains(expected));
+        assert!(other_xml(&artifacts, 10).contains(expected));
+
+        let index = generate_rpm_index(&artifacts).unwrap().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&index[0].1).unwrap();
+        assert_eq!(json["packages"][0]["epoch"], 1);
+        assert_eq!(json["packages"][0]["version"], "1:3.0.7-27.el9");
+
+        // Without stored properties the epoch comes from the stored version string
+        let fallback = vec![RpmArtifact::from_metadata(metadata)];
+        let xml = primary_xml(&fallback);
+        assert!(xml.contains(expected));
+        assert!(xml.contains(
+            "<rpm:entry name=\"openssl\" flags=\"EQ\" epoch=\"1\" ver=\"3.0.7\" rel=\"27.el9\"/>"
+        ));
+    }
+
+    #[test]
+    fn generate_index_lists_newest_first_per_name_arch() {
+        let artifact = |path: &str, version: &str| Metadata {
+            path: path.into(),
+            version: Some(version.into()),
+            content_type: "application/x-rpm".into(),
+            size_bytes: 1,
+            checksum_sha256: None,
+        };
+        let artifacts = vec![
+            artifact("nginx-1.9.0-1.el9.x86_64.rpm", "1.9.0-1.el9"),
+            artifact("bash-5.2.26-1.el9.x86_64.rpm", "5.2.26-1.el9"),
+            artifact("nginx-1.24.0~rc1-1.el9.x86_64.rpm", "1.24.0~rc1-1.el9"),
+            artifact("nginx-1.24.0-1.el9.x86_64.rpm", "1.24.0-1.el9"),
+            artifact("nginx-1.24.0-1.el9.aarch64.rpm", "1.24.0-1.el9"),
+            artifact("nginx-1.10.0-1.el9.x86_64.rpm", "1.10.0-1.el9"),
+        ];
+        let result = RpmFormatHandler::generate_index(artifacts)
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+
+        let paths: Vec<&str> = json["packages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["path"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "nginx-1.24.0-1.el9.x86_64.rpm",
+                "nginx-1.24.0~rc1-1.el9.x86_64.rpm",
+                "nginx-1.10.0-1.el9.x86_64.rpm",
+                "nginx-1.9.0-1.el9.x86_64.rpm",
+                "bash-5.2.26-1.el9.x86_64.rpm",
+                "nginx-1.24.0-1.el9.aarch64.rpm",
+            ]
+        );
+
+        let latest = json["latest"].as_array().unwrap();
+        assert_eq!(latest.len(), 3);
+        assert_eq!(latest[0]["name"], "nginx");
+        assert_eq!(latest[0]["arch"], "x86_64");
+        assert_eq!(latest[0]["version"], "1.24.0-1.el9");
+        assert_eq!(latest[1]["name"], "bash");
+    }
+
+    #[test]
+    fn sort_newest_first_honours_epoch() {
+        let artifact = |path: &str, version: &str| {
+            RpmArtifact::from_metadata(Metadata {
+                path: path.into(),
+                version: Some(version.into()),
+                content_type: "application/x-rpm".into(),
+                size_bytes: 1,
+                checksum_sha256: None,
+            })
+        };
+        let mut artifacts = vec![
+            artifact("openssl-3.0.7-1.el9.x86_64.rpm", "1:3.0.7-1.el9"),
+            artifact("openssl-3.2.0-1.el9.x86_64.rpm", "3.2.0-1.el9"),
+        ];
+        sort_newest_first(&mut artifacts);
+        assert_eq!(artifacts[0].metadata.path, "openssl-3.0.7-1.el9.x86_64.rpm");
+    }
+
+    #[test]
+    fn format_evr_omits_zero_epoch() {
+        assert_eq!(format_evr(Some(2), "1.0", "1"), "2:1.0-1");
+        assert_eq!(format_evr(Some(0), "1.0", "1"), "1.0-1");
+        assert_eq!(format_evr(None, "1.0", "1"), "1.0-1");
+    }
+
+    #[test]
+    fn split_evr_components() {
+        assert_eq!(
+            split_evr("1:3.0.7-2.el9"),
+            (Some("1"), "3.0.7", Some("2.el9"))
+        );
+        assert_eq!(split_evr("2.40"), (None, "2.40", None));
+        assert_eq!(split_evr("1.0-1"), (None, "1.0", Some("1")));
+        assert_eq!(split_evr("x:1.0"), (None, "x:1.0", None));
+    }
+
+    #[test]
+    fn handle_request_with_properties_serves_repodata() {
+        let artifacts = vec![with_properties(test_artifacts().remove(0), &nginx_rpm())];
+        let resp = RpmFormatHandler::handle_request_with_properties(
+            get_request("/repodata/primary.xml.gz"),
+            test_context(),
+            artifacts,
+        )
+        .unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.body[0], 0x1f);
+    }
+
+    #[test]
+    fn generate_index_with_properties_includes_summary() {
+        let artifacts = vec![with_properties(test_artifacts().remove(0), &nginx_rpm())];
+        let result = RpmFormatHandler::generate_index_with_properties(artifacts)
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+        assert_eq!(json["packages"][0]["summary"], "The nginx package");
+        assert_eq!(json["packages"][0]["license"], "MIT");
+    }
+
+    // -- signatures --
+
+    const RELEASE_KEY_ID: [u8; 8] = [0x19, 0x9e, 0x27, 0x40, 0x05, 0x00, 0x2e, 0x8f];
+
+    fn signed_nginx_rpm(key_id: [u8; 8]) -> Vec<u8> {
+        use super::header::testing::{HeaderBuilder, Value};
+        use super::header::RPMSIGTAG_RSA;
+
+        let mut builder = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64");
+        builder.signature = HeaderBuilder::new().tag(
+            RPMSIGTAG_RSA,
+            Value::Bin(openpgp::testing::v4_signature(key_id, 1, 8)),
+        );
+        builder.build()
+    }
+
+    fn key_policy(require: bool, trusted: &str) -> RpmConfig {
+        RpmConfig::from_lookup(|k| match k {
+            "RPM_REQUIRE_SIGNATURE" => Some(require.to_string()),
+            "RPM_TRUSTED_KEYS" => Some(trusted.to_string()),
+            _ => None,
+        })
+    }
+
+    const TEST_PUBLIC_KEY: &str = include_str!("../testdata/signing-key.pub.asc");
+    const TEST_KEY_ID: [u8; 8] = [0xa5, 0x93, 0x22, 0x0e, 0xb8, 0x73, 0xfe, 0x95];
+
+    /// `builder`'s package with an RSAHEADER-tag signature by the test Ed25519 key over
+    /// the main header of `signed`, as `rpmsign` writes it.
+    fn sign_rpm(mut builder: RpmBuilder, signed: &[u8]) -> Vec<u8> {
+        use super::header::testing::{HeaderBuilder, Value};
+        use super::header::RPMSIGTAG_RSA;
+
+        let key = SigningKey::from_armored(TEST_SIGNING_KEY).unwrap();
+        let rpm = header::parse_rpm(signed).unwrap();
+        let packet =
+            openpgp::testing::signature_packet(&key.sign_detached(rpm.header_bytes, 1_800_000_000));
+        builder.signature = HeaderBuilder::new().tag(RPMSIGTAG_RSA, Value::Bin(packet));
+        builder.build()
+    }
+
+    /// `builder`'s package signed the way older rpm releases did: an RSAHEADER signature
+    /// over the main header of `signed`, and a GPG signature over its header and payload.
+    fn sign_rpm_legacy(mut builder: RpmBuilder, signed: &[u8]) -> Vec<u8> {
+        use super::header::testing::{HeaderBuilder, Value};
+        use super::header::{RPMSIGTAG_GPG, RPMSIGTAG_RSA};
+
+        let key = SigningKey::from_armored(TEST_SIGNING_KEY).unwrap();
+        let rpm = header::parse_rpm(signed).unwrap();
+        let sign = |data: &[u8]| {
+            openpgp::testing::signature_packet(&key.sign_detached(data, 1_800_000_000))
+        };
+        builder.signature = HeaderBuilder::new()
+            .tag(RPMSIGTAG_RSA, Value::Bin(sign(rpm.header_bytes)))
+            .tag(
+                RPMSIGTAG_GPG,
+                Value::Bin(sign(&[rpm.header_bytes, rpm.payload].concat())),
+            );
+        builder.build()
+    }
+
+    #[test]
+    fn validate_accepts_unsigned_without_policy() {
+        let config = key_policy(false, "");
+        assert!(validate_package("nginx-1.24.0-1.el9.x86_64.rpm", &nginx_rpm(), &config).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_unsigned_when_required() {
+        let config = key_policy(true, "");
+        let err =
+            validate_package("nginx-1.24.0-1.el9.x86_64.rpm", &nginx_rpm(), &config).unwrap_err();
+        assert!(err.contains("not signed"), "{err}");
+
+        let signed = signed_nginx_rpm(RELEASE_KEY_ID);
+        assert!(validate_package("nginx-1.24.0-1.el9.x86_64.rpm", &signed, &config).is_ok());
+    }
+
+    #[test]
+    fn validate_verifies_trusted_keys() {
+        let config = key_policy(false, TEST_PUBLIC_KEY);
+        let path = "nginx-1.24.0-1.el9.x86_64.rpm";
+        let nginx = || RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64");
+
+        let signed = sign_rpm(nginx(), &nginx().build());
+        assert!(validate_package(path, &signed, &config).is_ok());
+
+        let err = validate_package(path, &signed_nginx_rpm(RELEASE_KEY_ID), &config).unwrap_err();
+        assert!(err.contains("199e274005002e8f (RSA/SHA256)"), "{err}");
+        assert!(err.contains("not in RPM_TRUSTED_KEYS"), "{err}");
+
+        // A signature packet that merely names the trusted key
+        let err = validate_package(path, &signed_nginx_rpm(TEST_KEY_ID), &config).unwrap_err();
+        assert!(err.contains("failed verification"), "{err}");
+
+        // A genuine signature over a different header
+        let other = nginx().header_tag(
+            header::RPMTAG_SOURCERPM,
+            header::testing::Value::Str("evil-1-1.src.rpm".into()),
+        );
+        let forged = sign_rpm(other, &nginx().build());
+        let err = validate_package(path, &forged, &config).unwrap_err();
+        assert!(err.contains("failed verification"), "{err}");
+
+        // Trusted keys imply signatures are required
+        let err = validate_package(path, &nginx_rpm(), &config).unwrap_err();
+        assert!(err.contains("not signed"), "{err}");
+
+        let config = key_policy(false, "not a key");
+        let err = validate_package(path, &signed, &config).unwrap_err();
+        assert!(err.contains("Invalid RPM_TRUSTED_KEYS"), "{err}");
+    }
+
+    #[test]
+    fn validate_checks_payload_of_packages_without_payload_digest() {
+        let config = key_policy(false, TEST_PUBLIC_KEY);
+        let path = "nginx-1.24.0-1.el9.x86_64.rpm";
+        let legacy = || {
+            let mut builder = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64");
+            builder.digests = false;
+            builder
+        };
+
+        // A header-only signature says nothing about the payload
+        let header_only = sign_rpm(legacy(), &legacy().build());
+        let err = validate_package(path, &header_only, &config).unwrap_err();
+        assert!(err.contains("doesn't cover its payload"), "{err}");
+
+        // So the header-and-payload signature is checked instead
+        let signed = sign_rpm_legacy(legacy(), &legacy().build());
+        assert!(validate_package(path, &signed, &config).is_ok());
+
+        let mut swapped = legacy();
+        swapped.payload = [&[0x1f, 0x8b, 0x08, 0x00][..], b"trojan"].concat();
+        let tampered = sign_rpm_legacy(swapped, &legacy().build());
+        let err = validate_package(path, &tampered, &config).unwrap_err();
+        assert!(err.contains("failed verification"), "{err}");
+    }
+
+    #[test]
+    fn signature_is_exposed_in_properties_and_index() {
+        let data = signed_nginx_rpm(RELEASE_KEY_ID);
+        let artifact = with_properties(test_artifacts().remove(0), &data);
+        assert!(artifact
+            .properties
+            .contains(&(SIGNATURE_KEY_ID_PROPERTY.into(), "199e274005002e8f".into())));
+        assert!(artifact
+            .properties
+            .contains(&(SIGNATURE_ALGORITHM_PROPERTY.into(), "RSA/SHA256".into())));
+
+        let result = RpmFormatHandler::generate_index_with_properties(vec![artifact])
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+        assert_eq!(
+            json["packages"][0]["signature"]["key_id"],
+            "199e274005002e8f"
+        );
+        assert_eq!(json["packages"][0]["signature"]["algorithm"], "RSA/SHA256");
+    }
+
+    // -- advisories --
+
+    const NGINX_ADVISORY: &str = r#"{
+        "id": "EXAMPLE-2026:0042",
+        "type": "security",
+        "severity": "Moderate",
+        "title": "nginx security update",
+        "description": "Fixes a request smuggling flaw.",
+        "issued": "2026-03-01 12:00:00",
+        "references": [{ "type": "cve", "id": "CVE-2026-4242" }],
+        "packages": ["nginx-1.24.0-1.el9.x86_64", "nginx-1.24.0-1.el9.aarch64"]
+    }"#;
+
+    fn advisory_metadata() -> Metadata {
+        Metadata {
+            path: "errata/EXAMPLE-2026-0042.advisory.json".into(),
+            version: None,
+            content_type: "application/json".into(),
+            size_bytes: NGINX_ADVISORY.len() as u64,
+            checksum_sha256: Some("f00d".into()),
+        }
+    }
+
+    fn nginx_with_advisory() -> Vec<Artifact> {
+        vec![
+            with_properties(test_artifacts().remove(0), &nginx_rpm()),
+            with_properties(advisory_metadata(), NGINX_ADVISORY.as_bytes()),
+        ]
+    }
+
+    fn gunzip(data: &[u8]) -> String {
+        let xml = miniz_oxide::inflate::decompress_to_vec(&data[10..data.len() - 8]).unwrap();
+        String::from_utf8(xml).unwrap()
+    }
+
+    #[test]
+    fn validate_accepts_advisory() {
+        let path = advisory_metadata().path;
+        assert!(RpmFormatHandler::validate(path.clone(), NGINX_ADVISORY.into()).is_ok());
+        let err = RpmFormatHandler::validate(path, b"{}".to_vec()).unwrap_err();
+        assert!(err.contains("Invalid advisory"), "{err}");
+    }
+
+    #[test]
+    fn parse_metadata_for_advisory() {
+        let metadata =
+            RpmFormatHandler::parse_metadata(advisory_metadata().path, NGINX_ADVISORY.into())
+                .unwrap();
+        assert_eq!(metadata.content_type, "application/json");
+        assert_eq!(metadata.version, None);
+    }
+
+    #[test]
+    fn updateinfo_lists_advisory_packages() {
+        let request = |path: &str| {
+            RpmFormatHandler::handle_request_with_properties(
+                get_request(path),
+                test_context(),
+                nginx_with_advisory(),
+            )
+            .unwrap()
+        };
+
+        let repomd = String::from_utf8(request("/repodata/repomd.xml").body).unwrap();
+        assert!(repomd.contains("<data type=\"updateinfo\">"));
+        assert!(repomd.contains("<location href=\"repodata/updateinfo.xml.gz\"/>"));
+        // The advisory isn't a package
+        assert!(!repomd.contains("advisory.json"));
+        assert!(gunzip(&request("/repodata/primary.xml.gz").body).contains("packages=\"1\""));
+
+        let served = request("/repodata/updateinfo.xml.gz").body;
+        assert!(repomd.contains(&sha256_hex(&served)));
+        let xml = gunzip(&served);
+        assert!(xml.contains("<update status=\"final\" type=\"security\">"));
+        assert!(xml.contains("<id>EXAMPLE-2026:0042</id>"));
+        assert!(xml.contains("<severity>Moderate</severity>"));
+        assert!(xml.contains("<issued date=\"2026-03-01 12:00:00\"/>"));
+        assert!(xml.contains(
+            "<reference href=\"https://www.cve.org/CVERecord?id=CVE-2026-4242\" \
+             id=\"CVE-2026-4242\" type=\"cve\" title=\"CVE-2026-4242\"/>"
+        ));
+        // Linked to the repository's package, with its checksum
+        assert!(xml.contains(
+            "<package name=\"nginx\" epoch=\"0\" version=\"1.24.0\" release=\"1.el9\" arch=\"x86_64\">\n          \
+             <filename>nginx-1.24.0-1.el9.x86_64.rpm</filename>\n          \
+             <sum type=\"sha256\">abc123def456</sum>"
+        ));
+        // Listed by NEVRA when the repository doesn't carry it
+        assert!(xml.contains(
+            "<package name=\"nginx\" epoch=\"0\" version=\"1.24.0\" release=\"1.el9\" arch=\"aarch64\">\n          \
+             <filename>nginx-1.24.0-1.el9.aarch64.rpm</filename>\n        </package>"
+        ));
+    }
+
+    #[test]
+    fn updateinfo_omitted_without_advisories() {
+        let config = RpmConfig::default();
+        let repomd = String::from_utf8(route("/repodata/repomd.xml", &config).body).unwrap();
+        assert!(!repomd.contains("updateinfo"));
+        assert_eq!(route("/repodata/updateinfo.xml.gz", &config).status, 404);
+    }
+
+    #[test]
+    fn generate_index_lists_advisories_separately() {
+        let result = RpmFormatHandler::generate_index_with_properties(nginx_with_advisory())
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+        assert_eq!(json["packages"].as_array().unwrap().len(), 1);
+        assert_eq!(json["latest"].as_array().unwrap().len(), 1);
+        assert_eq!(json["advisories"][0]["id"], "EXAMPLE-2026:0042");
+        assert_eq!(json["advisories"][0]["type"], "security");
+        assert_eq!(json["advisories"][0]["severity"], "Moderate");
+    }
+
+    // -- comps --
+
+    const COMPS: &str = "<comps><group><id>web-server</id><name>Web Server</name>\
+                         <packagelist><packagereq type=\"mandatory\">nginx</packagereq>\
+                         </packagelist></group></comps>";
+
+    fn comps_metadata() -> Metadata {
+        Metadata {
+            path: "comps.xml".into(),
+            version: None,
+            content_type: "application/xml".into(),
+            size_bytes: COMPS.len() as u64,
+            checksum_sha256: None,
+        }
+    }
 
     #[test]
-    fn gzip_compress_produces_valid_header() {
-        let result = gzip_compress(b"hello").unwrap();
-        assert_eq!(result[0], 0x1f);
-        assert_eq!(result[1], 0x8b);
-        assert_eq!(result[2], 0x08); // deflate
+    fn validate_checks_comps() {
+        assert!(RpmFormatHandler::validate("comps.xml".into(), COMPS.into()).is_ok());
+        let err = RpmFormatHandler::validate("comps.xml".into(), b"<comps/>".to_vec()).unwrap_err();
+        assert!(err.contains("defines no groups"), "{err}");
+
+        let metadata = RpmFormatHandler::parse_metadata("comps.xml".into(), COMPS.into()).unwrap();
+        assert_eq!(metadata.content_type, "application/xml");
     }
 
     #[test]
-    fn gzip_compress_empty_input() {
-        let result = gzip_compress(b"").unwrap();
-        assert!(result.len() > 10); // header + trailer at minimum
-        assert_eq!(result[0], 0x1f);
-        assert_eq!(result[1], 0x8b);
+    fn repomd_references_comps() {
+        let request = |path: &str| {
+            RpmFormatHandler::handle_request_with_properties(
+                get_request(path),
+                test_context(),
+                vec![
+                    with_properties(test_artifacts().remove(0), &nginx_rpm()),
+                    with_properties(comps_metadata(), COMPS.as_bytes()),
+                ],
+            )
+            .unwrap()
+        };
+        let repomd = String::from_utf8(request("/repodata/repomd.xml").body).unwrap();
+        let section = |data_type: &str| {
+            let section = repomd
+                .split(&format!("<data type=\"{data_type}\">"))
+                .nth(1)
+                .unwrap();
+            section[..section.find("</data>").unwrap()].to_string()
+        };
+
+        let plain = request("/repodata/comps.xml");
+        assert_eq!(plain.status, 200);
+        let xml = String::from_utf8(plain.body.clone()).unwrap();
+        assert!(xml.contains("<id>web-server</id>"));
+        let group = section("group");
+        assert!(group.contains("<location href=\"repodata/comps.xml\"/>"));
+        assert!(group.contains(&sha256_hex(&plain.body)));
+        assert!(!group.contains("open-checksum"));
+
+        let gz = request("/repodata/comps.xml.gz").body;
+        assert_eq!(gunzip(&gz), xml);
+        let group_gz = section("group_gz");
+        assert!(group_gz.contains("<location href=\"repodata/comps.xml.gz\"/>"));
+        assert!(group_gz.contains(&sha256_hex(&gz)));
+        assert!(group_gz.contains(&sha256_hex(xml.as_bytes())));
+
+        // The comps document isn't a package
+        assert!(gunzip(&request("/repodata/primary.xml.gz").body).contains("packages=\"1\""));
     }
 
     #[test]
-    fn crc32_known_value() {
-        // CRC32 of empty string is 0x00000000
-        assert_eq!(crc32(b""), 0x0000_0000);
-        // CRC32 of "123456789" is 0xCBF43926
-        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    fn comps_omitted_when_not_uploaded() {
+        let config = RpmConfig::default();
+        let repomd = String::from_utf8(route("/repodata/repomd.xml", &config).body).unwrap();
+        assert!(!repomd.contains("group"));
+        assert_eq!(route("/repodata/comps.xml", &config).status, 404);
+        assert_eq!(route("/repodata/comps.xml.gz", &config).status, 404);
+    }
+
+    // -- modules --
+
+    const MODULES: &str = "\
+document: modulemd
+version: 2
+data:
+  name: nginx
+  stream: \"1.24\"
+  version: 9040020240101000000
+  context: 9a7b3c1d
+  arch: x86_64
+  summary: nginx webserver
+  description: nginx 1.24 module stream.
+  license:
+    module: [MIT]
+  artifacts:
+    rpms:
+    - nginx-0:1.24.0-1.el9.x86_64
+    - nginx-mod-stream-0:1.24.0-1.el9.x86_64
+";
+
+    fn nginx_with_modules() -> Vec<Artifact> {
+        vec![
+            with_properties(test_artifacts().remove(0), &nginx_rpm()),
+            with_properties(
+                Metadata {
+                    path: "modules/nginx-modules.yaml".into(),
+                    version: None,
+                    content_type: "application/yaml".into(),
+                    size_bytes: MODULES.len() as u64,
+                    checksum_sha256: None,
+                },
+                MODULES.as_bytes(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn validate_checks_modulemd() {
+        let path = "nginx-modules.yaml".to_string();
+        assert!(RpmFormatHandler::validate(path.clone(), MODULES.into()).is_ok());
+        let err = RpmFormatHandler::validate(path, b"document: modulemd\n".to_vec()).unwrap_err();
+        assert!(err.contains("Invalid modulemd document 1"), "{err}");
+    }
+
+    #[test]
+    fn repomd_references_modules() {
+        let request = |path: &str| {
+            RpmFormatHandler::handle_request_with_properties(
+                get_request(path),
+                test_context(),
+                nginx_with_modules(),
+            )
+            .unwrap()
+        };
+        let repomd = String::from_utf8(request("/repodata/repomd.xml").body).unwrap();
+        assert!(repomd.contains("<data type=\"modules\">"));
+        assert!(repomd.contains("<location href=\"repodata/modules.yaml.gz\"/>"));
+
+        let served = request("/repodata/modules.yaml.gz").body;
+        assert!(repomd.contains(&sha256_hex(&served)));
+        let yaml = gunzip(&served);
+        assert!(yaml.starts_with("---\ndocument: modulemd\n"));
+        assert!(yaml.contains("- nginx-0:1.24.0-1.el9.x86_64\n"));
+        assert!(yaml.ends_with("...\n"));
+
+        assert!(gunzip(&request("/repodata/primary.xml.gz").body).contains("packages=\"1\""));
+        assert_eq!(
+            request("/repodata/modules.yaml.zst").headers[0].1,
+            "application/zstd"
+        );
+    }
+
+    #[test]
+    fn modules_omitted_when_not_uploaded() {
+        let config = RpmConfig::default();
+        let repomd = String::from_utf8(route("/repodata/repomd.xml", &config).body).unwrap();
+        assert!(!repomd.contains("modules"));
+        assert_eq!(route("/repodata/modules.yaml.gz", &config).status, 404);
+    }
+
+    #[test]
+    fn generate_index_links_modules_to_packages() {
+        let result = RpmFormatHandler::generate_index_with_properties(nginx_with_modules())
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+        let nsvca = "nginx:1.24:9040020240101000000:9a7b3c1d:x86_64";
+
+        assert_eq!(json["packages"].as_array().unwrap().len(), 1);
+        assert_eq!(json["packages"][0]["modules"], serde_json::json!([nsvca]));
+
+        let module = &json["modules"][0];
+        assert_eq!(module["nsvca"], nsvca);
+        assert_eq!(module["stream"], "1.24");
+        assert_eq!(module["rpms"][0]["path"], "nginx-1.24.0-1.el9.x86_64.rpm");
+        // Listed by the module but not uploaded
+        assert_eq!(
+            module["rpms"][1]["nevra"],
+            "nginx-mod-stream-0:1.24.0-1.el9.x86_64"
+        );
+        assert!(module["rpms"][1]["path"].is_null());
+    }
+
+    // -- repodata compression --
+
+    fn compression_config(name: &str) -> RpmConfig {
+        let name = name.to_string();
+        RpmConfig::from_lookup(move |k| (k == "RPM_REPODATA_COMPRESSION").then(|| name.clone()))
+    }
+
+    #[test]
+    fn repomd_advertises_configured_compression() {
+        let repomd =
+            String::from_utf8(route("/repodata/repomd.xml", &compression_config("zstd")).body)
+                .unwrap();
+        assert!(repomd.contains("<location href=\"repodata/primary.xml.zst\"/>"));
+        assert!(repomd.contains("<location href=\"repodata/filelists.xml.zst\"/>"));
+        assert!(repomd.contains("<location href=\"repodata/other.xml.zst\"/>"));
+        assert!(repomd.contains("<data type=\"primary\">"));
+        assert!(!repomd.contains(".xml.gz"));
+
+        let served = route("/repodata/primary.xml.zst", &compression_config("zstd")).body;
+        assert!(repomd.contains(&format!(
+            "<checksum type=\"sha256\">{}</checksum>",
+            sha256_hex(&served)
+        )));
+    }
+
+    #[test]
+    fn serves_every_compression_format() {
+        let config = RpmConfig::default();
+        for (path, content_type, magic) in [
+            (
+                "/repodata/primary.xml.gz",
+                "application/gzip",
+                &b"\x1f\x8b"[..],
+            ),
+            (
+                "/repodata/filelists.xml.zst",
+                "application/zstd",
+                &b"\x28\xb5\x2f\xfd"[..],
+            ),
+            (
+                "/repodata/other.xml.xz",
+                "application/x-xz",
+                &b"\xfd7zXZ\x00"[..],
+            ),
+            (
+                "/SRPMS/repodata/primary.xml.xz",
+                "application/x-xz",
+                &b"\xfd7zXZ\x00"[..],
+            ),
+        ] {
+            let resp = route(path, &config);
+            assert_eq!(resp.status, 200, "{path}");
+            assert_eq!(resp.headers[0].1, content_type, "{path}");
+            assert!(resp.body.starts_with(magic), "{path}");
+        }
+    }
+
+    #[test]
+    fn unknown_repodata_files_are_not_found() {
+        let config = RpmConfig::default();
+        for path in [
+            "/repodata/primary.xml.bz2",
+            "/repodata/primary.xml",
+            "/repodata/updates.xml.gz",
+        ] {
+            assert_eq!(route(path, &config).status, 404, "{path}");
+        }
+    }
+
+    // -- repomd signing --
+
+    const TEST_SIGNING_KEY: &str = include_str!("../testdata/signing-key.asc");
+
+    fn signing_config() -> RpmConfig {
+        RpmConfig::from_lookup(|k| (k == "RPM_SIGNING_KEY").then(|| TEST_SIGNING_KEY.into()))
+    }
+
+    fn route(path: &str, config: &RpmConfig) -> HttpResponse {
+        let artifacts = test_artifacts()
+            .into_iter()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+        route_request(&get_request(path), &test_context(), artifacts, config).unwrap()
+    }
+
+    #[test]
+    fn repomd_signature_covers_served_repomd() {
+        let config = signing_config();
+        let repomd = route("/repodata/repomd.xml", &config).body;
+
+        let resp = route("/repodata/repomd.xml.asc", &config);
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.headers[0].1, "application/pgp-signature");
+        let armored = String::from_utf8(resp.body).unwrap();
+        assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----"));
+
+        // Deterministic: a second request signs the same repomd.xml identically
+        assert_eq!(
+            route("/repodata/repomd.xml.asc", &config).body,
+            armored.as_bytes()
+        );
+
+        // Without build times to go by, repomd.xml and its signature are dated with the key
+        let key = SigningKey::from_armored(TEST_SIGNING_KEY).unwrap();
+        assert_eq!(armored, key.sign_detached(&repomd, key.created()));
+        let repomd = String::from_utf8(repomd).unwrap();
+        assert!(repomd.contains(&format!("<timestamp>{}</timestamp>", key.created())));
+    }
+
+    #[test]
+    fn repomd_signature_is_not_older_than_key() {
+        let config = signing_config();
+        let key = SigningKey::from_armored(TEST_SIGNING_KEY).unwrap();
+        let artifacts = vec![with_properties(
+            test_artifacts().remove(0),
+            &RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+                .header_tag(
+                    header::RPMTAG_BUILDTIME,
+                    header::testing::Value::Int32(vec![1_000_000_000]),
+                )
+                .build(),
+        )];
+        let resp = route_request(
+            &get_request("/repodata/repomd.xml.asc"),
+            &test_context(),
+            artifacts.into_iter().map(RpmArtifact::new).collect(),
+            &config,
+        )
+        .unwrap();
+        let armored = String::from_utf8(resp.body).unwrap();
+        assert!(openpgp::testing::signature_created(&armored) >= key.created());
+    }
+
+    #[test]
+    fn repodata_without_build_times_is_stable() {
+        let config = mirror_config();
+        assert_eq!(repo_timestamp(&[], &config), 0);
+
+        let fetch = |path: &str, artifacts: Vec<RpmArtifact>| {
+            route_request(&get_request(path), &test_context(), artifacts, &config)
+                .unwrap()
+                .body
+        };
+        let headerless = || {
+            test_artifacts()
+                .into_iter()
+                .map(RpmArtifact::from_metadata)
+                .collect::<Vec<_>>()
+        };
+        for artifacts in [Vec::new, headerless] {
+            let metalink = String::from_utf8(fetch("/metalink", artifacts())).unwrap();
+            let repomd = fetch("/repodata/repomd.xml", artifacts());
+            assert!(metalink.contains(&format!("<size>{}</size>", repomd.len())));
+            assert!(metalink.contains(&sha256_hex(&repomd)));
+            assert_eq!(fetch("/metalink", artifacts()), metalink.as_bytes());
+            assert_eq!(fetch("/repodata/repomd.xml", artifacts()), repomd);
+        }
+    }
+
+    #[test]
+    fn repomd_key_serves_public_key() {
+        let resp = route("/repodata/repomd.xml.key", &signing_config());
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.headers[0].1, "application/pgp-keys");
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+        assert!(!body.contains("PRIVATE"));
+    }
+
+    #[test]
+    fn repomd_signing_routes_404_without_key() {
+        let config = RpmConfig::default();
+        assert_eq!(route("/repodata/repomd.xml.asc", &config).status, 404);
+        assert_eq!(route("/repodata/repomd.xml.key", &config).status, 404);
+    }
+
+    #[test]
+    fn invalid_signing_key_is_an_error() {
+        let config = RpmConfig::from_lookup(|k| (k == "RPM_SIGNING_KEY").then(|| "junk".into()));
+        let artifacts = test_artifacts()
+            .into_iter()
+            .map(RpmArtifact::from_metadata)
+            .collect();
+        let err = route_request(
+            &get_request("/repodata/repomd.xml.asc"),
+            &test_context(),
+            artifacts,
+            &config,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("Invalid RPM_SIGNING_KEY"), "{err}");
+    }
+
+    // -- source packages --
+
+    fn bash_source_artifacts() -> Vec<Artifact> {
+        use super::header::testing::Value;
+        use super::header::RPMTAG_SOURCERPM;
+
+        let binary = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64")
+            .header_tag(
+                RPMTAG_SOURCERPM,
+                Value::Str("bash-5.2.26-1.el9.src.rpm".into()),
+            )
+            .build();
+        let mut source = RpmBuilder::new("bash", "5.2.26", "1.el9", "x86_64");
+        source.package_type = 1;
+
+        let metadata = |path: &str| Metadata {
+            path: path.into(),
+            version: Some("5.2.26-1.el9".into()),
+            content_type: "application/x-rpm".into(),
+            size_bytes: 1024,
+            checksum_sha256: None,
+        };
+        vec![
+            with_properties(metadata("bash-5.2.26-1.el9.x86_64.rpm"), &binary),
+            with_properties(metadata("bash-5.2.26-1.el9.src.rpm"), &source.build()),
+        ]
+    }
+
+    #[test]
+    fn source_packages_are_detected_from_the_lead() {
+        let artifacts: Vec<RpmArtifact> = bash_source_artifacts()
+            .into_iter()
+            .map(RpmArtifact::new)
+            .collect();
+        assert!(!artifacts[0].is_source());
+        assert!(artifacts[1].is_source());
+        assert_eq!(artifacts[1].file_info().arch.as_deref(), Some("src"));
+
+        // Without a stored header, the filename arch decides
+        let fallback = RpmArtifact::from_metadata(bash_source_artifacts().remove(1).metadata);
+        assert!(fallback.is_source());
+    }
+
+    #[test]
+    fn primary_xml_links_binaries_to_source_rpm() {
+        let artifacts: Vec<RpmArtifact> = bash_source_artifacts()
+            .into_iter()
+            .map(RpmArtifact::new)
+            .collect();
+        let xml = primary_xml(&artifacts[..1]);
+        assert!(xml.contains("<rpm:sourcerpm>bash-5.2.26-1.el9.src.rpm</rpm:sourcerpm>"));
+
+        let xml = primary_xml(&artifacts[1..]);
+        assert!(xml.contains("<arch>src</arch>"));
+        assert!(xml.contains("<rpm:sourcerpm/>"));
+    }
+
+    #[test]
+    fn source_packages_are_served_under_srpms() {
+        let request = |path: &str| {
+            RpmFormatHandler::handle_request_with_properties(
+                get_request(path),
+                test_context(),
+                bash_source_artifacts(),
+            )
+            .unwrap()
+        };
+
+        let resp = request("/SRPMS/packages/bash-5.2.26-1.el9.src.rpm");
+        assert_eq!(resp.status, 302);
+        assert!(resp.headers[0].1.ends_with("/bash-5.2.26-1.el9.src.rpm"));
+        assert_eq!(request("/packages/bash-5.2.26-1.el9.src.rpm").status, 404);
+        assert_eq!(
+            request("/SRPMS/packages/bash-5.2.26-1.el9.x86_64.rpm").status,
+            404
+        );
+        assert_eq!(
+            request("/packages/bash-5.2.26-1.el9.x86_64.rpm").status,
+            302
+        );
+
+        assert_eq!(request("/SRPMS/repodata/repomd.xml").status, 200);
+        assert_eq!(request("/SRPMSX/repodata/repomd.xml").status, 404);
+        assert_ne!(
+            request("/SRPMS/repodata/repomd.xml").body,
+            request("/repodata/repomd.xml").body
+        );
+    }
+
+    #[test]
+    fn generate_index_lists_source_packages_separately() {
+        let result = RpmFormatHandler::generate_index_with_properties(bash_source_artifacts())
+            .unwrap()
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&result[0].1).unwrap();
+        assert_eq!(json["total_count"], 2);
+        assert_eq!(json["packages"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            json["packages"][0]["source_rpm"],
+            "bash-5.2.26-1.el9.src.rpm"
+        );
+        assert_eq!(json["source_packages"].as_array().unwrap().len(), 1);
+        assert_eq!(json["source_packages"][0]["arch"], "src");
+    }
+
+    // -- repoclosure --
+
+    #[test]
+    fn generate_index_reports_repoclosure() {
+        use super::header::{RPMTAG_REQUIREFLAGS, RPMTAG_REQUIRENAME, RPMTAG_REQUIREVERSION};
+
+        let requires = [
+            RPMTAG_REQUIRENAME,
+            RPMTAG_REQUIREFLAGS,
+            RPMTAG_REQUIREVERSION,
+        ];
+        let nginx = RpmBuilder::new("nginx", "1.24.0", "1.el9", "x86_64")
+            .dependencies(
+                requires,
+                &[
+                    ("nginx-filesystem", RPMSENSE_EQUAL, "1.24.0-1.el9"),
+                    ("openssl-libs", RPMSENSE_GREATER | RPMSENSE_EQUAL, "1:3.0.7"),
+                ],
+            )
+            .build();
+        let filesystem = RpmBuilder::new("nginx-filesystem", "1.24.0", "1.el9", "noarch")
+            .dependencies(requires, &[("/bin/sh", 0, "")])
+            .files(&[("/bin/sh", 0o100755, 0)])
+            .build();
+        let metadata = |path: &str| Metadata {
+            path: path.into(),
+            version: None,
+            content_type: "application/x-rpm".into(),
+            size_bytes: 1024,
+            checksum_sha256: None,
+        };
+        let artifacts = vec![
+            with_properties(metadata("nginx-1.24.0-1.el9.x86_64.rpm"), &nginx),
+            with_properties(
+                metadata("nginx-filesystem-1.24.0-1.el9.noarch.rpm"),
+                &filesystem,
+            ),
+            Artifact {
+                metadata: metadata("bash-5.2.26-1.el9.x86_64.rpm"),
+                properties: Vec::new(),
+            },
+        ];
+
+        let result = RpmFormatHandler::generate_index_with_properties(artifacts)
+            .unwrap()
+            .unwrap();
+        assert_eq!(result[1].0, "repoclosure.json");
+        let json: serde_json::Value = serde_json::from_slice(&result[1].1).unwrap();
+        assert_eq!(json["complete"], false);
+        assert_eq!(json["checked_count"], 2);
+        assert_eq!(json["broken_count"], 1);
+        assert_eq!(json["broken"][0]["nevra"], "nginx-0:1.24.0-1.el9.x86_64");
+        assert_eq!(
+            json["broken"][0]["unresolved"],
+            serde_json::json!(["openssl-libs >= 1:3.0.7"])
+        );
+        assert_eq!(
+            json["unchecked"],
+            serde_json::json!(["bash-5.2.26-1.el9.x86_64.rpm"])
+        );
+    }
+
+    // -- distribution trees --
+
+    fn multi_distro_request(path: &str) -> HttpResponse {
+        let metadata = |path: &str| Metadata {
+            path: format!("Packages/{path}"),
+            version: None,
+            content_type: "application/x-rpm".into(),
+            size_bytes: 1024,
+            checksum_sha256: None,
+        };
+        let artifacts = [
+            "nginx-1.24.0-1.el9.x86_64.rpm",
+            "nginx-1.24.0-1.el9.aarch64.rpm",
+            "nginx-1.20.1-14.el8.x86_64.rpm",
+            "nginx-1.24.0-3.fc39.x86_64.rpm",
+            "nginx-filesystem-1.24.0-1.el9.noarch.rpm",
+            "nginx-1.24.0-1.el9.src.rpm",
+            "nginx-1.20.1-14.el8.src.rpm",
+        ]
+        .map(metadata)
+        .to_vec();
+        RpmFormatHandler::handle_request(get_request(path), test_context(), artifacts).unwrap()
+    }
+
+    fn primary_locations(tree: &str) -> Vec<String> {
+        let resp = multi_distro_request(&format!("{tree}/repodata/primary.xml.gz"));
+        assert_eq!(resp.status, 200);
+        gunzip(&resp.body)
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("<location href=\"packages/"))
+            .map(|l| l.trim_end_matches("\"/>").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn tree_primary_filters_by_dist_tag_and_arch() {
+        assert_eq!(
+            primary_locations("/el9/x86_64"),
+            [
+                "nginx-1.24.0-1.el9.x86_64.rpm",
+                "nginx-filesystem-1.24.0-1.el9.noarch.rpm"
+            ]
+        );
+        assert_eq!(
+            primary_locations("/el9/aarch64"),
+            [
+                "nginx-1.24.0-1.el9.aarch64.rpm",
+                "nginx-filesystem-1.24.0-1.el9.noarch.rpm"
+            ]
+        );
+        assert_eq!(
+            primary_locations("/8/x86_64"),
+            ["nginx-1.20.1-14.el8.x86_64.rpm"]
+        );
+        assert_eq!(
+            primary_locations("/fc39/x86_64"),
+            ["nginx-1.24.0-3.fc39.x86_64.rpm"]
+        );
+        assert_eq!(
+            primary_locations("/el9/SRPMS"),
+            ["nginx-1.24.0-1.el9.src.rpm"]
+        );
+        // The flat tree still serves every binary package
+        assert_eq!(primary_locations("").len(), 5);
+    }
+
+    #[test]
+    fn tree_repomd_matches_its_primary() {
+        let repomd = multi_distro_request("/el9/x86_64/repodata/repomd.xml");
+        assert_eq!(repomd.status, 200);
+        let repomd = String::from_utf8(repomd.body).unwrap();
+        let primary = multi_distro_request("/el9/x86_64/repodata/primary.xml.gz").body;
+        assert!(repomd.contains(&sha256_hex(&primary)));
+        assert_ne!(
+            repomd.as_bytes(),
+            multi_distro_request("/el8/x86_64/repodata/repomd.xml").body
+        );
+    }
+
+    #[test]
+    fn tree_package_download_is_filtered() {
+        let resp = multi_distro_request("/el9/x86_64/packages/nginx-1.24.0-1.el9.x86_64.rpm");
+        assert_eq!(resp.status, 302);
+        assert!(resp.headers[0]
+            .1
+            .ends_with("/Packages/nginx-1.24.0-1.el9.x86_64.rpm"));
+
+        let resp = multi_distro_request("/el8/x86_64/packages/nginx-1.24.0-1.el9.x86_64.rpm");
+        assert_eq!(resp.status, 404);
+        assert_eq!(multi_distro_request("/el9/x86_64/unknown").status, 404);
+    }
+
+    // -- metalink and mirrorlist --
+
+    fn mirror_config() -> RpmConfig {
+        RpmConfig {
+            mirrors: vec!["https://eu.example.com/rpm".to_string()],
+            ..RpmConfig::default()
+        }
+    }
+
+    #[test]
+    fn metalink_matches_served_repomd() {
+        let config = mirror_config();
+        let repomd = route("/repodata/repomd.xml", &config).body;
+
+        let resp = route("/metalink", &config);
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.headers[0].1, "application/metalink+xml");
+        let xml = String::from_utf8(resp.body).unwrap();
+        assert!(xml.contains(&format!("<size>{}</size>", repomd.len())));
+        assert!(xml.contains(&format!(
+            "<hash type=\"sha256\">{}</hash>",
+            sha256_hex(&repomd)
+        )));
+        assert!(
+            xml.contains("preference=\"100\">https://eu.example.com/rpm/repodata/repomd.xml</url>")
+        );
+        assert!(xml.contains(
+            "preference=\"99\">http://localhost:8080/ext/rpm-custom/rpm-test/repodata/repomd.xml</url>"
+        ));
+    }
+
+    #[test]
+    fn mirrorlist_lists_mirrors_then_origin() {
+        let resp = route("/mirrorlist", &mirror_config());
+        assert_eq!(resp.status, 200);
+        assert_eq!(
+            String::from_utf8(resp.body).unwrap(),
+            "https://eu.example.com/rpm/\nhttp://localhost:8080/ext/rpm-custom/rpm-test/\n"
+        );
+    }
+
+    #[test]
+    fn tree_metalink_points_into_the_tree() {
+        let xml = String::from_utf8(multi_distro_request("/el9/x86_64/metalink").body).unwrap();
+        let repomd = multi_distro_request("/el9/x86_64/repodata/repomd.xml").body;
+        assert!(xml.contains(&sha256_hex(&repomd)));
+        assert!(xml.contains("/rpm-test/el9/x86_64/repodata/repomd.xml</url>"));
+
+        let list = multi_distro_request("/SRPMS/mirrorlist").body;
+        assert_eq!(
+            String::from_utf8(list).unwrap(),
+            "http://localhost:8080/ext/rpm-custom/rpm-test/SRPMS/\n"
+        );
     }
 
     #[test]
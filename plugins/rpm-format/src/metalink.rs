@@ -0,0 +1,91 @@
+//! Metalink and mirrorlist documents.
+//!
+//! dnf can locate a repository through `metalink=` or `mirrorlist=` instead of a fixed
+//! `baseurl`. A metalink describes repomd.xml (its size, timestamp, and hashes) and
+//! lists every URL it can be fetched from, so dnf can reject a stale or tampered mirror.
+//! A mirrorlist is just the list of base URLs, one per line.
+
+use crate::{sha256_hex, xml_escape};
+
+/// Render a metalink for repomd.xml.
+///
+/// `base_urls` are repository base URLs in order of preference; each is listed with
+/// the path to its repomd.xml.
+pub(crate) fn metalink_xml(repomd: &[u8], timestamp: u64, base_urls: &[String]) -> String {
+    let sha256 = sha256_hex(repomd);
+
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <metalink version=\"3.0\" xmlns=\"http://www.metalinker.org/\" type=\"dynamic\" \
+         xmlns:mm0=\"http://fedorahosted.org/mirrormanager\">\n",
+    );
+    xml.push_str("  <files>\n");
+    xml.push_str("    <file name=\"repomd.xml\">\n");
+    xml.push_str(&format!(
+        "      <mm0:timestamp>{timestamp}</mm0:timestamp>\n"
+    ));
+    xml.push_str(&format!("      <size>{}</size>\n", repomd.len()));
+    xml.push_str("      <verification>\n");
+    xml.push_str(&format!("        <hash type=\"sha256\">{sha256}</hash>\n"));
+    xml.push_str("      </verification>\n");
+    xml.push_str("      <resources maxconnections=\"1\">\n");
+    for (i, base) in base_urls.iter().enumerate() {
+        let protocol = base.split_once("://").map_or("http", |(scheme, _)| scheme);
+        let preference = 100usize.saturating_sub(i).max(1);
+        xml.push_str(&format!(
+            "        <url protocol=\"{protocol}\" type=\"{protocol}\" preference=\"{preference}\">\
+             {}/repodata/repomd.xml</url>\n",
+            xml_escape(base)
+        ));
+    }
+    xml.push_str("      </resources>\n");
+    xml.push_str("    </file>\n");
+    xml.push_str("  </files>\n");
+    xml.push_str("</metalink>\n");
+    xml
+}
+
+/// Render a mirrorlist: each base URL on its own line, with a trailing slash.
+pub(crate) fn mirrorlist(base_urls: &[String]) -> String {
+    base_urls.iter().map(|base| format!("{base}/\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<String> {
+        vec![
+            "https://mirror.example.com/rpm".to_string(),
+            "http://origin.example.com/R&D/rpm".to_string(),
+        ]
+    }
+
+    #[test]
+    fn metalink_describes_repomd() {
+        let xml = metalink_xml(b"<repomd/>", 1_700_000_000, &urls());
+        assert!(xml.contains("<file name=\"repomd.xml\">"));
+        assert!(xml.contains("<mm0:timestamp>1700000000</mm0:timestamp>"));
+        assert!(xml.contains("<size>9</size>"));
+        assert!(xml.contains(&format!(
+            "<hash type=\"sha256\">{}</hash>",
+            sha256_hex(b"<repomd/>")
+        )));
+        assert!(xml.contains(
+            "<url protocol=\"https\" type=\"https\" preference=\"100\">\
+             https://mirror.example.com/rpm/repodata/repomd.xml</url>"
+        ));
+        assert!(xml.contains(
+            "<url protocol=\"http\" type=\"http\" preference=\"99\">\
+             http://origin.example.com/R&amp;D/rpm/repodata/repomd.xml</url>"
+        ));
+    }
+
+    #[test]
+    fn mirrorlist_lists_base_urls() {
+        assert_eq!(
+            mirrorlist(&urls()),
+            "https://mirror.example.com/rpm/\nhttp://origin.example.com/R&D/rpm/\n"
+        );
+    }
+}
@@ -0,0 +1,404 @@
+//! Modularity metadata (modulemd).
+//!
+//! Module streams are uploaded to the repository as YAML documents named `modules.yaml`,
+//! `<anything>-modules.yaml`, or `<anything>.modulemd.yaml`. Each file may hold several
+//! documents (`modulemd` streams, `modulemd-defaults`, and so on). They are validated on
+//! upload, stored as an artifact property, and merged into `repodata/modules.yaml`, which
+//! dnf reads on RHEL 8 and 9 to decide which packages belong to which module stream.
+
+use std::collections::HashSet;
+
+use serde_yaml_ng::Value;
+
+use crate::updateinfo::parse_nevra;
+
+/// Document types accepted in a repository, with the version each must declare.
+const DOCUMENT_VERSIONS: [(&str, u64); 4] = [
+    ("modulemd", 2),
+    ("modulemd-defaults", 1),
+    ("modulemd-obsoletes", 1),
+    ("modulemd-translations", 1),
+];
+
+/// Whether an artifact path names a modulemd document.
+pub(crate) fn is_modulemd_path(path: &str) -> bool {
+    let filename = path.rsplit('/').next().unwrap_or(path).to_ascii_lowercase();
+    filename == "modules.yaml"
+        || filename.ends_with("-modules.yaml")
+        || filename.ends_with(".modulemd.yaml")
+}
+
+/// A module stream and the packages it's built from.
+pub(crate) struct ModuleStream {
+    pub(crate) name: String,
+    pub(crate) stream: String,
+    pub(crate) version: u64,
+    pub(crate) context: String,
+    pub(crate) arch: String,
+    /// Package NEVRAs as listed under `artifacts.rpms`, each with an explicit epoch.
+    pub(crate) rpms: Vec<String>,
+}
+
+impl ModuleStream {
+    /// The `name:stream:version:context:arch` identifier dnf reports.
+    pub(crate) fn nsvca(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            self.name, self.stream, self.version, self.context, self.arch
+        )
+    }
+}
+
+/// Parse and validate an uploaded modulemd file, returning its text.
+pub(crate) fn parse_modulemd(data: &[u8]) -> Result<String, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "Invalid modulemd: not UTF-8".to_string())?;
+    let documents = documents(text).map_err(|e| format!("Invalid modulemd YAML: {e}"))?;
+    if documents.is_empty() {
+        return Err("Modulemd file contains no documents".to_string());
+    }
+    for (i, (_, document)) in documents.iter().enumerate() {
+        check_document(document)
+            .map_err(|e| format!("Invalid modulemd document {}: {e}", i + 1))?;
+    }
+    Ok(text.to_string())
+}
+
+/// Merge modulemd files into one YAML stream.
+///
+/// Each document's source text is copied verbatim, in file order, between `---` and `...`
+/// markers. When the same stream, defaults, or translation appears more than once, the
+/// first copy wins.
+pub(crate) fn merge_modulemd<'a>(files: impl IntoIterator<Item = &'a str>) -> String {
+    let mut yaml = String::new();
+    let mut seen = HashSet::new();
+
+    // Stored files were validated on upload
+    for (text, document) in files
+        .into_iter()
+        .flat_map(|f| documents(f).unwrap_or_default())
+    {
+        if !seen.insert(document_key(&document)) {
+            continue;
+        }
+        if !text.starts_with("---") {
+            yaml.push_str("---\n");
+        }
+        yaml.push_str(text);
+        if !text.ends_with('\n') {
+            yaml.push('\n');
+        }
+        yaml.push_str("...\n");
+    }
+    yaml
+}
+
+/// Every module stream defined in the given modulemd files.
+pub(crate) fn module_streams<'a>(files: impl IntoIterator<Item = &'a str>) -> Vec<ModuleStream> {
+    files
+        .into_iter()
+        .flat_map(|f| documents(f).unwrap_or_default())
+        .map(|(_, d)| d)
+        .filter(|d| d.get("document").and_then(Value::as_str) == Some("modulemd"))
+        .filter_map(|d| {
+            let data = d.get("data")?;
+            Some(ModuleStream {
+                name: scalar(data.get("name")?)?,
+                stream: scalar(data.get("stream")?)?,
+                version: data.get("version")?.as_u64()?,
+                context: scalar(data.get("context")?)?,
+                arch: scalar(data.get("arch")?)?,
+                rpms: data
+                    .get("artifacts")
+                    .and_then(|a| a.get("rpms"))
+                    .and_then(Value::as_sequence)
+                    .map(|rpms| rpms.iter().filter_map(scalar).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// The non-empty documents of a YAML stream: each one's source text, from its `---`
+/// marker if it has one, with its parsed value.
+fn documents(text: &str) -> Result<Vec<(&str, Value)>, serde_yaml_ng::Error> {
+    split_documents(text)
+        .into_iter()
+        .map(|source| Ok((source, serde_yaml_ng::from_str(source)?)))
+        .filter(|d| !matches!(d, Ok((_, Value::Null))))
+        .collect()
+}
+
+/// Split a YAML stream at its document markers, which always start a line: `---`
+/// begins a document (and ends the previous one), and `...` ends one. The `...` markers
+/// are dropped, and so is anything between a `...` and the next document.
+fn split_documents(text: &str) -> Vec<&str> {
+    let is_marker = |line: &str, marker: &str| {
+        line.strip_prefix(marker)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']))
+    };
+
+    let mut documents = Vec::new();
+    let mut start = Some(0);
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if is_marker(line, "---") {
+            documents.extend(start.map(|s| &text[s..offset]));
+            start = Some(offset);
+        } else if is_marker(line, "...") {
+            documents.extend(start.map(|s| &text[s..offset]));
+            start = None;
+        } else if start.is_none() && !line.trim().is_empty() && !line.starts_with('#') {
+            // Content after `...` without a `---` starts a bare document
+            start = Some(offset);
+        }
+        offset += line.len();
+    }
+    documents.extend(start.map(|s| &text[s..]));
+    documents
+}
+
+/// A string or number scalar as a string; unquoted streams like `3.9` parse as numbers.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Identifies a document for de-duplication across files.
+fn document_key(document: &Value) -> Vec<String> {
+    let data = document.get("data");
+    [
+        "name", "module", "stream", "version", "context", "arch", "modified",
+    ]
+    .into_iter()
+    .map(|field| {
+        data.and_then(|d| d.get(field))
+            .and_then(scalar)
+            .unwrap_or_default()
+    })
+    .chain(document.get("document").and_then(scalar))
+    .collect()
+}
+
+fn check_document(document: &Value) -> Result<(), String> {
+    let kind = document
+        .get("document")
+        .and_then(Value::as_str)
+        .ok_or("missing 'document'")?;
+    let (_, expected) = DOCUMENT_VERSIONS
+        .into_iter()
+        .find(|(k, _)| *k == kind)
+        .ok_or_else(|| format!("unsupported document type '{kind}'"))?;
+    let version = document.get("version").and_then(Value::as_u64);
+    if version != Some(expected) {
+        return Err(format!("{kind} documents must be version {expected}"));
+    }
+    let data = document
+        .get("data")
+        .filter(|d| d.is_mapping())
+        .ok_or("missing 'data' mapping")?;
+
+    match kind {
+        "modulemd" => check_stream(data),
+        "modulemd-defaults" => data
+            .get("module")
+            .and_then(scalar)
+            .map(|_| ())
+            .ok_or_else(|| "modulemd-defaults is missing 'module'".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn check_stream(data: &Value) -> Result<(), String> {
+    for field in [
+        "name",
+        "stream",
+        "context",
+        "arch",
+        "summary",
+        "description",
+    ] {
+        if data.get(field).and_then(scalar).is_none() {
+            return Err(format!("modulemd stream is missing '{field}'"));
+        }
+    }
+    if data.get("version").and_then(Value::as_u64).is_none() {
+        return Err("modulemd stream 'version' must be an unsigned integer".to_string());
+    }
+    let licenses = data
+        .get("license")
+        .and_then(|l| l.get("module"))
+        .and_then(Value::as_sequence);
+    if licenses.is_none_or(|l| l.is_empty()) {
+        return Err("modulemd stream is missing 'license.module'".to_string());
+    }
+
+    let rpms = data.get("artifacts").and_then(|a| a.get("rpms"));
+    if let Some(rpms) = rpms {
+        let rpms = rpms
+            .as_sequence()
+            .ok_or("modulemd 'artifacts.rpms' must be a list")?;
+        for rpm in rpms {
+            let nevra = rpm.as_str().unwrap_or("");
+            if !nevra.contains(':') || parse_nevra(nevra).is_none() {
+                return Err(format!(
+                    "modulemd artifact '{nevra}' is not a name-epoch:version-release.arch NEVRA"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULES: &str = "\
+---
+document: modulemd
+version: 2
+data:
+  name: nginx
+  stream: \"1.24\"
+  version: 9040020240101000000
+  context: 9a7b3c1d
+  arch: x86_64
+  summary: nginx webserver
+  description: >-
+    nginx 1.24 module stream.
+  license:
+    module: [MIT]
+  artifacts:
+    rpms:
+    - nginx-1:1.24.0-1.module+el9.4.0+1234+abcdef.x86_64
+    - nginx-1:1.24.0-1.module+el9.4.0+1234+abcdef.src
+...
+---
+document: modulemd-defaults
+version: 1
+data:
+  module: nginx
+  stream: \"1.24\"
+...
+";
+
+    #[test]
+    fn accepts_valid_modulemd() {
+        assert_eq!(parse_modulemd(MODULES.as_bytes()).unwrap(), MODULES);
+    }
+
+    #[test]
+    fn rejects_invalid_modulemd() {
+        let stream = |data: &str| {
+            format!(
+                "document: modulemd\nversion: 2\ndata:\n  name: n\n  stream: s\n  context: c\n  \
+                 arch: x86_64\n  summary: s\n  description: d\n  license: {{module: [MIT]}}\n{data}"
+            )
+        };
+        let cases = [
+            ("", "contains no documents".to_string()),
+            ("[unclosed", "Invalid modulemd YAML".to_string()),
+            (
+                "document: modulemd-packager\nversion: 3\ndata: {}",
+                "unsupported document type".to_string(),
+            ),
+            (
+                "document: modulemd\nversion: 1\ndata: {}",
+                "must be version 2".to_string(),
+            ),
+            (
+                "document: modulemd-defaults\nversion: 1\ndata: {}",
+                "missing 'module'".to_string(),
+            ),
+            (
+                &*stream(""),
+                "'version' must be an unsigned integer".to_string(),
+            ),
+            (
+                &*stream("  version: 1\n  artifacts: {rpms: [nginx-1.24.0-1.x86_64]}\n"),
+                "not a name-epoch:version-release.arch NEVRA".to_string(),
+            ),
+        ];
+        for (yaml, expected) in cases {
+            let err = parse_modulemd(yaml.as_bytes()).unwrap_err();
+            assert!(err.contains(&expected), "{yaml}: {err}");
+        }
+        assert!(parse_modulemd(stream("  version: 1\n").as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn merges_and_deduplicates() {
+        let other = "document: modulemd-defaults\nversion: 1\ndata:\n  module: postgresql\n";
+        let merged = merge_modulemd([MODULES, MODULES, other]);
+        assert_eq!(merged.matches("---\n").count(), 3);
+        assert_eq!(merged.matches("...\n").count(), 3);
+        assert!(parse_modulemd(merged.as_bytes()).is_ok());
+
+        let streams = module_streams([merged.as_str()]);
+        assert_eq!(streams.len(), 1);
+        assert_eq!(
+            streams[0].nsvca(),
+            "nginx:1.24:9040020240101000000:9a7b3c1d:x86_64"
+        );
+        assert_eq!(streams[0].rpms.len(), 2);
+    }
+
+    #[test]
+    fn merge_keeps_source_text() {
+        // Quoting, key order, comments, and block scalars are kept as written
+        let quirky = "\
+# Built by MBS
+document: modulemd
+version: 2
+data:
+  stream: '1.24'
+  name: nginx
+  version: 1
+  context: \"00000000\"
+  arch: x86_64
+  summary: nginx
+  description: |
+    Line one.
+
+    --- not a marker when indented
+  license: {module: [MIT]}
+--- !!map
+document: modulemd-defaults
+version: 1
+data: {module: nginx}
+";
+        assert!(parse_modulemd(quirky.as_bytes()).is_ok());
+        let merged = merge_modulemd([quirky]);
+        assert_eq!(
+            merged,
+            format!(
+                "---\n{}...\n--- !!map\ndocument: modulemd-defaults\nversion: 1\n\
+                 data: {{module: nginx}}\n...\n",
+                &quirky[..quirky.find("--- !!map").unwrap()]
+            )
+        );
+        assert_eq!(merge_modulemd([MODULES]), MODULES);
+    }
+
+    #[test]
+    fn splits_documents_at_markers() {
+        assert_eq!(split_documents("a: 1\n"), ["a: 1\n"]);
+        assert_eq!(
+            split_documents("---\na: 1\n...\n# between\n---\nb: 2\n"),
+            ["", "---\na: 1\n", "---\nb: 2\n"]
+        );
+        assert_eq!(split_documents("a: 1\n...\nb: 2\n"), ["a: 1\n", "b: 2\n"]);
+        assert_eq!(split_documents("a: ---x\n----\n"), ["a: ---x\n----\n"]);
+    }
+
+    #[test]
+    fn modulemd_paths() {
+        assert!(is_modulemd_path("modules.yaml"));
+        assert!(is_modulemd_path("modules/nginx-modules.yaml"));
+        assert!(is_modulemd_path("nginx-1.24.modulemd.yaml"));
+        assert!(!is_modulemd_path("mymodules.yaml"));
+    }
+}
@@ -0,0 +1,1025 @@
+//! Minimal OpenPGP (RFC 4880) support.
+//!
+//! RPM signature tags hold a single binary OpenPGP signature packet. The issuer
+//! key ID and the public-key and hash algorithms identify the signer; v4 RSA and
+//! Ed25519 signatures can also be verified against public keys loaded from
+//! ASCII-armored key blocks (as exported by `gpg --export --armor`).
+//!
+//! Repository metadata is signed with an Ed25519 key supplied as an unprotected,
+//! ASCII-armored secret key (as exported by `gpg --export-secret-keys --armor`).
+//! Signatures are v4 binary-document signatures over SHA-256, which dnf and
+//! gpg verify with the matching public key.
+
+use ed25519_dalek::{Signer, Verifier};
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+// Packet tags.
+const SIGNATURE_PACKET_TAG: u8 = 2;
+const SECRET_KEY_PACKET_TAG: u8 = 5;
+const PUBLIC_KEY_PACKET_TAG: u8 = 6;
+const SECRET_SUBKEY_PACKET_TAG: u8 = 7;
+const USER_ID_PACKET_TAG: u8 = 13;
+const PUBLIC_SUBKEY_PACKET_TAG: u8 = 14;
+const USER_ATTRIBUTE_PACKET_TAG: u8 = 17;
+
+// Public-key and hash algorithm IDs.
+const PUBKEY_ALGO_RSA: u8 = 1;
+const PUBKEY_ALGO_RSA_SIGN: u8 = 3;
+const PUBKEY_ALGO_EDDSA: u8 = 22;
+const HASH_ALGO_SHA1: u8 = 2;
+const HASH_ALGO_SHA256: u8 = 8;
+const HASH_ALGO_SHA384: u8 = 9;
+const HASH_ALGO_SHA512: u8 = 10;
+const HASH_ALGO_SHA224: u8 = 11;
+
+/// DER-encoded OID of the Ed25519 curve, as used by EdDSA keys.
+const ED25519_OID: [u8; 9] = [0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+/// Signature type of a signature over a binary document.
+const SIGNATURE_TYPE_BINARY: u8 = 0x00;
+
+const SUBPACKET_CREATION_TIME: u8 = 2;
+
+// Signature subpacket types.
+const SUBPACKET_ISSUER: u8 = 16;
+const SUBPACKET_ISSUER_FINGERPRINT: u8 = 33;
+
+/// The signer of an OpenPGP signature.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct SignatureInfo {
+    /// 64-bit issuer key ID as 16 lowercase hex digits.
+    pub(crate) key_id: String,
+    pub(crate) public_key_algorithm: u8,
+    pub(crate) hash_algorithm: u8,
+}
+
+impl SignatureInfo {
+    /// The algorithm in rpm's `RSA/SHA256` notation.
+    pub(crate) fn algorithm(&self) -> String {
+        format!(
+            "{}/{}",
+            public_key_algorithm_name(self.public_key_algorithm),
+            hash_algorithm_name(self.hash_algorithm)
+        )
+    }
+}
+
+/// Decode the signature packet at the start of `data`.
+pub(crate) fn parse_signature(data: &[u8]) -> Result<SignatureInfo, String> {
+    let (tag, body, _) = read_packet(data)?;
+    if tag != SIGNATURE_PACKET_TAG {
+        return Err(format!(
+            "Expected an OpenPGP signature packet, found packet tag {tag}"
+        ));
+    }
+
+    let truncated = || "Truncated OpenPGP signature packet".to_string();
+    match body.first() {
+        // Version 3: fixed layout with the key ID inline
+        Some(3) => {
+            let fields = body.get(..17).ok_or_else(truncated)?;
+            if fields[1] != 5 {
+                return Err(format!(
+                    "Invalid v3 OpenPGP signature: hashed length {} (expected 5)",
+                    fields[1]
+                ));
+            }
+            Ok(SignatureInfo {
+                key_id: hex(&fields[7..15]),
+                public_key_algorithm: fields[15],
+                hash_algorithm: fields[16],
+            })
+        }
+        // Versions 4 to 6: the key ID lives in the (hashed or unhashed) subpackets
+        Some(&version @ 4..=6) => {
+            let fields = body.get(..4).ok_or_else(truncated)?;
+            let (public_key_algorithm, hash_algorithm) = (fields[2], fields[3]);
+
+            // v4 subpacket areas have 2-byte lengths, v5 and v6 have 4-byte lengths
+            let len_size = if version == 4 { 2 } else { 4 };
+            let mut pos = 4;
+            let mut key_id = None;
+            for _ in 0..2 {
+                let len = be_uint(body.get(pos..pos + len_size).ok_or_else(truncated)?);
+                pos += len_size;
+                let end = pos.checked_add(len).ok_or_else(truncated)?;
+                let area = body.get(pos..end).ok_or_else(truncated)?;
+                pos = end;
+                key_id = key_id.or(issuer_key_id(area)?);
+            }
+
+            Ok(SignatureInfo {
+                key_id: key_id.ok_or("OpenPGP signature has no issuer key ID")?,
+                public_key_algorithm,
+                hash_algorithm,
+            })
+        }
+        Some(v) => Err(format!("Unsupported OpenPGP signature version {v}")),
+        None => Err(truncated()),
+    }
+}
+
+/// Read one packet, returning its tag, its body, and its total length including the header.
+fn read_packet(data: &[u8]) -> Result<(u8, &[u8], usize), String> {
+    let truncated = || "Truncated OpenPGP packet".to_string();
+    let &first = data.first().ok_or_else(truncated)?;
+    if first & 0x80 == 0 {
+        return Err("Invalid OpenPGP packet header".to_string());
+    }
+
+    let (tag, len, header_len): (u8, usize, usize) = if first & 0x40 == 0 {
+        // Old format: tag in bits 5-2, length type in bits 1-0
+        let tag = (first >> 2) & 0x0f;
+        let (len, n) = match first & 0x03 {
+            0 => (*data.get(1).ok_or_else(truncated)? as usize, 1),
+            1 => (be_uint(data.get(1..3).ok_or_else(truncated)?), 2),
+            2 => (be_uint(data.get(1..5).ok_or_else(truncated)?), 4),
+            _ => (data.len() - 1, 0),
+        };
+        (tag, len, 1 + n)
+    } else {
+        // New format: tag in bits 5-0, variable-length length
+        let tag = first & 0x3f;
+        let &o1 = data.get(1).ok_or_else(truncated)?;
+        match o1 {
+            0..=191 => (tag, o1 as usize, 2),
+            192..=223 => {
+                let &o2 = data.get(2).ok_or_else(truncated)?;
+                (tag, ((o1 as usize - 192) << 8) + o2 as usize + 192, 3)
+            }
+            255 => (tag, be_uint(data.get(2..6).ok_or_else(truncated)?), 6),
+            _ => return Err("Partial-length OpenPGP packets are not supported".to_string()),
+        }
+    };
+
+    // Lengths come from the packet, so they may overflow a 32-bit usize
+    let end = header_len.checked_add(len).ok_or_else(truncated)?;
+    let body = data.get(header_len..end).ok_or_else(truncated)?;
+    Ok((tag, body, end))
+}
+
+/// Find the issuer key ID in a subpacket area.
+fn issuer_key_id(mut area: &[u8]) -> Result<Option<String>, String> {
+    let truncated = || "Truncated OpenPGP signature subpacket".to_string();
+    let mut fingerprint_key_id = None;
+
+    while !area.is_empty() {
+        let o1 = area[0] as usize;
+        let (len, n): (usize, usize) = match o1 {
+            0..=191 => (o1, 1),
+            192..=254 => {
+                let &o2 = area.get(1).ok_or_else(truncated)?;
+                (((o1 - 192) << 8) + o2 as usize + 192, 2)
+            }
+            _ => (be_uint(area.get(1..5).ok_or_else(truncated)?), 5),
+        };
+        // The length covers the type byte and the data
+        let end = n.checked_add(len).ok_or_else(truncated)?;
+        let subpacket = area.get(n..end).ok_or_else(truncated)?;
+        area = &area[end..];
+
+        let Some((&kind, value)) = subpacket.split_first() else {
+            continue;
+        };
+        match kind & 0x7f {
+            SUBPACKET_ISSUER if value.len() == 8 => return Ok(Some(hex(value))),
+            // A v4 fingerprint ends with the key ID; v5 and v6 fingerprints start with it
+            SUBPACKET_ISSUER_FINGERPRINT => match value.split_first() {
+                Some((4, fpr)) if fpr.len() == 20 => fingerprint_key_id = Some(hex(&fpr[12..])),
+                Some((5 | 6, fpr)) if fpr.len() == 32 => fingerprint_key_id = Some(hex(&fpr[..8])),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    Ok(fingerprint_key_id)
+}
+
+fn public_key_algorithm_name(algorithm: u8) -> String {
+    match algorithm {
+        1..=3 => "RSA".to_string(),
+        17 => "DSA".to_string(),
+        19 => "ECDSA".to_string(),
+        22 => "EdDSA".to_string(),
+        27 => "Ed25519".to_string(),
+        28 => "Ed448".to_string(),
+        other => format!("PK{other}"),
+    }
+}
+
+fn hash_algorithm_name(algorithm: u8) -> String {
+    match algorithm {
+        1 => "MD5".to_string(),
+        2 => "SHA1".to_string(),
+        3 => "RIPEMD160".to_string(),
+        8 => "SHA256".to_string(),
+        9 => "SHA384".to_string(),
+        10 => "SHA512".to_string(),
+        11 => "SHA224".to_string(),
+        12 => "SHA3-256".to_string(),
+        14 => "SHA3-512".to_string(),
+        other => format!("HASH{other}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Verification
+// ---------------------------------------------------------------------------
+
+/// A primary key or subkey that signatures can be verified against.
+pub(crate) struct PublicKey {
+    /// 64-bit key ID as 16 lowercase hex digits.
+    pub(crate) key_id: String,
+    material: KeyMaterial,
+}
+
+enum KeyMaterial {
+    Rsa(RsaPublicKey),
+    Ed25519(ed25519_dalek::VerifyingKey),
+    /// A key whose algorithm can't verify signatures here, such as DSA or ECDSA.
+    Unsupported(u8),
+}
+
+/// Load every primary key and subkey from one or more ASCII-armored public key blocks.
+pub(crate) fn parse_public_keys(armored: &str) -> Result<Vec<PublicKey>, String> {
+    const LABEL: &str = "PGP PUBLIC KEY BLOCK";
+    let begin = format!("-----BEGIN {LABEL}-----");
+
+    let mut keys = Vec::new();
+    for (start, _) in armored.match_indices(&begin) {
+        let data = dearmor(&armored[start..], LABEL)?;
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let (tag, body, len) = read_packet(rest)?;
+            if tag == PUBLIC_KEY_PACKET_TAG || tag == PUBLIC_SUBKEY_PACKET_TAG {
+                keys.push(PublicKey::from_packet(body)?);
+            }
+            rest = &rest[len..];
+        }
+    }
+    if keys.is_empty() {
+        return Err(format!("Expected an ASCII-armored {LABEL}"));
+    }
+    Ok(keys)
+}
+
+impl PublicKey {
+    /// Read a v4 public key packet body.
+    fn from_packet(body: &[u8]) -> Result<Self, String> {
+        let public_len = public_key_len(body)?;
+        let body = &body[..public_len];
+        let fingerprint = v4_fingerprint(body);
+        let invalid = |e: String| format!("Invalid OpenPGP public key {}: {e}", hex(&fingerprint));
+
+        let material = match body[5] {
+            PUBKEY_ALGO_RSA | PUBKEY_ALGO_RSA_SIGN => {
+                let (n, rest) = read_mpi(&body[6..]).ok_or("truncated RSA modulus")?;
+                let (e, _) = read_mpi(rest).ok_or("truncated RSA exponent")?;
+                let key = RsaPublicKey::new(BigUint::from_bytes_be(n), BigUint::from_bytes_be(e))
+                    .map_err(|e| invalid(e.to_string()))?;
+                KeyMaterial::Rsa(key)
+            }
+            PUBKEY_ALGO_EDDSA => {
+                let point = ed25519_point(body, public_len)?;
+                let key = <[u8; 32]>::try_from(point)
+                    .ok()
+                    .and_then(|p| ed25519_dalek::VerifyingKey::from_bytes(&p).ok())
+                    .ok_or_else(|| invalid("not an Ed25519 point".to_string()))?;
+                KeyMaterial::Ed25519(key)
+            }
+            other => KeyMaterial::Unsupported(other),
+        };
+
+        Ok(PublicKey {
+            key_id: hex(&fingerprint[12..]),
+            material,
+        })
+    }
+
+    /// Verify the v4 signature packet at the start of `signature` over `data`, whose
+    /// parts are hashed in order as a single document.
+    pub(crate) fn verify(&self, signature: &[u8], data: &[&[u8]]) -> Result<(), String> {
+        let truncated = || "Truncated OpenPGP signature packet".to_string();
+        let (_, body, _) = read_packet(signature)?;
+        let fields = body.get(..6).ok_or_else(truncated)?;
+        if fields[0] != 4 {
+            return Err(format!(
+                "Version {} OpenPGP signatures can't be verified",
+                fields[0]
+            ));
+        }
+        let (public_key_algorithm, hash_algorithm) = (fields[2], fields[3]);
+
+        // The hash covers the data, the hashed part of the packet, and a trailer
+        let hashed_len = 6 + be_uint(&fields[4..6]);
+        let hashed = body.get(..hashed_len).ok_or_else(truncated)?;
+        let unhashed_len = be_uint(body.get(hashed_len..hashed_len + 2).ok_or_else(truncated)?);
+        let pos = hashed_len + 2 + unhashed_len;
+        let left16 = body.get(pos..pos + 2).ok_or_else(truncated)?;
+        let mpis = &body[pos + 2..];
+
+        let trailer = [&[0x04, 0xff][..], &(hashed_len as u32).to_be_bytes()].concat();
+        let parts = [data, &[hashed, &trailer]].concat();
+        let (digest, scheme) = match hash_algorithm {
+            HASH_ALGO_SHA1 => (digest::<Sha1>(&parts), Pkcs1v15Sign::new::<Sha1>()),
+            HASH_ALGO_SHA224 => (digest::<Sha224>(&parts), Pkcs1v15Sign::new::<Sha224>()),
+            HASH_ALGO_SHA256 => (digest::<Sha256>(&parts), Pkcs1v15Sign::new::<Sha256>()),
+            HASH_ALGO_SHA384 => (digest::<Sha384>(&parts), Pkcs1v15Sign::new::<Sha384>()),
+            HASH_ALGO_SHA512 => (digest::<Sha512>(&parts), Pkcs1v15Sign::new::<Sha512>()),
+            other => {
+                return Err(format!(
+                    "{} signatures can't be verified",
+                    hash_algorithm_name(other)
+                ))
+            }
+        };
+        let mismatch = || "signature does not match the signed data".to_string();
+        if digest[..2] != *left16 {
+            return Err(mismatch());
+        }
+
+        match (&self.material, public_key_algorithm) {
+            (KeyMaterial::Rsa(key), PUBKEY_ALGO_RSA | PUBKEY_ALGO_RSA_SIGN) => {
+                let (value, _) = read_mpi(mpis).ok_or_else(truncated)?;
+                // The MPI drops leading zeros, but the signature must be as long as the modulus
+                let size = rsa::traits::PublicKeyParts::size(key);
+                let padding = size.checked_sub(value.len()).ok_or_else(mismatch)?;
+                let signature = [&vec![0; padding][..], value].concat();
+                key.verify(scheme, &digest, &signature)
+                    .map_err(|_| mismatch())
+            }
+            (KeyMaterial::Ed25519(key), PUBKEY_ALGO_EDDSA) => {
+                let (r, rest) = read_mpi(mpis).ok_or_else(truncated)?;
+                let (s, _) = read_mpi(rest).ok_or_else(truncated)?;
+                if r.len() > 32 || s.len() > 32 {
+                    return Err(mismatch());
+                }
+                let mut signature = [0u8; 64];
+                signature[32 - r.len()..32].copy_from_slice(r);
+                signature[64 - s.len()..].copy_from_slice(s);
+                // EdDSA signs the digest itself
+                key.verify(&digest, &ed25519_dalek::Signature::from_bytes(&signature))
+                    .map_err(|_| mismatch())
+            }
+            (KeyMaterial::Unsupported(algorithm), _) => Err(format!(
+                "{} keys can't verify signatures",
+                public_key_algorithm_name(*algorithm)
+            )),
+            _ => Err(format!(
+                "{} signature made with a key of a different algorithm",
+                public_key_algorithm_name(public_key_algorithm)
+            )),
+        }
+    }
+}
+
+fn digest<D: Digest>(parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Split an MPI off the front of `data`, returning its value bytes and the rest.
+fn read_mpi(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let bits = be_uint(data.get(..2)?);
+    let end = 2 + bits.div_ceil(8);
+    Some((data.get(2..end)?, &data[end..]))
+}
+
+// ---------------------------------------------------------------------------
+// Signing
+// ---------------------------------------------------------------------------
+
+/// An Ed25519 OpenPGP key that signs repository metadata.
+pub(crate) struct SigningKey {
+    key: ed25519_dalek::SigningKey,
+    fingerprint: [u8; 20],
+    /// Key creation time in seconds since the Unix epoch.
+    created: u32,
+    /// The transferable public key: key packets with their secret material removed,
+    /// followed by the user IDs and signatures exported with them.
+    public_packets: Vec<u8>,
+}
+
+impl SigningKey {
+    /// Load an ASCII-armored, unprotected secret key.
+    pub(crate) fn from_armored(armored: &str) -> Result<Self, String> {
+        let data = dearmor(armored, "PGP PRIVATE KEY BLOCK")?;
+
+        let mut primary = None;
+        let mut public_packets = Vec::new();
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let (tag, body, len) = read_packet(rest)?;
+            match tag {
+                SECRET_KEY_PACKET_TAG | SECRET_SUBKEY_PACKET_TAG => {
+                    let public_len = public_key_len(body)?;
+                    let public_tag = if tag == SECRET_KEY_PACKET_TAG {
+                        PUBLIC_KEY_PACKET_TAG
+                    } else {
+                        PUBLIC_SUBKEY_PACKET_TAG
+                    };
+                    public_packets.extend(write_packet(public_tag, &body[..public_len]));
+                    if tag == SECRET_KEY_PACKET_TAG && primary.is_none() {
+                        primary = Some((body, public_len));
+                    }
+                }
+                USER_ID_PACKET_TAG | USER_ATTRIBUTE_PACKET_TAG | SIGNATURE_PACKET_TAG => {
+                    public_packets.extend_from_slice(&rest[..len]);
+                }
+                _ => {}
+            }
+            rest = &rest[len..];
+        }
+
+        let (body, public_len) = primary.ok_or("Signing key contains no secret key packet")?;
+        let key = ed25519_secret_key(body, public_len)?;
+        let fingerprint = v4_fingerprint(&body[..public_len]);
+
+        Ok(SigningKey {
+            key,
+            fingerprint,
+            created: u32::from_be_bytes([body[1], body[2], body[3], body[4]]),
+            public_packets,
+        })
+    }
+
+    /// When the key was created; verifiers reject signatures dated before it.
+    pub(crate) fn created(&self) -> u32 {
+        self.created
+    }
+
+    /// The public key, ASCII-armored for `gpgkey=` or `rpm --import`.
+    pub(crate) fn public_key_armored(&self) -> String {
+        armor("PGP PUBLIC KEY BLOCK", &self.public_packets)
+    }
+
+    /// Create an ASCII-armored detached signature over `data`, dated `created`.
+    ///
+    /// gpg refuses signatures older than the key ("Time conflict"), so `created` should
+    /// not be earlier than [`SigningKey::created`].
+    pub(crate) fn sign_detached(&self, data: &[u8], created: u32) -> String {
+        let mut hashed_subpackets = vec![22, SUBPACKET_ISSUER_FINGERPRINT, 4];
+        hashed_subpackets.extend_from_slice(&self.fingerprint);
+        hashed_subpackets.extend_from_slice(&[5, SUBPACKET_CREATION_TIME]);
+        hashed_subpackets.extend_from_slice(&created.to_be_bytes());
+
+        let mut body = vec![
+            4,
+            SIGNATURE_TYPE_BINARY,
+            PUBKEY_ALGO_EDDSA,
+            HASH_ALGO_SHA256,
+        ];
+        body.extend_from_slice(&(hashed_subpackets.len() as u16).to_be_bytes());
+        body.extend_from_slice(&hashed_subpackets);
+
+        // The hash covers the document, the hashed part of the packet, and a trailer
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.update(&body);
+        hasher.update([0x04, 0xff]);
+        hasher.update((body.len() as u32).to_be_bytes());
+        let digest = hasher.finalize();
+
+        // EdDSA signs the digest itself
+        let signature = self.key.sign(&digest).to_bytes();
+
+        let mut unhashed_subpackets = vec![9, SUBPACKET_ISSUER];
+        unhashed_subpackets.extend_from_slice(&self.fingerprint[12..]);
+        body.extend_from_slice(&(unhashed_subpackets.len() as u16).to_be_bytes());
+        body.extend_from_slice(&unhashed_subpackets);
+        body.extend_from_slice(&digest[..2]);
+        body.extend(write_mpi(&signature[..32]));
+        body.extend(write_mpi(&signature[32..]));
+
+        armor("PGP SIGNATURE", &write_packet(SIGNATURE_PACKET_TAG, &body))
+    }
+}
+
+/// The length of the public part of a v4 key packet body.
+fn public_key_len(body: &[u8]) -> Result<usize, String> {
+    let truncated = || "Truncated OpenPGP key packet".to_string();
+    let &version = body.first().ok_or_else(truncated)?;
+    if version != 4 {
+        return Err(format!("Unsupported OpenPGP key version {version}"));
+    }
+    let &algorithm = body.get(5).ok_or_else(truncated)?;
+
+    let mut pos = 6;
+    let skip_mpi = |pos: &mut usize| -> Result<(), String> {
+        let bits = be_uint(body.get(*pos..*pos + 2).ok_or_else(truncated)?);
+        *pos += 2 + bits.div_ceil(8);
+        Ok(())
+    };
+    let skip_sized = |pos: &mut usize| -> Result<(), String> {
+        let &len = body.get(*pos).ok_or_else(truncated)?;
+        *pos += 1 + len as usize;
+        Ok(())
+    };
+
+    match algorithm {
+        // RSA: n, e
+        1..=3 => (0..2).try_for_each(|_| skip_mpi(&mut pos))?,
+        // ElGamal: p, g, y
+        16 => (0..3).try_for_each(|_| skip_mpi(&mut pos))?,
+        // DSA: p, q, g, y
+        17 => (0..4).try_for_each(|_| skip_mpi(&mut pos))?,
+        // ECDH: curve OID, point, KDF parameters
+        18 => {
+            skip_sized(&mut pos)?;
+            skip_mpi(&mut pos)?;
+            skip_sized(&mut pos)?;
+        }
+        // ECDSA, EdDSA: curve OID, point
+        19 | 22 => {
+            skip_sized(&mut pos)?;
+            skip_mpi(&mut pos)?;
+        }
+        other => return Err(format!("Unsupported OpenPGP key algorithm {other}")),
+    }
+
+    if pos > body.len() {
+        return Err(truncated());
+    }
+    Ok(pos)
+}
+
+/// The v4 fingerprint of a key: the SHA-1 of its public key packet body.
+fn v4_fingerprint(public_body: &[u8]) -> [u8; 20] {
+    Sha1::digest(
+        [
+            &[0x99][..],
+            &(public_body.len() as u16).to_be_bytes(),
+            public_body,
+        ]
+        .concat(),
+    )
+    .into()
+}
+
+/// The public point of a v4 EdDSA key packet body, which must be on the Ed25519 curve.
+fn ed25519_point(body: &[u8], public_len: usize) -> Result<&[u8], String> {
+    let oid_len = body[6] as usize;
+    if body[5] != PUBKEY_ALGO_EDDSA || body.get(7..7 + oid_len) != Some(&ED25519_OID[..]) {
+        return Err("Only Ed25519 EdDSA keys are supported".to_string());
+    }
+    // The public point is prefixed with 0x40 (native encoding)
+    body.get(7 + oid_len + 2..public_len)
+        .and_then(|p| p.strip_prefix(&[0x40]))
+        .ok_or_else(|| "Truncated OpenPGP key packet".to_string())
+}
+
+/// Extract the Ed25519 secret key from a v4 EdDSA secret key packet body.
+fn ed25519_secret_key(body: &[u8], public_len: usize) -> Result<ed25519_dalek::SigningKey, String> {
+    let truncated = || "Truncated OpenPGP secret key".to_string();
+    let point = ed25519_point(body, public_len)?;
+
+    let &s2k_usage = body.get(public_len).ok_or_else(truncated)?;
+    if s2k_usage != 0 {
+        return Err("Signing key is protected by a passphrase; export it without one".to_string());
+    }
+    let pos = public_len + 1;
+    let bits = be_uint(body.get(pos..pos + 2).ok_or_else(truncated)?);
+    let scalar = body
+        .get(pos + 2..pos + 2 + bits.div_ceil(8))
+        .filter(|s| s.len() <= 32)
+        .ok_or_else(truncated)?;
+
+    // The MPI drops leading zero bytes of the 32-byte seed
+    let mut seed = [0u8; 32];
+    seed[32 - scalar.len()..].copy_from_slice(scalar);
+    let key = ed25519_dalek::SigningKey::from_bytes(&seed);
+    if key.verifying_key().as_bytes()[..] != point[..] {
+        return Err("Signing key's secret part does not match its public key".to_string());
+    }
+    Ok(key)
+}
+
+/// Encode a packet with an old-format header, as gpg writes them.
+fn write_packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let len = body.len();
+    let mut packet = match len {
+        0..=0xff => vec![0x80 | (tag << 2), len as u8],
+        0x100..=0xffff => {
+            let mut h = vec![0x80 | (tag << 2) | 1];
+            h.extend_from_slice(&(len as u16).to_be_bytes());
+            h
+        }
+        _ => {
+            let mut h = vec![0x80 | (tag << 2) | 2];
+            h.extend_from_slice(&(len as u32).to_be_bytes());
+            h
+        }
+    };
+    packet.extend_from_slice(body);
+    packet
+}
+
+/// Encode a big-endian integer as an MPI: a bit count followed by the minimal bytes.
+fn write_mpi(value: &[u8]) -> Vec<u8> {
+    let zeros = value.iter().take_while(|&&b| b == 0).count();
+    let value = &value[zeros..];
+    let bits = match value.first() {
+        Some(&first) => value.len() * 8 - first.leading_zeros() as usize,
+        None => 0,
+    };
+    let mut mpi = (bits as u16).to_be_bytes().to_vec();
+    mpi.extend_from_slice(value);
+    mpi
+}
+
+// ---------------------------------------------------------------------------
+// ASCII armor
+// ---------------------------------------------------------------------------
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Wrap binary OpenPGP data in ASCII armor with the given block label.
+fn armor(label: &str, data: &[u8]) -> String {
+    let mut out = format!("-----BEGIN {label}-----\n\n");
+    let encoded = base64_encode(data);
+    for line in encoded.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).unwrap_or_default());
+        out.push('\n');
+    }
+    out.push('=');
+    out.push_str(&base64_encode(&crc24(data).to_be_bytes()[1..]));
+    out.push_str(&format!("\n-----END {label}-----\n"));
+    out
+}
+
+/// Decode an ASCII-armored block with the given label, checking its CRC-24 if present.
+fn dearmor(armored: &str, label: &str) -> Result<Vec<u8>, String> {
+    let begin = format!("-----BEGIN {label}-----");
+    let end = format!("-----END {label}-----");
+    let mut lines = armored
+        .lines()
+        .map(str::trim)
+        .skip_while(|l| *l != begin)
+        .skip(1);
+
+    // Armor headers (e.g. "Comment: ...") end at the first blank line
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+    }
+
+    let mut encoded = String::new();
+    let mut checksum = None;
+    let mut terminated = false;
+    for line in lines {
+        if line == end {
+            terminated = true;
+            break;
+        }
+        match line.strip_prefix('=') {
+            Some(crc) => checksum = Some(crc.to_string()),
+            None => encoded.push_str(line),
+        }
+    }
+    if !terminated {
+        return Err(format!("Expected an ASCII-armored {label}"));
+    }
+
+    let data = base64_decode(&encoded).ok_or("Invalid base64 in ASCII armor")?;
+    if let Some(crc) = checksum {
+        if base64_decode(&crc).as_deref() != Some(&crc24(&data).to_be_bytes()[1..]) {
+            return Err("ASCII armor checksum mismatch".to_string());
+        }
+    }
+    Ok(data)
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim_end_matches('=');
+    let mut out = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in encoded.bytes() {
+        let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+        acc = (acc << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(out)
+}
+
+/// The CRC-24 checksum used by ASCII armor (RFC 4880, section 6.1).
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0x00b7_04ce;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= 0x0186_4cfb;
+            }
+        }
+    }
+    crc & 0x00ff_ffff
+}
+
+fn be_uint(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as usize)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Test support
+// ---------------------------------------------------------------------------
+
+/// Builders for synthetic signature packets used by unit tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    /// The binary packet of an armored signature from [`super::SigningKey::sign_detached`].
+    pub(crate) fn signature_packet(armored: &str) -> Vec<u8> {
+        super::dearmor(armored, "PGP SIGNATURE").unwrap()
+    }
+
+    /// The creation time of a signature from [`super::SigningKey::sign_detached`], which
+    /// writes it as the last hashed subpacket.
+    pub(crate) fn signature_created(armored: &str) -> u32 {
+        let packet = signature_packet(armored);
+        let (_, body, _) = super::read_packet(&packet).unwrap();
+        let hashed_end = 6 + super::be_uint(&body[4..6]);
+        let time = &body[hashed_end - 4..hashed_end];
+        assert_eq!(body[hashed_end - 5], super::SUBPACKET_CREATION_TIME);
+        u32::from_be_bytes([time[0], time[1], time[2], time[3]])
+    }
+
+    /// A v4 signature packet (new packet format) naming `key_id` in an unhashed
+    /// issuer subpacket. The signature MPI is a placeholder.
+    pub(crate) fn v4_signature(key_id: [u8; 8], public_key_algorithm: u8, hash: u8) -> Vec<u8> {
+        let hashed = [5, 2, 0x65, 0x00, 0x00, 0x00]; // creation time subpacket
+        let mut unhashed = vec![9, 16];
+        unhashed.extend_from_slice(&key_id);
+
+        let mut body = vec![4, 0x00, public_key_algorithm, hash];
+        body.extend_from_slice(&(hashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(&hashed);
+        body.extend_from_slice(&(unhashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(&unhashed);
+        body.extend_from_slice(&[0xab, 0xcd, 0x00, 0x08, 0xff]);
+
+        let mut packet = vec![0xc2, body.len() as u8];
+        packet.extend_from_slice(&body);
+        packet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::v4_signature;
+    use super::*;
+
+    const KEY_ID: [u8; 8] = [0x19, 0x9e, 0x27, 0x40, 0x05, 0x00, 0x2e, 0x8f];
+
+    #[test]
+    fn v4_issuer_subpacket() {
+        let info = parse_signature(&v4_signature(KEY_ID, 1, 8)).unwrap();
+        assert_eq!(info.key_id, "199e274005002e8f");
+        assert_eq!(info.algorithm(), "RSA/SHA256");
+    }
+
+    #[test]
+    fn v4_issuer_fingerprint_subpacket() {
+        let mut fingerprint = vec![0x11; 12];
+        fingerprint.extend_from_slice(&KEY_ID);
+        let mut hashed = vec![22, 33, 4];
+        hashed.extend_from_slice(&fingerprint);
+
+        let mut body = vec![4, 0x00, 22, 10];
+        body.extend_from_slice(&(hashed.len() as u16).to_be_bytes());
+        body.extend_from_slice(&hashed);
+        body.extend_from_slice(&[0, 0, 0xab, 0xcd]);
+        // Old packet format, 1-byte length
+        let mut packet = vec![0x88, body.len() as u8];
+        packet.extend_from_slice(&body);
+
+        let info = parse_signature(&packet).unwrap();
+        assert_eq!(info.key_id, "199e274005002e8f");
+        assert_eq!(info.algorithm(), "EdDSA/SHA512");
+    }
+
+    #[test]
+    fn v3_signature() {
+        let mut body = vec![3, 5, 0x00, 0, 0, 0, 0];
+        body.extend_from_slice(&KEY_ID);
+        body.extend_from_slice(&[17, 2, 0xab, 0xcd]);
+        // Old packet format, 2-byte length
+        let mut packet = vec![0x89, 0x00, body.len() as u8];
+        packet.extend_from_slice(&body);
+
+        let info = parse_signature(&packet).unwrap();
+        assert_eq!(info.key_id, "199e274005002e8f");
+        assert_eq!(info.algorithm(), "DSA/SHA1");
+    }
+
+    #[test]
+    fn rejects_non_signature_packets() {
+        let err = parse_signature(&[0xc6, 0x01, 0x04]).unwrap_err();
+        assert!(err.contains("packet tag 6"));
+    }
+
+    // -- signing --
+
+    const TEST_KEY: &str = include_str!("../testdata/signing-key.asc");
+    const TEST_PUBLIC_KEY: &str = include_str!("../testdata/signing-key.pub.asc");
+
+    #[test]
+    fn loads_gpg_exported_key() {
+        let key = SigningKey::from_armored(TEST_KEY).unwrap();
+        assert_eq!(hex(&key.fingerprint[12..]), "a593220eb873fe95");
+        assert_eq!(
+            hex(&key.fingerprint),
+            "0979fc951f8942bdbdf3083ea593220eb873fe95"
+        );
+    }
+
+    #[test]
+    fn public_key_matches_gpg_export() {
+        let key = SigningKey::from_armored(TEST_KEY).unwrap();
+        let ours = dearmor(&key.public_key_armored(), "PGP PUBLIC KEY BLOCK").unwrap();
+        let gpg = dearmor(TEST_PUBLIC_KEY, "PGP PUBLIC KEY BLOCK").unwrap();
+        assert_eq!(ours, gpg);
+    }
+
+    #[test]
+    fn detached_signature_verifies() {
+        use ed25519_dalek::Verifier;
+
+        let key = SigningKey::from_armored(TEST_KEY).unwrap();
+        let document = b"<repomd/>\n";
+        assert_eq!(key.created(), 1_792_190_822);
+        let armored = key.sign_detached(document, key.created());
+        assert!(armored.starts_with("-----BEGIN PGP SIGNATURE-----\n\n"));
+        assert_eq!(testing::signature_created(&armored), key.created());
+
+        let packet = dearmor(&armored, "PGP SIGNATURE").unwrap();
+        let info = parse_signature(&packet).unwrap();
+        assert_eq!(info.key_id, "a593220eb873fe95");
+        assert_eq!(info.algorithm(), "EdDSA/SHA256");
+
+        // Recompute the signed digest and check the EdDSA signature against it
+        let (_, body, _) = read_packet(&packet).unwrap();
+        let hashed_len = 6 + be_uint(&body[4..6]);
+        let mut hasher = Sha256::new();
+        hasher.update(document);
+        hasher.update(&body[..hashed_len]);
+        hasher.update([0x04, 0xff]);
+        hasher.update((hashed_len as u32).to_be_bytes());
+        let digest = hasher.finalize();
+
+        let unhashed_len = be_uint(&body[hashed_len..hashed_len + 2]);
+        let mut pos = hashed_len + 2 + unhashed_len;
+        assert_eq!(body[pos..pos + 2], digest[..2]);
+        pos += 2;
+        let mut signature = [0u8; 64];
+        for half in signature.chunks_mut(32) {
+            let bits = be_uint(&body[pos..pos + 2]);
+            let value = &body[pos + 2..pos + 2 + bits.div_ceil(8)];
+            half[32 - value.len()..].copy_from_slice(value);
+            pos += 2 + value.len();
+        }
+        key.key
+            .verifying_key()
+            .verify(&digest, &ed25519_dalek::Signature::from_bytes(&signature))
+            .unwrap();
+    }
+
+    // -- verification --
+
+    const RSA_PUBLIC_KEY: &str = include_str!("../testdata/rsa-release-key.pub.asc");
+    /// A gpg detached signature over `RSA_SIGNED_DOCUMENT` by `RSA_PUBLIC_KEY`.
+    const RSA_SIGNATURE: &[u8] = include_bytes!("../testdata/rsa-release-key.sig");
+    const RSA_SIGNED_DOCUMENT: &[u8] = b"Signed by the release key.\n";
+
+    #[test]
+    fn verifies_gpg_rsa_signature() {
+        let keys = parse_public_keys(RSA_PUBLIC_KEY).unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].key_id, "9b050c5666308c93");
+        assert_eq!(
+            parse_signature(RSA_SIGNATURE).unwrap().algorithm(),
+            "RSA/SHA256"
+        );
+
+        keys[0]
+            .verify(RSA_SIGNATURE, &[RSA_SIGNED_DOCUMENT])
+            .unwrap();
+        // The document may be hashed in pieces
+        let (head, tail) = RSA_SIGNED_DOCUMENT.split_at(6);
+        keys[0].verify(RSA_SIGNATURE, &[head, tail]).unwrap();
+
+        let err = keys[0]
+            .verify(RSA_SIGNATURE, &[b"Signed by someone else.\n"])
+            .unwrap_err();
+        assert!(err.contains("does not match"), "{err}");
+    }
+
+    #[test]
+    fn verifies_ed25519_signature() {
+        let key = SigningKey::from_armored(TEST_KEY).unwrap();
+        let document = b"<repomd/>\n";
+        let packet = dearmor(&key.sign_detached(document, 1_800_000_000), "PGP SIGNATURE").unwrap();
+
+        let keys = parse_public_keys(TEST_PUBLIC_KEY).unwrap();
+        assert_eq!(keys[0].key_id, "a593220eb873fe95");
+        keys[0].verify(&packet, &[document]).unwrap();
+        assert!(keys[0].verify(&packet, &[b"<repomd/>"]).is_err());
+
+        // Signed by the Ed25519 key, checked against the RSA one
+        let rsa = parse_public_keys(RSA_PUBLIC_KEY).unwrap();
+        let err = rsa[0].verify(&packet, &[document]).unwrap_err();
+        assert!(err.contains("different algorithm"), "{err}");
+    }
+
+    #[test]
+    fn loads_concatenated_key_blocks() {
+        let keys = parse_public_keys(&format!("{RSA_PUBLIC_KEY}\n{TEST_PUBLIC_KEY}")).unwrap();
+        let ids: Vec<&str> = keys.iter().map(|k| k.key_id.as_str()).collect();
+        assert!(ids.contains(&"9b050c5666308c93"), "{ids:?}");
+        assert!(ids.contains(&"a593220eb873fe95"), "{ids:?}");
+
+        let err = parse_public_keys("no keys here").err().unwrap();
+        assert!(err.contains("PGP PUBLIC KEY BLOCK"), "{err}");
+    }
+
+    #[test]
+    fn rejects_unsupported_keys() {
+        let err = SigningKey::from_armored("not a key").err().unwrap();
+        assert!(err.contains("ASCII-armored"), "{err}");
+
+        // An RSA public key packet in place of the secret key
+        let mut body = vec![4, 0, 0, 0, 0, 1, 0, 8, 0xc5, 0, 8, 0x01];
+        body.push(0);
+        let armored = armor("PGP PRIVATE KEY BLOCK", &write_packet(5, &body));
+        let err = SigningKey::from_armored(&armored).err().unwrap();
+        assert!(err.contains("Only Ed25519"), "{err}");
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        for len in 0..8 {
+            let data: Vec<u8> = (0..len).map(|i| i * 37).collect();
+            assert_eq!(base64_decode(&base64_encode(&data)).unwrap(), data);
+        }
+        assert_eq!(base64_encode(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn rejects_truncated_packet() {
+        let packet = v4_signature(KEY_ID, 1, 8);
+        let err = parse_signature(&packet[..packet.len() - 3]).unwrap_err();
+        assert!(err.contains("Truncated"));
+    }
+
+    #[test]
+    fn rejects_overflowing_lengths() {
+        // Packet length of u32::MAX
+        let err = parse_signature(&[0xc2, 0xff, 0xff, 0xff, 0xff, 0xff, 4]).unwrap_err();
+        assert!(err.contains("Truncated"), "{err}");
+
+        // A v5 subpacket area claiming u32::MAX bytes
+        let mut packet = vec![0xc2, 10, 5, 0x00, 1, 8, 0xff, 0xff, 0xff, 0xff];
+        packet.extend_from_slice(&[0, 0]);
+        let err = parse_signature(&packet).unwrap_err();
+        assert!(err.contains("Truncated"), "{err}");
+
+        // A subpacket claiming u32::MAX bytes
+        let err = issuer_key_id(&[0xff, 0xff, 0xff, 0xff, 0xff, 16]).unwrap_err();
+        assert!(err.contains("Truncated"), "{err}");
+    }
+}
@@ -0,0 +1,353 @@
+//! Update advisories (errata).
+//!
+//! An advisory is uploaded to the repository as a JSON document named
+//! `<anything>.advisory.json`. It is validated on upload, stored as an artifact
+//! property, and rendered into `repodata/updateinfo.xml`, which is what
+//! `dnf updateinfo` and `dnf upgrade --security` read.
+//!
+//! ```json
+//! {
+//!   "id": "EXAMPLE-2026:0001",
+//!   "type": "security",
+//!   "severity": "Important",
+//!   "title": "openssl security update",
+//!   "issued": "2026-03-01 12:00:00",
+//!   "references": [{ "type": "cve", "id": "CVE-2026-1234" }],
+//!   "packages": ["openssl-1:3.0.7-27.el9.x86_64"]
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Filename suffix that marks an artifact as an advisory rather than a package.
+const ADVISORY_SUFFIX: &str = ".advisory.json";
+
+/// Severities understood by `dnf --advisory-severities`.
+const SEVERITIES: [&str; 4] = ["Critical", "Important", "Moderate", "Low"];
+
+/// Whether an artifact path names an advisory document.
+pub(crate) fn is_advisory_path(path: &str) -> bool {
+    path.to_ascii_lowercase().ends_with(ADVISORY_SUFFIX)
+}
+
+/// The kind of update an advisory announces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AdvisoryType {
+    Security,
+    Bugfix,
+    Enhancement,
+    Newpackage,
+}
+
+impl AdvisoryType {
+    /// The `type` attribute of `<update>`.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            AdvisoryType::Security => "security",
+            AdvisoryType::Bugfix => "bugfix",
+            AdvisoryType::Enhancement => "enhancement",
+            AdvisoryType::Newpackage => "newpackage",
+        }
+    }
+}
+
+/// A link from an advisory to a CVE, bug, or other document.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Reference {
+    /// `cve`, `bugzilla`, `self`, or `other`.
+    #[serde(rename = "type")]
+    pub(crate) kind: String,
+    pub(crate) id: String,
+    #[serde(default)]
+    pub(crate) href: Option<String>,
+    #[serde(default)]
+    pub(crate) title: Option<String>,
+}
+
+/// An update advisory and the packages that fix it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Advisory {
+    pub(crate) id: String,
+    #[serde(rename = "type")]
+    pub(crate) kind: AdvisoryType,
+    #[serde(default)]
+    pub(crate) severity: Option<String>,
+    #[serde(default)]
+    pub(crate) title: Option<String>,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+    /// Publisher, the `from` attribute of `<update>`.
+    #[serde(default)]
+    pub(crate) from: Option<String>,
+    /// `YYYY-MM-DD` or `YYYY-MM-DD HH:MM:SS`.
+    pub(crate) issued: String,
+    #[serde(default)]
+    pub(crate) updated: Option<String>,
+    #[serde(default)]
+    pub(crate) references: Vec<Reference>,
+    /// Fixed packages as `name-[epoch:]version-release.arch`.
+    pub(crate) packages: Vec<String>,
+}
+
+/// A package named by an advisory.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Nevra<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) epoch: u32,
+    pub(crate) version: &'a str,
+    pub(crate) release: &'a str,
+    pub(crate) arch: &'a str,
+}
+
+impl std::fmt::Display for Nevra<'_> {
+    /// `name-epoch:version-release.arch`, always with the epoch.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}:{}-{}.{}",
+            self.name, self.epoch, self.version, self.release, self.arch
+        )
+    }
+}
+
+/// Parse `name-[epoch:]version-release.arch`.
+pub(crate) fn parse_nevra(s: &str) -> Option<Nevra<'_>> {
+    let (rest, arch) = s.rsplit_once('.')?;
+    let (rest, release) = rest.rsplit_once('-')?;
+    let (name, ev) = rest.rsplit_once('-')?;
+    let (epoch, version) = match ev.split_once(':') {
+        Some((e, v)) => (e.parse().ok()?, v),
+        None => (0, ev),
+    };
+    let parts = [name, version, release, arch];
+    if parts
+        .iter()
+        .any(|p| p.is_empty() || p.contains(char::is_whitespace))
+    {
+        return None;
+    }
+    Some(Nevra {
+        name,
+        epoch,
+        version,
+        release,
+        arch,
+    })
+}
+
+/// Parse and validate an uploaded advisory document.
+///
+/// The severity is normalized to the capitalization dnf expects, and CVE references
+/// without an `href` link to their cve.org record.
+pub(crate) fn parse_advisory(data: &[u8]) -> Result<Advisory, String> {
+    let mut advisory: Advisory =
+        serde_json::from_slice(data).map_err(|e| format!("Invalid advisory: {e}"))?;
+
+    if advisory.id.is_empty() || advisory.id.contains(char::is_whitespace) {
+        return Err(format!("Invalid advisory ID '{}'", advisory.id));
+    }
+
+    if let Some(severity) = &advisory.severity {
+        advisory.severity = Some(
+            SEVERITIES
+                .into_iter()
+                .find(|s| s.eq_ignore_ascii_case(severity))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid advisory severity '{severity}': expected one of {}",
+                        SEVERITIES.join(", ")
+                    )
+                })?
+                .to_string(),
+        );
+    }
+
+    for (field, date) in std::iter::once(("issued", &advisory.issued))
+        .chain(advisory.updated.iter().map(|d| ("updated", d)))
+    {
+        if !is_valid_date(date) {
+            return Err(format!(
+                "Invalid advisory {field} date '{date}': expected YYYY-MM-DD or YYYY-MM-DD HH:MM:SS"
+            ));
+        }
+    }
+
+    for reference in &mut advisory.references {
+        match reference.kind.as_str() {
+            "cve" => {
+                if !is_valid_cve(&reference.id) {
+                    return Err(format!("Invalid CVE ID '{}'", reference.id));
+                }
+                if reference.href.is_none() {
+                    reference.href =
+                        Some(format!("https://www.cve.org/CVERecord?id={}", reference.id));
+                }
+            }
+            "bugzilla" | "self" | "other" => {}
+            kind => {
+                return Err(format!(
+                "Invalid advisory reference type '{kind}': expected cve, bugzilla, self, or other"
+            ))
+            }
+        }
+    }
+
+    if advisory.packages.is_empty() {
+        return Err("Advisory must list at least one package".to_string());
+    }
+    if let Some(bad) = advisory.packages.iter().find(|p| parse_nevra(p).is_none()) {
+        return Err(format!(
+            "Invalid advisory package '{bad}': expected name-[epoch:]version-release.arch"
+        ));
+    }
+
+    Ok(advisory)
+}
+
+/// `YYYY-MM-DD`, optionally followed by ` HH:MM:SS`.
+fn is_valid_date(s: &str) -> bool {
+    let b = s.as_bytes();
+    let digits = |range: std::ops::Range<usize>| b[range].iter().all(u8::is_ascii_digit);
+    let date = b.len() >= 10
+        && digits(0..4)
+        && b[4] == b'-'
+        && digits(5..7)
+        && b[7] == b'-'
+        && digits(8..10);
+    match b.len() {
+        10 => date,
+        19 => {
+            date && b[10] == b' '
+                && digits(11..13)
+                && b[13] == b':'
+                && digits(14..16)
+                && b[16] == b':'
+                && digits(17..19)
+        }
+        _ => false,
+    }
+}
+
+/// `CVE-YYYY-NNNN`, with at least four digits in the sequence number.
+fn is_valid_cve(id: &str) -> bool {
+    let Some(rest) = id.strip_prefix("CVE-") else {
+        return false;
+    };
+    let Some((year, number)) = rest.split_once('-') else {
+        return false;
+    };
+    year.len() == 4
+        && number.len() >= 4
+        && year
+            .bytes()
+            .chain(number.bytes())
+            .all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADVISORY: &str = r#"{
+        "id": "EXAMPLE-2026:0001",
+        "type": "security",
+        "severity": "important",
+        "title": "openssl security update",
+        "issued": "2026-03-01 12:00:00",
+        "references": [{ "type": "cve", "id": "CVE-2026-1234" }],
+        "packages": ["openssl-1:3.0.7-27.el9.x86_64", "openssl-libs-3.0.7-27.el9.i686"]
+    }"#;
+
+    fn with(field: &str, value: serde_json::Value) -> Vec<u8> {
+        let mut json: serde_json::Value = serde_json::from_str(ADVISORY).unwrap();
+        json[field] = value;
+        serde_json::to_vec(&json).unwrap()
+    }
+
+    #[test]
+    fn parses_advisory() {
+        let advisory = parse_advisory(ADVISORY.as_bytes()).unwrap();
+        assert_eq!(advisory.id, "EXAMPLE-2026:0001");
+        assert_eq!(advisory.kind, AdvisoryType::Security);
+        assert_eq!(advisory.severity.as_deref(), Some("Important"));
+        assert_eq!(
+            advisory.references[0].href.as_deref(),
+            Some("https://www.cve.org/CVERecord?id=CVE-2026-1234")
+        );
+        assert_eq!(advisory.packages.len(), 2);
+    }
+
+    #[test]
+    fn parses_nevra() {
+        assert_eq!(
+            parse_nevra("python3-numpy-1:1.24.2-4.el9.x86_64"),
+            Some(Nevra {
+                name: "python3-numpy",
+                epoch: 1,
+                version: "1.24.2",
+                release: "4.el9",
+                arch: "x86_64",
+            })
+        );
+        assert_eq!(parse_nevra("bash-5.2.26-1.el9.src").unwrap().epoch, 0);
+        assert_eq!(parse_nevra("bash-5.2.26.x86_64"), None);
+        assert_eq!(parse_nevra("bash-x:5.2-1.noarch"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let cases = [
+            ("id", serde_json::json!("two words"), "Invalid advisory ID"),
+            ("type", serde_json::json!("hotfix"), "Invalid advisory"),
+            (
+                "severity",
+                serde_json::json!("urgent"),
+                "Invalid advisory severity",
+            ),
+            (
+                "issued",
+                serde_json::json!("March 1st"),
+                "Invalid advisory issued date",
+            ),
+            (
+                "updated",
+                serde_json::json!("2026-3-1"),
+                "Invalid advisory updated date",
+            ),
+            (
+                "references",
+                serde_json::json!([{ "type": "cve", "id": "CVE-26-1" }]),
+                "Invalid CVE ID",
+            ),
+            (
+                "references",
+                serde_json::json!([{ "type": "jira", "id": "X-1" }]),
+                "Invalid advisory reference type",
+            ),
+            ("packages", serde_json::json!([]), "at least one package"),
+            (
+                "packages",
+                serde_json::json!(["openssl"]),
+                "Invalid advisory package",
+            ),
+        ];
+        for (field, value, expected) in cases {
+            let err = parse_advisory(&with(field, value)).err().unwrap();
+            assert!(err.contains(expected), "{field}: {err}");
+        }
+    }
+
+    #[test]
+    fn accepts_date_only() {
+        assert!(parse_advisory(&with("issued", serde_json::json!("2026-03-01"))).is_ok());
+    }
+
+    #[test]
+    fn advisory_paths() {
+        assert!(is_advisory_path("errata/EXAMPLE-2026-0001.advisory.json"));
+        assert!(is_advisory_path("EXAMPLE.Advisory.JSON"));
+        assert!(!is_advisory_path("nginx-1.24.0-1.el9.x86_64.rpm"));
+        assert!(!is_advisory_path("advisory.json"));
+    }
+}
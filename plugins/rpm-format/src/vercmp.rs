@@ -0,0 +1,277 @@
+//! RPM version comparison.
+//!
+//! A port of rpm's `rpmvercmp()`: versions are split into alternating runs of
+//! digits and letters, with every other character acting as a separator.
+//! Numeric runs compare numerically and always beat alphabetic runs. A tilde
+//! sorts before anything, even the end of the string (`1.0~rc1 < 1.0`); a caret
+//! sorts after the end of the string but before anything else (`1.0 < 1.0^git1 < 1.0.1`).
+
+use std::cmp::Ordering;
+
+/// An epoch, version, and release, compared the way rpm orders packages.
+pub(crate) struct Evr<'a> {
+    pub(crate) epoch: u32,
+    pub(crate) version: &'a str,
+    pub(crate) release: &'a str,
+}
+
+/// Compare two EVRs: epoch numerically, then version and release with `rpmvercmp`.
+pub(crate) fn compare_evr(a: &Evr<'_>, b: &Evr<'_>) -> Ordering {
+    a.epoch
+        .cmp(&b.epoch)
+        .then_with(|| rpmvercmp(a.version, b.version))
+        .then_with(|| rpmvercmp(a.release, b.release))
+}
+
+/// Compare two version (or release) strings with rpm's `rpmvercmp` semantics.
+pub(crate) fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let one = a.as_bytes();
+    let two = b.as_bytes();
+    let (mut i, mut j) = (0, 0);
+
+    let is_separator = |c: u8| !c.is_ascii_alphanumeric() && c != b'~' && c != b'^';
+
+    while i < one.len() || j < two.len() {
+        while i < one.len() && is_separator(one[i]) {
+            i += 1;
+        }
+        while j < two.len() && is_separator(two[j]) {
+            j += 1;
+        }
+
+        let c1 = one.get(i).copied();
+        let c2 = two.get(j).copied();
+
+        // Tilde sorts before everything else, including the end of the string
+        if c1 == Some(b'~') || c2 == Some(b'~') {
+            if c1 != Some(b'~') {
+                return Ordering::Greater;
+            }
+            if c2 != Some(b'~') {
+                return Ordering::Less;
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        // Caret sorts after the end of the string, but before anything else
+        if c1 == Some(b'^') || c2 == Some(b'^') {
+            if c1.is_none() {
+                return Ordering::Less;
+            }
+            if c2.is_none() {
+                return Ordering::Greater;
+            }
+            if c1 != Some(b'^') {
+                return Ordering::Greater;
+            }
+            if c2 != Some(b'^') {
+                return Ordering::Less;
+            }
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        if c1.is_none() || c2.is_none() {
+            break;
+        }
+
+        // Grab the next run of the same kind from both strings
+        let is_num = one[i].is_ascii_digit();
+        let same_kind = |c: &u8| {
+            if is_num {
+                c.is_ascii_digit()
+            } else {
+                c.is_ascii_alphabetic()
+            }
+        };
+        let end1 = i + one[i..].iter().take_while(|c| same_kind(c)).count();
+        let end2 = j + two[j..].iter().take_while(|c| same_kind(c)).count();
+
+        // A run in `one` can't be empty; an empty run in `two` means the kinds differ,
+        // and numeric runs are newer than alphabetic ones
+        if end2 == j {
+            return if is_num {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+        }
+
+        let mut seg1 = &one[i..end1];
+        let mut seg2 = &two[j..end2];
+
+        if is_num {
+            // Strip leading zeros; the longer number is then the larger one
+            seg1 = trim_leading_zeros(seg1);
+            seg2 = trim_leading_zeros(seg2);
+            match seg1.len().cmp(&seg2.len()) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+        }
+
+        match seg1.cmp(seg2) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        i = end1;
+        j = end2;
+    }
+
+    match (i >= one.len(), j >= two.len()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+fn trim_leading_zeros(s: &[u8]) -> &[u8] {
+    let zeros = s.iter().take_while(|&&c| c == b'0').count();
+    &s[zeros..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vectors from rpm's `tests/rpmvercmp.at`.
+    const VECTORS: &[(&str, &str, i8)] = &[
+        ("1.0", "1.0", 0),
+        ("1.0", "2.0", -1),
+        ("2.0", "1.0", 1),
+        ("2.0.1", "2.0.1", 0),
+        ("2.0", "2.0.1", -1),
+        ("2.0.1", "2.0", 1),
+        ("2.0.1a", "2.0.1a", 0),
+        ("2.0.1a", "2.0.1", 1),
+        ("2.0.1", "2.0.1a", -1),
+        ("5.5p1", "5.5p1", 0),
+        ("5.5p1", "5.5p2", -1),
+        ("5.5p2", "5.5p1", 1),
+        ("5.5p10", "5.5p10", 0),
+        ("5.5p1", "5.5p10", -1),
+        ("5.5p10", "5.5p1", 1),
+        ("10xyz", "10.1xyz", -1),
+        ("10.1xyz", "10xyz", 1),
+        ("xyz10", "xyz10", 0),
+        ("xyz10", "xyz10.1", -1),
+        ("xyz10.1", "xyz10", 1),
+        ("xyz.4", "xyz.4", 0),
+        ("xyz.4", "8", -1),
+        ("8", "xyz.4", 1),
+        ("xyz.4", "2", -1),
+        ("2", "xyz.4", 1),
+        ("5.5p2", "5.6p1", -1),
+        ("5.6p1", "5.5p2", 1),
+        ("5.6p1", "6.5p1", -1),
+        ("6.5p1", "5.6p1", 1),
+        ("6.0.rc1", "6.0", 1),
+        ("6.0", "6.0.rc1", -1),
+        ("10b2", "10a1", 1),
+        ("10a2", "10b2", -1),
+        ("1.0aa", "1.0aa", 0),
+        ("1.0a", "1.0aa", -1),
+        ("1.0aa", "1.0a", 1),
+        ("10.0001", "10.0001", 0),
+        ("10.0001", "10.1", 0),
+        ("10.1", "10.0001", 0),
+        ("10.0001", "10.0039", -1),
+        ("10.0039", "10.0001", 1),
+        ("4.999.9", "5.0", -1),
+        ("5.0", "4.999.9", 1),
+        ("20101121", "20101121", 0),
+        ("20101121", "20101122", -1),
+        ("20101122", "20101121", 1),
+        ("2_0", "2_0", 0),
+        ("2.0", "2_0", 0),
+        ("2_0", "2.0", 0),
+        ("a", "a", 0),
+        ("a+", "a+", 0),
+        ("a+", "a_", 0),
+        ("a_", "a+", 0),
+        ("+a", "+a", 0),
+        ("+a", "_a", 0),
+        ("_a", "+a", 0),
+        ("+_", "+_", 0),
+        ("_+", "+_", 0),
+        ("_+", "_+", 0),
+        ("+", "_", 0),
+        ("_", "+", 0),
+        ("1.0~rc1", "1.0~rc1", 0),
+        ("1.0~rc1", "1.0", -1),
+        ("1.0", "1.0~rc1", 1),
+        ("1.0~rc1", "1.0~rc2", -1),
+        ("1.0~rc2", "1.0~rc1", 1),
+        ("1.0~rc1~git123", "1.0~rc1~git123", 0),
+        ("1.0~rc1~git123", "1.0~rc1", -1),
+        ("1.0~rc1", "1.0~rc1~git123", 1),
+        ("1.0^", "1.0^", 0),
+        ("1.0^", "1.0", 1),
+        ("1.0", "1.0^", -1),
+        ("1.0^git1", "1.0^git1", 0),
+        ("1.0^git1", "1.0", 1),
+        ("1.0", "1.0^git1", -1),
+        ("1.0^git1", "1.0^git2", -1),
+        ("1.0^git2", "1.0^git1", 1),
+        ("1.0^git1", "1.01", -1),
+        ("1.01", "1.0^git1", 1),
+        ("1.0^20160101", "1.0^20160101", 0),
+        ("1.0^20160101", "1.0.1", -1),
+        ("1.0.1", "1.0^20160101", 1),
+        ("1.0^20160101^git1", "1.0^20160101^git1", 0),
+        ("1.0^20160102", "1.0^20160101^git1", 1),
+        ("1.0^20160101^git1", "1.0^20160102", -1),
+        ("1.0~rc1^git1", "1.0~rc1^git1", 0),
+        ("1.0~rc1^git1", "1.0~rc1", 1),
+        ("1.0~rc1", "1.0~rc1^git1", -1),
+        ("1.0^git1~pre", "1.0^git1~pre", 0),
+        ("1.0^git1", "1.0^git1~pre", 1),
+        ("1.0^git1~pre", "1.0^git1", -1),
+    ];
+
+    #[test]
+    fn rpmvercmp_test_vectors() {
+        for &(a, b, expected) in VECTORS {
+            let expected = expected.cmp(&0);
+            assert_eq!(rpmvercmp(a, b), expected, "rpmvercmp({a:?}, {b:?})");
+        }
+    }
+
+    #[test]
+    fn epoch_wins_over_version() {
+        let old = Evr {
+            epoch: 1,
+            version: "1.0",
+            release: "1",
+        };
+        let new = Evr {
+            epoch: 0,
+            version: "9.0",
+            release: "1",
+        };
+        assert_eq!(compare_evr(&old, &new), Ordering::Greater);
+    }
+
+    #[test]
+    fn release_breaks_version_tie() {
+        let a = Evr {
+            epoch: 0,
+            version: "1.0",
+            release: "2.el9",
+        };
+        let b = Evr {
+            epoch: 0,
+            version: "1.0",
+            release: "10.el9",
+        };
+        assert_eq!(compare_evr(&a, &b), Ordering::Less);
+    }
+}